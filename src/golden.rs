@@ -0,0 +1,222 @@
+//! Golden image tests, comparing what panels and layouts draw with
+//! reference images in `tests/golden`.
+//!
+//! A missing reference fails the test, unless it runs with `UPDATE_GOLDEN=1`,
+//! which writes the rendered images as the new references. On a mismatch
+//! the rendered image and a diff, failing pixels in red over a faded copy of
+//! the reference, are written to `target/golden`. Tests drawing anything
+//! need a graphics adapter and are ignored by default, run them with
+//! `cargo test golden -- --ignored`.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::*;
+use image::{Rgba, RgbaImage};
+
+use crate::interface::{render_panel, ContextGlobal, Panel, Window, Workspace};
+use crate::outliner::Outliner;
+use crate::scene::{Mesh, Scene, SharedScene, Transform};
+use crate::viewport::Viewport;
+
+/// Color difference, in CIE76 delta E, up to which pixels count as equal.
+/// Around 2.3 is the smallest difference people notice.
+const MAX_DELTA_E: f64 = 2.3;
+/// Share of pixels which may differ, e.g. along antialiased edges.
+const MAX_FAILING: f64 = 0.001;
+
+fn reference_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+}
+
+fn output_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("target")
+        .join("golden")
+}
+
+/// CIELAB coordinates of an sRGB color, for a D65 white point.
+fn lab(color: Rgba<u8>) -> [f64; 3] {
+    let linear = |c: u8| {
+        let c = c as f64 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let [r, g, b] = [linear(color[0]), linear(color[1]), linear(color[2])];
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f64| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn delta_e(a: Rgba<u8>, b: Rgba<u8>) -> f64 {
+    let (a, b) = (lab(a), lab(b));
+    let d = [a[0] - b[0], a[1] - b[1], a[2] - b[2]];
+    (d[0] * d[0] + d[1] * d[1] + d[2] * d[2]).sqrt()
+}
+
+struct Comparison {
+    failing: usize,
+    diff: RgbaImage,
+}
+
+impl Comparison {
+    fn passed(&self) -> bool {
+        self.failing as f64 <= MAX_FAILING * (self.diff.width() * self.diff.height()) as f64
+    }
+}
+
+fn compare(expected: &RgbaImage, actual: &RgbaImage) -> Result<Comparison> {
+    if expected.dimensions() != actual.dimensions() {
+        bail!(
+            "expected a {:?} image, got {:?}",
+            expected.dimensions(),
+            actual.dimensions()
+        );
+    }
+    let mut failing = 0;
+    let diff = RgbaImage::from_fn(expected.width(), expected.height(), |x, y| {
+        let (a, b) = (*expected.get_pixel(x, y), *actual.get_pixel(x, y));
+        if a[3] != b[3] || delta_e(a, b) > MAX_DELTA_E {
+            failing += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let gray = (a[0] as u32 + a[1] as u32 + a[2] as u32) / 3;
+            let faded = (128 + gray / 2) as u8;
+            Rgba([faded, faded, faded, 255])
+        }
+    });
+    Ok(Comparison { failing, diff })
+}
+
+/// Compares `actual` with the reference image called `name`.
+fn check(name: &str, actual: &RgbaImage) -> Result<()> {
+    let reference = reference_dir().join(format!("{}.png", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(reference_dir())?;
+        actual.save(&reference)?;
+        eprintln!("wrote {}", reference.display());
+        return Ok(());
+    }
+    if !reference.exists() {
+        bail!(
+            "{}: missing {}, run with UPDATE_GOLDEN=1 to create it",
+            name,
+            reference.display()
+        );
+    }
+    let expected = image::open(&reference)
+        .with_context(|| format!("failed to load {}", reference.display()))?
+        .to_rgba8();
+    let comparison = compare(&expected, actual).with_context(|| name.to_string())?;
+    if comparison.passed() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(output_dir())?;
+    let actual_path = output_dir().join(format!("{}.png", name));
+    let diff_path = output_dir().join(format!("{}.diff.png", name));
+    actual.save(&actual_path)?;
+    comparison.diff.save(&diff_path)?;
+    bail!(
+        "{}: {} pixels differ from {}, see {} and {}",
+        name,
+        comparison.failing,
+        reference.display(),
+        actual_path.display(),
+        diff_path.display()
+    )
+}
+
+/// Headless context drawing `scene`.
+fn context(width: u32, height: u32, scene: SharedScene) -> ContextGlobal {
+    let size = winit::dpi::PhysicalSize::new(width, height);
+    pollster::block_on(ContextGlobal::init_headless(size, scene)).unwrap()
+}
+
+/// Two cubes, one of them a child of the other, and an empty node.
+fn sample_scene() -> Scene {
+    let mut scene = Scene::new();
+    let mesh = scene.add_mesh(Mesh::cube(1.0));
+    let cube = scene.add("Cube", None);
+    scene.set_mesh(cube, Some(mesh));
+    let child = scene.add("Small Cube", Some(cube));
+    scene.set_mesh(child, Some(mesh));
+    let mut transform = Transform::IDENTITY;
+    transform.translation.x = 1.5;
+    scene.set_transform(child, transform);
+    scene.add("Empty", None);
+    scene.select(child);
+    scene
+}
+
+fn render(panel: &mut dyn Panel, width: u32, height: u32) -> RgbaImage {
+    let context = context(width, height, sample_scene().shared());
+    panel.init(&context);
+    render_panel(panel, &context).unwrap()
+}
+
+#[test]
+fn test_compare() {
+    let expected = RgbaImage::from_pixel(40, 30, Rgba([40, 120, 200, 255]));
+    let mut actual = expected.clone();
+    // a step of one in each channel is not noticeable
+    actual.put_pixel(3, 4, Rgba([41, 121, 199, 255]));
+    let comparison = compare(&expected, &actual).unwrap();
+    assert_eq!(comparison.failing, 0);
+
+    actual.put_pixel(5, 6, Rgba([200, 120, 40, 255]));
+    let comparison = compare(&expected, &actual).unwrap();
+    assert_eq!(comparison.failing, 1);
+    assert_eq!(comparison.diff.get_pixel(5, 6), &Rgba([255, 0, 0, 255]));
+    // a stray pixel is tolerated, a visible change is not
+    assert!(comparison.passed());
+    for (x, y) in (10..13).flat_map(|x| (10..13).map(move |y| (x, y))) {
+        actual.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+    }
+    assert!(!compare(&expected, &actual).unwrap().passed());
+
+    let smaller = RgbaImage::new(40, 20);
+    assert!(compare(&expected, &smaller).is_err());
+}
+
+#[test]
+#[ignore = "needs a graphics adapter"]
+fn test_outliner() {
+    let image = render(&mut Outliner::default(), 240, 160);
+    check("outliner", &image).unwrap();
+}
+
+#[test]
+#[ignore = "needs a graphics adapter"]
+fn test_viewport() {
+    let image = render(&mut Viewport::default(), 320, 240);
+    check("viewport", &image).unwrap();
+}
+
+#[test]
+#[ignore = "needs a graphics adapter"]
+fn test_layouts() {
+    for workspace in Workspace::presets() {
+        let mut window = Window::new();
+        crate::register_panels(&mut window);
+        window.set_workspace(&workspace, None).unwrap();
+        *window.scene().borrow_mut() = sample_scene();
+        let context = context(640, 400, window.scene());
+        window.init(&context);
+        let image = window.render_image(&context).unwrap();
+        let name = format!("layout_{}", workspace.name.to_lowercase());
+        check(&name, &image).unwrap();
+    }
+}
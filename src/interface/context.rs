@@ -1,19 +1,68 @@
-use wgpu;
+use std::rc::Rc;
+
+use super::font::Font;
+use crate::prim::{Rect, Vec2};
+use crate::scene::SharedScene;
 
 pub struct ContextGlobal {
-    pub surface: wgpu::Surface,
+    /// Missing for headless contexts, which draw into textures of the
+    /// configured size and format instead.
+    pub surface: Option<wgpu::Surface>,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
+    /// Physical pixels per logical pixel of the window.
+    pub scale_factor: f64,
+    pub scene: SharedScene,
+    pub font: Rc<Font>,
 }
 
 pub struct ContextFrame {
     pub view: wgpu::TextureView,
 }
 
+/// What a panel needs to draw itself into the current frame.
 pub struct Context<'a> {
     pub global: &'a ContextGlobal,
-    pub local: ContextFrame,
+    pub local: &'a ContextFrame,
+    /// Area of the panel in surface pixels, origin at the bottom-left
+    /// corner. Panels draw through [`Context::begin_render_pass`] and need
+    /// not know where they are.
+    pub area: Rect,
+}
+
+impl<'a> Context<'a> {
+    /// Size of the panel in pixels.
+    pub fn size(&self) -> Vec2 {
+        self.area.extent()
+    }
+
+    /// Begins a render pass restricted to the area of the panel, where
+    /// normalized device coordinates span the panel rather than the frame.
+    pub fn begin_render_pass<'e>(
+        &self,
+        encoder: &'e mut wgpu::CommandEncoder,
+        descriptor: &wgpu::RenderPassDescriptor<'e, '_>,
+    ) -> wgpu::RenderPass<'e> {
+        let (x, y, width, height) = self.viewport();
+        let mut renderpass = encoder.begin_render_pass(descriptor);
+        renderpass.set_viewport(x, y, width, height, 0.0, 1.0);
+        renderpass.set_scissor_rect(x as u32, y as u32, width as u32, height as u32);
+        renderpass
+    }
+
+    /// Area of the panel as x, y, width, height with the origin moved to
+    /// the top-left corner like wgpu viewports, clamped to the frame.
+    fn viewport(&self) -> (f32, f32, f32, f32) {
+        let config = &self.global.config;
+        let (width, height) = (config.width as f64, config.height as f64);
+        let area = self.area;
+        let x = area.min.x.round().clamp(0.0, width);
+        let y = (height - area.max.y).round().clamp(0.0, height);
+        let right = area.max.x.round().clamp(0.0, width);
+        let bottom = (height - area.min.y).round().clamp(0.0, height);
+        (x as f32, y as f32, (right - x) as f32, (bottom - y) as f32)
+    }
 }
 
 #[repr(C)]
@@ -46,15 +95,51 @@ impl VertexColor {
 
 #[repr(C)]
 #[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
-pub struct VertexUV {
+pub struct VertexText {
+    pub position: [f32; 3],
+    pub uv: [f32; 2],
+    pub color: [f32; 3],
+}
+
+impl VertexText {
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<VertexText>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 5]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+            ],
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct VertexMesh {
     pub position: [f32; 3],
+    pub normal: [f32; 3],
     pub uv: [f32; 2],
+    pub color: [f32; 3],
 }
 
-impl VertexUV {
+impl VertexMesh {
     pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
         wgpu::VertexBufferLayout {
-            array_stride: std::mem::size_of::<VertexUV>() as wgpu::BufferAddress,
+            array_stride: std::mem::size_of::<VertexMesh>() as wgpu::BufferAddress,
             step_mode: wgpu::VertexStepMode::Vertex,
             attributes: &[
                 wgpu::VertexAttribute {
@@ -65,15 +150,25 @@ impl VertexUV {
                 wgpu::VertexAttribute {
                     offset: std::mem::size_of::<[f32; 3]>() as wgpu::BufferAddress,
                     shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as wgpu::BufferAddress,
+                    shader_location: 2,
                     format: wgpu::VertexFormat::Float32x2,
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x3,
+                },
             ],
         }
     }
 }
 
 impl ContextGlobal {
-    pub async fn init_wgpu(window: &winit::window::Window) -> ContextGlobal {
+    pub async fn init_wgpu(window: &winit::window::Window, scene: SharedScene) -> ContextGlobal {
         let size = window.inner_size();
         let instance = wgpu::Instance::new(wgpu::Backends::all());
         let surface = unsafe { instance.create_surface(window) };
@@ -108,10 +203,30 @@ impl ContextGlobal {
         surface.configure(&device, &config);
 
         ContextGlobal {
-            surface,
+            surface: Some(surface),
             device,
             queue,
             config,
+            scale_factor: window.scale_factor(),
+            scene,
+            font: Rc::new(Font::default_font()),
+        }
+    }
+
+    /// Reconfigures the surface for a new window size. Zero sized windows,
+    /// e.g. minimized ones, keep the previous configuration.
+    pub fn resize(&mut self, size: winit::dpi::PhysicalSize<u32>) {
+        if size.width > 0 && size.height > 0 {
+            self.config.width = size.width;
+            self.config.height = size.height;
+            self.reconfigure();
+        }
+    }
+
+    /// Restores the surface after it has been lost or become outdated.
+    pub fn reconfigure(&self) {
+        if let Some(surface) = &self.surface {
+            surface.configure(&self.device, &self.config);
         }
     }
 }
@@ -0,0 +1,303 @@
+use super::event::*;
+use crate::prim::{Pnt2, Rect, Vec2};
+
+/// Routes window input to panels.
+///
+/// All positions passed in are window pixels with the origin at the
+/// bottom-left corner, and `rects` holds the area of each panel in the same
+/// space. The panel under the cursor receives pointer events; pressing a
+/// button captures the pointer for that panel until every button is
+/// released, and gives it keyboard focus. A panel gets `CursorLeft` only
+/// after a `CursorMoved`.
+#[derive(Default)]
+pub struct Dispatcher {
+    cursor: Option<Pnt2>,
+    hover: Option<usize>,
+    /// Panel that got a `CursorMoved` and no `CursorLeft` since.
+    inside: Option<usize>,
+    focus: Option<usize>,
+    capture: Option<usize>,
+    pressed: Vec<MouseButton>,
+}
+
+impl Dispatcher {
+    pub fn new() -> Dispatcher {
+        Dispatcher::default()
+    }
+
+    pub fn capture(&self) -> Option<usize> {
+        self.capture
+    }
+
+    /// Index of the panel under the cursor.
+    pub fn hover(&self) -> Option<usize> {
+        self.hover
+    }
+
+    fn hit(rects: &[Rect], pos: Pnt2) -> Option<usize> {
+        rects.iter().position(|rect| rect.contains(pos))
+    }
+
+    fn local(rects: &[Rect], index: usize, pos: Pnt2) -> Pnt2 {
+        let min = rects[index].min;
+        Pnt2::new(pos.x - min.x, pos.y - min.y)
+    }
+
+    fn target(&self) -> Option<usize> {
+        self.capture.or(self.hover)
+    }
+
+    /// Sends `CursorLeft` to the panel the cursor was inside if that is not
+    /// `panel`, which the caller then sends a `CursorMoved`.
+    fn enter(&mut self, panel: Option<usize>, events: &mut Vec<(usize, Event)>) {
+        if self.inside != panel {
+            if let Some(left) = self.inside {
+                events.push((left, Event::CursorLeft));
+            }
+            self.inside = panel;
+        }
+    }
+
+    pub fn cursor_moved(&mut self, rects: &[Rect], pos: Pnt2) -> Vec<(usize, Event)> {
+        let delta = match self.cursor {
+            Some(last) => pos - last,
+            None => Vec2::new(0.0, 0.0),
+        };
+        self.cursor = Some(pos);
+
+        self.hover = Self::hit(rects, pos);
+        let target = self.target();
+        let mut events = vec![];
+        self.enter(target, &mut events);
+        if let Some(target) = target {
+            events.push((target, Event::CursorMoved { delta }));
+        }
+        events
+    }
+
+    pub fn cursor_left(&mut self) -> Vec<(usize, Event)> {
+        self.cursor = None;
+        self.hover = None;
+        let mut events = vec![];
+        if self.capture.is_none() {
+            self.enter(None, &mut events);
+        }
+        events
+    }
+
+    pub fn mouse_input(
+        &mut self,
+        rects: &[Rect],
+        button: MouseButton,
+        state: ElementState,
+    ) -> Vec<(usize, Event)> {
+        let pos = match self.cursor {
+            Some(pos) => pos,
+            None => return vec![],
+        };
+        let target = match self.target() {
+            Some(target) => target,
+            None => return vec![],
+        };
+
+        match state {
+            ElementState::Pressed => {
+                if !self.pressed.contains(&button) {
+                    self.pressed.push(button);
+                }
+                self.capture = Some(target);
+                self.focus = Some(target);
+            }
+            ElementState::Released => {
+                self.pressed.retain(|&x| x != button);
+                if self.pressed.is_empty() {
+                    self.capture = None;
+                }
+            }
+        }
+
+        let mut events = vec![(
+            target,
+            Event::MouseInput {
+                pos: Self::local(rects, target, pos),
+                button,
+                state,
+            },
+        )];
+        // the panel under the cursor takes over from the released one
+        if self.capture.is_none() && self.inside != self.hover {
+            let hover = self.hover;
+            self.enter(hover, &mut events);
+            if let Some(hover) = hover {
+                let delta = Vec2::new(0.0, 0.0);
+                events.push((hover, Event::CursorMoved { delta }));
+            }
+        }
+        events
+    }
+
+    /// `delta` is measured in lines.
+    pub fn mouse_wheel(&mut self, rects: &[Rect], delta: Vec2) -> Vec<(usize, Event)> {
+        match (self.target(), self.cursor) {
+            (Some(target), Some(pos)) => vec![(
+                target,
+                Event::MouseWheel {
+                    pos: Self::local(rects, target, pos),
+                    delta,
+                },
+            )],
+            _ => vec![],
+        }
+    }
+
+    /// Keyboard events go to the focused panel, or the hovered one if no
+    /// panel has been clicked yet.
+    pub fn keyboard(&mut self, event: Event) -> Vec<(usize, Event)> {
+        match self.focus.or(self.hover) {
+            Some(target) => vec![(target, event)],
+            None => vec![],
+        }
+    }
+
+    pub fn modifiers_changed(
+        &mut self,
+        count: usize,
+        state: ModifiersState,
+    ) -> Vec<(usize, Event)> {
+        (0..count)
+            .map(|i| (i, Event::ModifiersChanged(state)))
+            .collect()
+    }
+
+    /// Forgets panel indices, used when the set of panels changes.
+    pub fn reset(&mut self) {
+        self.hover = None;
+        self.inside = None;
+        self.focus = None;
+        self.capture = None;
+        self.pressed.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns() -> Vec<Rect> {
+        vec![
+            Rect::from_corner(Pnt2::new(0.0, 0.0), Pnt2::new(100.0, 100.0)),
+            Rect::from_corner(Pnt2::new(100.0, 0.0), Pnt2::new(200.0, 100.0)),
+        ]
+    }
+
+    #[test]
+    fn test_hover_local_position() {
+        let rects = columns();
+        let mut dispatcher = Dispatcher::new();
+        let events = dispatcher.cursor_moved(&rects, Pnt2::new(150.0, 20.0));
+        assert!(matches!(events[..], [(1, Event::CursorMoved { .. })]));
+        let events = dispatcher.mouse_wheel(&rects, Vec2::new(0.0, 1.0));
+        match events[..] {
+            [(1, Event::MouseWheel { pos, .. })] => assert_eq!(pos, Pnt2::new(50.0, 20.0)),
+            _ => panic!("unexpected events {:?}", events),
+        }
+
+        let events = dispatcher.cursor_moved(&rects, Pnt2::new(50.0, 20.0));
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[0], (1, Event::CursorLeft)));
+        assert!(matches!(events[1], (0, Event::CursorMoved { .. })));
+    }
+
+    #[test]
+    fn test_capture_during_drag() {
+        let rects = columns();
+        let mut dispatcher = Dispatcher::new();
+        dispatcher.cursor_moved(&rects, Pnt2::new(50.0, 50.0));
+        dispatcher.mouse_input(&rects, MouseButton::Left, ElementState::Pressed);
+        assert_eq!(dispatcher.capture(), Some(0));
+
+        // dragging into the other panel keeps feeding the captured one
+        let events = dispatcher.cursor_moved(&rects, Pnt2::new(150.0, 50.0));
+        assert_eq!(events.len(), 1);
+        match events[0] {
+            (0, Event::CursorMoved { delta }) => assert_eq!(delta.x, 100.0),
+            _ => panic!("unexpected event"),
+        }
+
+        let events = dispatcher.mouse_input(&rects, MouseButton::Left, ElementState::Released);
+        assert_eq!(dispatcher.capture(), None);
+        match events[0] {
+            (0, Event::MouseInput { pos, .. }) => assert_eq!(pos, Pnt2::new(150.0, 50.0)),
+            _ => panic!("unexpected event"),
+        }
+        assert!(matches!(events[1], (0, Event::CursorLeft)));
+        assert!(matches!(events[2], (1, Event::CursorMoved { .. })));
+
+        let events = dispatcher.cursor_moved(&rects, Pnt2::new(160.0, 50.0));
+        assert!(matches!(events[..], [(1, Event::CursorMoved { .. })]));
+    }
+
+    #[test]
+    fn test_leave_follows_move() {
+        let rects = vec![
+            Rect::from_corner(Pnt2::new(0.0, 0.0), Pnt2::new(100.0, 100.0)),
+            Rect::from_corner(Pnt2::new(100.0, 0.0), Pnt2::new(200.0, 100.0)),
+            Rect::from_corner(Pnt2::new(200.0, 0.0), Pnt2::new(300.0, 100.0)),
+        ];
+        let mut dispatcher = Dispatcher::new();
+        let mut inside = [false; 3];
+        let mut check = |events: Vec<(usize, Event)>| {
+            for (panel, event) in events {
+                match event {
+                    Event::CursorMoved { .. } => inside[panel] = true,
+                    Event::CursorLeft => {
+                        assert!(inside[panel], "{} left before moving", panel);
+                        inside[panel] = false;
+                    }
+                    _ => {}
+                }
+            }
+            inside
+        };
+
+        // dragging from the first panel across the middle one to the last
+        check(dispatcher.cursor_moved(&rects, Pnt2::new(50.0, 50.0)));
+        check(dispatcher.mouse_input(&rects, MouseButton::Left, ElementState::Pressed));
+        for x in [150.0, 250.0] {
+            let state = check(dispatcher.cursor_moved(&rects, Pnt2::new(x, 50.0)));
+            assert_eq!(state, [true, false, false]);
+        }
+        let events = dispatcher.mouse_input(&rects, MouseButton::Left, ElementState::Released);
+        assert_eq!(check(events), [false, false, true]);
+        assert_eq!(check(dispatcher.cursor_left()), [false; 3]);
+    }
+
+    #[test]
+    fn test_keyboard_follows_focus() {
+        let rects = columns();
+        let mut dispatcher = Dispatcher::new();
+        assert!(dispatcher.keyboard(Event::Character('a')).is_empty());
+
+        dispatcher.cursor_moved(&rects, Pnt2::new(150.0, 50.0));
+        dispatcher.mouse_input(&rects, MouseButton::Left, ElementState::Pressed);
+        dispatcher.mouse_input(&rects, MouseButton::Left, ElementState::Released);
+        dispatcher.cursor_moved(&rects, Pnt2::new(50.0, 50.0));
+        let events = dispatcher.keyboard(Event::Character('a'));
+        assert!(matches!(events[..], [(1, Event::Character('a'))]));
+    }
+
+    #[test]
+    fn test_outside_every_panel() {
+        let rects = columns();
+        let mut dispatcher = Dispatcher::new();
+        assert!(dispatcher
+            .cursor_moved(&rects, Pnt2::new(300.0, 50.0))
+            .is_empty());
+        assert!(dispatcher
+            .mouse_input(&rects, MouseButton::Left, ElementState::Pressed)
+            .is_empty());
+        assert!(dispatcher
+            .mouse_wheel(&rects, Vec2::new(0.0, 1.0))
+            .is_empty());
+    }
+}
@@ -1,11 +1,23 @@
-use super::context::{VertexColor, VertexUV};
-use crate::prim::{Rect, RGB};
+use super::context::{VertexColor, VertexText};
+use crate::prim::{Pnt2, Rect, Vec2, RGB};
+
+/// Line of text waiting to be laid out by a `TextRenderer`.
+#[derive(Clone, Debug)]
+pub struct TextRun {
+    /// Start of the baseline.
+    pub pos: Pnt2,
+    pub text: String,
+    /// Font size in pixels.
+    pub size: f64,
+    pub color: RGB,
+}
 
 pub struct DrawBuffer {
     pub vertices_pure: Vec<VertexColor>,
     pub indices_pure: Vec<u16>,
-    pub vertices_uv: Vec<VertexUV>,
-    pub indices_uv: Vec<u16>,
+    pub texts: Vec<TextRun>,
+    pub vertices_text: Vec<VertexText>,
+    pub indices_text: Vec<u16>,
 }
 
 impl DrawBuffer {
@@ -13,8 +25,9 @@ impl DrawBuffer {
         DrawBuffer {
             vertices_pure: vec![],
             indices_pure: vec![],
-            vertices_uv: vec![],
-            indices_uv: vec![],
+            texts: vec![],
+            vertices_text: vec![],
+            indices_text: vec![],
         }
     }
 
@@ -31,22 +44,54 @@ impl DrawBuffer {
             .chain(other.indices_pure.iter().map(|x| x + offset))
             .collect();
 
-        let offset = self.vertices_uv.len() as u16;
-        let vertices_uv = [self.vertices_uv.as_slice(), &other.vertices_uv.as_slice()].concat();
-        let indices_uv = self
-            .indices_uv
+        let offset = self.vertices_text.len() as u16;
+        let vertices_text = [
+            self.vertices_text.as_slice(),
+            other.vertices_text.as_slice(),
+        ]
+        .concat();
+        let indices_text = self
+            .indices_text
             .into_iter()
-            .chain(other.indices_uv.iter().map(|x| x + offset))
+            .chain(other.indices_text.iter().map(|x| x + offset))
             .collect();
+        let texts = self.texts.into_iter().chain(other.texts).collect();
 
         DrawBuffer {
             vertices_pure,
             indices_pure,
-            vertices_uv,
-            indices_uv,
+            texts,
+            vertices_text,
+            indices_text,
         }
     }
 
+    /// Maps every vertex position from the `from` area onto `to`, e.g. from
+    /// panel pixels to its area on screen.
+    pub fn remap(&mut self, from: Rect, to: Rect) {
+        let scale = to.extent() / from.extent();
+        let map = |position: &mut [f32; 3]| {
+            position[0] = (to.min.x + (position[0] as f64 - from.min.x) * scale.x) as f32;
+            position[1] = (to.min.y + (position[1] as f64 - from.min.y) * scale.y) as f32;
+        };
+        self.vertices_pure
+            .iter_mut()
+            .for_each(|v| map(&mut v.position));
+        self.vertices_text
+            .iter_mut()
+            .for_each(|v| map(&mut v.position));
+    }
+
+    /// Maps vertices given in pixels of an area of `size`, origin at the
+    /// bottom-left corner, to the normalized device coordinates spanning
+    /// it.
+    pub fn remap_to_ndc(&mut self, size: Vec2) {
+        self.remap(
+            Rect::from_corner(Pnt2::ZERO, Pnt2::new(size.x, size.y)),
+            Rect::from_corner(Pnt2::new(-1.0, -1.0), Pnt2::new(1.0, 1.0)),
+        );
+    }
+
     pub fn rect(&mut self, rect: Rect, color: RGB) {
         let color = [color.r as f32, color.g as f32, color.b as f32];
         let base = self.vertices_pure.len() as u16;
@@ -76,31 +121,32 @@ impl DrawBuffer {
         ]);
     }
 
-    pub fn rect_uv(&mut self, rect: Rect) {
-        let base = self.vertices_uv.len() as u16;
-        self.vertices_uv.push(VertexUV {
-            position: [rect.min.x as f32, rect.min.y as f32, 0.0],
-            uv: [0.0, 1.0],
-        });
-        self.vertices_uv.push(VertexUV {
-            position: [rect.max.x as f32, rect.min.y as f32, 0.0],
-            uv: [1.0, 1.0],
-        });
-        self.vertices_uv.push(VertexUV {
-            position: [rect.min.x as f32, rect.max.y as f32, 0.0],
-            uv: [0.0, 0.0],
-        });
-        self.vertices_uv.push(VertexUV {
-            position: [rect.max.x as f32, rect.max.y as f32, 0.0],
-            uv: [1.0, 0.0],
+    /// Solid triangle, the corners may be given in either winding order.
+    pub fn triangle(&mut self, a: Pnt2, b: Pnt2, c: Pnt2, color: RGB) {
+        let color = [color.r as f32, color.g as f32, color.b as f32];
+        let (b, c) = if (b - a).cross(c - a) < 0.0 {
+            (c, b)
+        } else {
+            (b, c)
+        };
+        let base = self.vertices_pure.len() as u16;
+        for p in [a, b, c] {
+            self.vertices_pure.push(VertexColor {
+                position: [p.x as f32, p.y as f32, 0.0],
+                color,
+            });
+        }
+        self.indices_pure.extend([base, base + 1, base + 2]);
+    }
+
+    /// Queues a line of text starting at `pos` on its baseline. Text is laid
+    /// out in pixels, so call `TextRenderer::layout` before remapping.
+    pub fn text(&mut self, pos: Pnt2, text: &str, size: f64, color: RGB) {
+        self.texts.push(TextRun {
+            pos,
+            text: text.to_string(),
+            size,
+            color,
         });
-        self.indices_uv.extend(vec![
-            base + 0,
-            base + 1,
-            base + 2,
-            base + 2,
-            base + 1,
-            base + 3,
-        ]);
     }
 }
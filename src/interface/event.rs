@@ -0,0 +1,26 @@
+pub use winit::event::{ElementState, KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode};
+
+use crate::prim::{Pnt2, Vec2};
+
+/// Input delivered to a panel. Positions are panel-local pixels with the
+/// origin at the bottom-left corner of the panel and y pointing up.
+#[derive(Clone, Copy, Debug)]
+pub enum Event {
+    CursorMoved {
+        delta: Vec2,
+    },
+    CursorLeft,
+    MouseInput {
+        pos: Pnt2,
+        button: MouseButton,
+        state: ElementState,
+    },
+    /// Scroll amount in lines, positive y scrolls up.
+    MouseWheel {
+        pos: Pnt2,
+        delta: Vec2,
+    },
+    Keyboard(KeyboardInput),
+    Character(char),
+    ModifiersChanged(ModifiersState),
+}
@@ -0,0 +1,317 @@
+use std::collections::HashMap;
+
+use ab_glyph::{Font as _, FontVec, GlyphId, PxScale, ScaleFont};
+use anyhow::*;
+
+use crate::prim::{Pnt2, Rect, Vec2};
+
+pub struct Font {
+    font: FontVec,
+}
+
+/// Glyph of a shaped run, `x` is the pen position relative to the start of
+/// the run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ShapedGlyph {
+    pub id: GlyphId,
+    pub x: f64,
+}
+
+impl Font {
+    /// Loads a TrueType or OpenType font.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Font> {
+        let font = FontVec::try_from_vec(bytes.to_vec()).context("invalid font data")?;
+        Ok(Font { font })
+    }
+
+    /// The font bundled with the interface.
+    pub fn default_font() -> Font {
+        Font::from_bytes(include_bytes!("DejaVuSans.ttf")).unwrap()
+    }
+
+    /// Places glyphs along a single line, applying kerning. Line breaks are
+    /// not handled.
+    pub fn shape(&self, text: &str, size: f64) -> Vec<ShapedGlyph> {
+        let font = self.font.as_scaled(PxScale::from(size as f32));
+        let mut result = Vec::with_capacity(text.len());
+        let mut x = 0.0;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                x += font.kern(previous, id) as f64;
+            }
+            result.push(ShapedGlyph { id, x });
+            x += font.h_advance(id) as f64;
+            previous = Some(id);
+        }
+        result
+    }
+
+    /// Width of the text and height of a line at the given pixel size.
+    pub fn measure(&self, text: &str, size: f64) -> Vec2 {
+        let font = self.font.as_scaled(PxScale::from(size as f32));
+        let width = match (self.shape(text, size).last(), text.chars().last()) {
+            (Some(glyph), Some(c)) => glyph.x + font.h_advance(font.glyph_id(c)) as f64,
+            _ => 0.0,
+        };
+        Vec2::new(width, font.height() as f64)
+    }
+
+    /// Distance from the baseline to the top of the tallest glyphs.
+    pub fn ascent(&self, size: f64) -> f64 {
+        self.font.as_scaled(PxScale::from(size as f32)).ascent() as f64
+    }
+
+    /// Distance from the baseline to the bottom of the lowest glyphs,
+    /// negative below the baseline.
+    pub fn descent(&self, size: f64) -> f64 {
+        self.font.as_scaled(PxScale::from(size as f32)).descent() as f64
+    }
+
+    /// Height of the baseline that centers a line vertically on `y`, with y
+    /// pointing up.
+    pub fn centered_baseline(&self, y: f64, size: f64) -> f64 {
+        y - (self.ascent(size) + self.descent(size)) / 2.0
+    }
+}
+
+/// Rasterized glyph stored in the atlas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AtlasGlyph {
+    /// Pixel area in the atlas image, y pointing down.
+    pub region: Rect,
+    /// Offset of the bottom-left corner from the pen position on the
+    /// baseline, y pointing up.
+    pub offset: Vec2,
+}
+
+/// Result of looking a glyph up in the atlas.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GlyphSlot {
+    Glyph(AtlasGlyph),
+    /// The glyph has no outline, like a space, and nothing to draw.
+    Empty,
+    /// The glyph does not fit even into the empty atlas.
+    TooLarge,
+}
+
+/// Outcome of drawing a glyph into the free space of the atlas.
+enum Raster {
+    Placed(AtlasGlyph),
+    Empty,
+    Full,
+}
+
+/// CPU side glyph atlas packed in shelves. Glyphs are rasterized on first
+/// use; when the atlas is full it is cleared and refilled.
+pub struct GlyphAtlas {
+    pub image: image::RgbaImage,
+    /// Keyed by the bits of the pixel size as given to the font.
+    glyphs: HashMap<(GlyphId, u32), GlyphSlot>,
+    cursor: Pnt2,
+    shelf_height: u32,
+    dirty: bool,
+    generation: u32,
+}
+
+impl GlyphAtlas {
+    /// Empty pixels kept around every glyph to avoid sampling neighbours.
+    const MARGIN: u32 = 1;
+
+    pub fn new(size: u32) -> GlyphAtlas {
+        GlyphAtlas {
+            image: image::RgbaImage::new(size, size),
+            glyphs: HashMap::new(),
+            cursor: Pnt2::ZERO,
+            shelf_height: 0,
+            dirty: true,
+            generation: 0,
+        }
+    }
+
+    pub fn size(&self) -> u32 {
+        self.image.width()
+    }
+
+    /// Returns whether the image changed since the last call.
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::replace(&mut self.dirty, false)
+    }
+
+    /// Counts how often the atlas was cleared, which invalidates the
+    /// regions of all glyphs returned before.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    fn clear(&mut self) {
+        self.image
+            .pixels_mut()
+            .for_each(|p| *p = image::Rgba([0; 4]));
+        self.glyphs.clear();
+        self.cursor = Pnt2::ZERO;
+        self.shelf_height = 0;
+        self.dirty = true;
+        self.generation += 1;
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cursor == Pnt2::ZERO && self.shelf_height == 0
+    }
+
+    /// Returns the glyph at the given pixel size, the same [`Font::shape`]
+    /// was given, rasterizing it if needed. A glyph that no longer fits
+    /// clears the atlas and starts it over.
+    pub fn glyph(&mut self, font: &Font, id: GlyphId, size: f64) -> GlyphSlot {
+        let size = size as f32;
+        let key = (id, size.to_bits());
+        if let Some(slot) = self.glyphs.get(&key) {
+            return *slot;
+        }
+        let mut raster = self.rasterize(font, id, size);
+        if let Raster::Full = raster {
+            if !self.is_empty() {
+                self.clear();
+                raster = self.rasterize(font, id, size);
+            }
+        }
+        let slot = match raster {
+            Raster::Placed(glyph) => GlyphSlot::Glyph(glyph),
+            Raster::Empty => GlyphSlot::Empty,
+            Raster::Full => GlyphSlot::TooLarge,
+        };
+        self.glyphs.insert(key, slot);
+        slot
+    }
+
+    fn rasterize(&mut self, font: &Font, id: GlyphId, size: f32) -> Raster {
+        let outlined = match font
+            .font
+            .outline_glyph(id.with_scale_and_position(size, ab_glyph::point(0.0, 0.0)))
+        {
+            Some(outlined) => outlined,
+            None => return Raster::Empty,
+        };
+        let bounds = outlined.px_bounds();
+        let width = bounds.width() as u32;
+        let height = bounds.height() as u32;
+
+        let atlas = self.size();
+        let (mut x, mut y) = (self.cursor.x as u32, self.cursor.y as u32);
+        if x + width + Self::MARGIN > atlas {
+            x = 0;
+            y += self.shelf_height;
+            self.shelf_height = 0;
+        }
+        if y + height + Self::MARGIN > atlas {
+            return Raster::Full;
+        }
+        let (x0, y0) = (x + Self::MARGIN, y + Self::MARGIN);
+        outlined.draw(|gx, gy, coverage| {
+            let alpha = (coverage.clamp(0.0, 1.0) * 255.0) as u8;
+            self.image
+                .put_pixel(x0 + gx, y0 + gy, image::Rgba([255, 255, 255, alpha]));
+        });
+        self.cursor = Pnt2::new((x0 + width) as f64, y as f64);
+        self.shelf_height = self.shelf_height.max(height + Self::MARGIN);
+        self.dirty = true;
+
+        Raster::Placed(AtlasGlyph {
+            region: Rect::from_corner(
+                Pnt2::new(x0 as f64, y0 as f64),
+                Pnt2::new((x0 + width) as f64, (y0 + height) as f64),
+            ),
+            offset: Vec2::new(bounds.min.x as f64, -bounds.max.y as f64),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placed(slot: GlyphSlot) -> AtlasGlyph {
+        match slot {
+            GlyphSlot::Glyph(glyph) => glyph,
+            slot => panic!("expected a glyph, got {:?}", slot),
+        }
+    }
+
+    #[test]
+    fn test_measure() {
+        let font = Font::default_font();
+        let short = font.measure("ab", 16.0);
+        let long = font.measure("abab", 16.0);
+        assert!(short.x > 0.0);
+        assert!((long.x - 2.0 * short.x).abs() < 2.0);
+        assert_eq!(font.measure("", 16.0).x, 0.0);
+        assert!((font.measure("ab", 32.0).x - 2.0 * short.x).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_centered_baseline() {
+        let font = Font::default_font();
+        let baseline = font.centered_baseline(100.0, 13.0);
+        let (top, bottom) = (baseline + font.ascent(13.0), baseline + font.descent(13.0));
+        assert!(bottom < baseline && baseline < 100.0 && 100.0 < top);
+        assert!(((top + bottom) / 2.0 - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_shape_advances() {
+        let font = Font::default_font();
+        let glyphs = font.shape("AVA", 20.0);
+        assert_eq!(glyphs.len(), 3);
+        assert_eq!(glyphs[0].x, 0.0);
+        assert!(glyphs[1].x < glyphs[2].x);
+    }
+
+    #[test]
+    fn test_atlas_caches_and_refills() {
+        let font = Font::default_font();
+        let mut atlas = GlyphAtlas::new(64);
+        let a = font.shape("a", 20.0)[0].id;
+        let glyph = placed(atlas.glyph(&font, a, 20.0));
+        assert!(atlas.take_dirty());
+        assert_eq!(atlas.glyph(&font, a, 20.0), GlyphSlot::Glyph(glyph));
+        assert!(!atlas.take_dirty());
+
+        // many large glyphs overflow the small atlas and force a refill
+        let generation = atlas.generation();
+        for c in "abcdefghijklmnopqrstuvwxyz".chars() {
+            let id = font.shape(&c.to_string(), 30.0)[0].id;
+            let glyph = placed(atlas.glyph(&font, id, 30.0));
+            assert!(glyph.region.max.x <= 64.0 && glyph.region.max.y <= 64.0);
+        }
+        assert!(atlas.generation() > generation);
+        let huge = font.shape("W", 200.0)[0].id;
+        assert_eq!(atlas.glyph(&font, huge, 200.0), GlyphSlot::TooLarge);
+    }
+
+    #[test]
+    fn test_atlas_empty_glyphs() {
+        let font = Font::default_font();
+        let mut atlas = GlyphAtlas::new(64);
+        let a = font.shape("a", 20.0)[0].id;
+        let glyph = placed(atlas.glyph(&font, a, 20.0));
+
+        // a space has nothing to draw and leaves the atlas as it is
+        let space = font.shape(" ", 20.0)[0].id;
+        assert_eq!(atlas.glyph(&font, space, 20.0), GlyphSlot::Empty);
+        assert_eq!(atlas.generation(), 0);
+        assert_eq!(atlas.glyph(&font, a, 20.0), GlyphSlot::Glyph(glyph));
+    }
+
+    #[test]
+    fn test_atlas_keys_exact_sizes() {
+        let font = Font::default_font();
+        let mut atlas = GlyphAtlas::new(256);
+        let a = font.shape("a", 20.0)[0].id;
+        let glyph = placed(atlas.glyph(&font, a, 20.0));
+        // sizes rounding to the same pixel count are rasterized separately
+        let larger = placed(atlas.glyph(&font, a, 20.4));
+        assert_ne!(larger.region, glyph.region);
+        assert_eq!(atlas.glyph(&font, a, 20.0), GlyphSlot::Glyph(glyph));
+    }
+}
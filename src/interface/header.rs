@@ -0,0 +1,393 @@
+use wgpu::util::DeviceExt;
+
+use super::context::*;
+use super::draw::DrawBuffer;
+use super::layout::LeafId;
+use super::registry::Registry;
+use super::text::TextRenderer;
+use crate::prim::{Pnt2, Rect, Vec2, RGB};
+
+/// Metrics in pixels.
+pub const HEIGHT: f64 = 24.0;
+const BUTTON_WIDTH: f64 = 120.0;
+const MENU_WIDTH: f64 = 140.0;
+const ROW_HEIGHT: f64 = 22.0;
+const PADDING: f64 = 6.0;
+const ARROW_SIZE: f64 = 8.0;
+const FONT_SIZE: f64 = 13.0;
+
+const BACKGROUND: RGB = RGB::new(0.22, 0.22, 0.22);
+const BUTTON: RGB = RGB::new(0.3, 0.3, 0.3);
+const BUTTON_OPEN: RGB = RGB::new(0.38, 0.38, 0.38);
+const MENU: RGB = RGB::new(0.12, 0.12, 0.12);
+const ENTRY_HOVERED: RGB = RGB::new(0.28, 0.4, 0.6);
+const ARROW: RGB = RGB::new(0.7, 0.7, 0.7);
+const LABEL: RGB = RGB::new(0.85, 0.85, 0.85);
+const LABEL_CURRENT: RGB = RGB::new(0.9, 0.6, 0.3);
+
+/// Strip along the top of a layout area holding its header.
+pub fn strip(area: Rect) -> Rect {
+    Rect::from_corner(
+        Pnt2::new(area.min.x, (area.max.y - HEIGHT).max(area.min.y)),
+        area.max,
+    )
+}
+
+/// Part of a layout area left to its panel, below the header.
+pub fn content(area: Rect) -> Rect {
+    Rect::from_corner(area.min, Pnt2::new(area.max.x, strip(area).min.y))
+}
+
+/// Button opening the panel type menu, at the left of the header.
+fn button(area: Rect) -> Rect {
+    let strip = strip(area);
+    Rect::from_corner(
+        strip.min,
+        Pnt2::new((strip.min.x + BUTTON_WIDTH).min(strip.max.x), strip.max.y),
+    )
+}
+
+/// Entry `index` of the menu hanging below the button of `area`.
+fn entry(area: Rect, index: usize) -> Rect {
+    let top = strip(area).min.y - index as f64 * ROW_HEIGHT;
+    Rect::from_corner(
+        Pnt2::new(area.min.x, top - ROW_HEIGHT),
+        Pnt2::new(area.min.x + MENU_WIDTH, top),
+    )
+}
+
+/// Open panel type menu.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Menu {
+    leaf: LeafId,
+    /// Entry under the cursor.
+    hovered: Option<usize>,
+}
+
+/// Outcome of a click handed to [`Header::click`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Click {
+    /// No header under the cursor, the click belongs to the panels.
+    Ignored,
+    Used,
+    /// The registered panel type at this index was picked for the area of
+    /// the leaf.
+    Select(LeafId, usize),
+}
+
+/// Headers of all layout areas, each with a button to pick the type of
+/// panel in the area.
+///
+/// Positions and areas are window pixels with the origin at the
+/// bottom-left corner, `areas` holds each leaf with its whole area including
+/// the header.
+#[derive(Default)]
+pub struct Header {
+    menu: Option<Menu>,
+    pipeline: Option<wgpu::RenderPipeline>,
+    text: Option<TextRenderer>,
+}
+
+impl Header {
+    pub fn is_open(&self) -> bool {
+        self.menu.is_some()
+    }
+
+    pub fn close(&mut self) {
+        self.menu = None;
+    }
+
+    /// Index of the menu entry at `pos`, `count` being the number of panel
+    /// types.
+    fn entry_at(area: Rect, count: usize, pos: Pnt2) -> Option<usize> {
+        (0..count).find(|&index| entry(area, index).contains(pos))
+    }
+
+    fn menu_area(&self, areas: &[(LeafId, Rect)]) -> Option<Rect> {
+        let menu = self.menu?;
+        areas
+            .iter()
+            .find(|(leaf, _)| *leaf == menu.leaf)
+            .map(|(_, area)| *area)
+    }
+
+    pub fn hover(&mut self, areas: &[(LeafId, Rect)], count: usize, pos: Pnt2) {
+        let area = self.menu_area(areas);
+        if let (Some(menu), Some(area)) = (&mut self.menu, area) {
+            menu.hovered = Self::entry_at(area, count, pos);
+        }
+    }
+
+    /// Handles a left click. An open menu takes every click, anything but
+    /// an entry closes it.
+    pub fn click(&mut self, areas: &[(LeafId, Rect)], count: usize, pos: Pnt2) -> Click {
+        if let Some(menu) = self.menu.take() {
+            let area = areas.iter().find(|(leaf, _)| *leaf == menu.leaf);
+            return match area.and_then(|(_, area)| Self::entry_at(*area, count, pos)) {
+                Some(index) => Click::Select(menu.leaf, index),
+                None => Click::Used,
+            };
+        }
+        match areas.iter().find(|(_, area)| strip(*area).contains(pos)) {
+            Some(&(leaf, area)) => {
+                if button(area).contains(pos) {
+                    self.menu = Some(Menu {
+                        leaf,
+                        hovered: None,
+                    });
+                }
+                Click::Used
+            }
+            None => Click::Ignored,
+        }
+    }
+
+    pub fn init(&mut self, context: &ContextGlobal) {
+        let device = &context.device;
+        let config = &context.config;
+
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Pure Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("pure.wgsl").into()),
+        });
+
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Header Render Pipeline Layout"),
+                bind_group_layouts: &[],
+                push_constant_ranges: &[],
+            });
+        self.pipeline = Some(
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("Header Render Pipeline"),
+                layout: Some(&render_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_main",
+                    buffers: &[VertexColor::desc()],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_main",
+                    targets: &[wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::TriangleList,
+                    strip_index_format: None,
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: Some(wgpu::Face::Back),
+                    polygon_mode: wgpu::PolygonMode::Fill,
+                    unclipped_depth: false,
+                    conservative: false,
+                },
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            }),
+        );
+        self.text = Some(TextRenderer::new(context));
+    }
+
+    /// Queues an icon and a name vertically centered in `rect`.
+    fn label(&self, buffer: &mut DrawBuffer, rect: Rect, icon: char, name: &str, color: RGB) {
+        let font = self.text.as_ref().unwrap().font();
+        let baseline = Pnt2::new(
+            rect.min.x + PADDING,
+            font.centered_baseline(rect.center().y, FONT_SIZE),
+        );
+        buffer.text(baseline, &format!("{} {}", icon, name), FONT_SIZE, color);
+    }
+
+    /// `kinds` holds the [`Panel::kind`](super::Panel::kind) of the panel
+    /// in each area.
+    fn draw(
+        &self,
+        areas: &[(LeafId, Rect)],
+        kinds: &[&str],
+        registry: &Registry,
+        buffer: &mut DrawBuffer,
+    ) {
+        for (&(leaf, area), kind) in areas.iter().zip(kinds) {
+            buffer.rect(strip(area), BACKGROUND);
+            let button = button(area);
+            let open = self.menu.is_some_and(|x| x.leaf == leaf);
+            buffer.rect(button, if open { BUTTON_OPEN } else { BUTTON });
+            match registry.get(kind) {
+                Some(panel_type) => {
+                    self.label(buffer, button, panel_type.icon, panel_type.name, LABEL)
+                }
+                None => self.label(buffer, button, '?', kind, LABEL),
+            }
+            let center = Pnt2::new(button.max.x - PADDING - ARROW_SIZE / 2.0, button.center().y);
+            let h = ARROW_SIZE / 2.0;
+            buffer.triangle(
+                center + Vec2::new(-h, h / 2.0),
+                center + Vec2::new(h, h / 2.0),
+                center + Vec2::new(0.0, -h),
+                ARROW,
+            );
+        }
+
+        // the menu goes last to cover the areas below
+        let menu = match self.menu {
+            Some(menu) => menu,
+            None => return,
+        };
+        let (area, kind) = match areas.iter().zip(kinds).find(|((x, _), _)| *x == menu.leaf) {
+            Some(((_, area), kind)) => (*area, *kind),
+            None => return,
+        };
+        for (index, panel_type) in registry.types().iter().enumerate() {
+            let rect = entry(area, index);
+            let background = if menu.hovered == Some(index) {
+                ENTRY_HOVERED
+            } else {
+                MENU
+            };
+            buffer.rect(rect, background);
+            let color = if panel_type.kind == kind {
+                LABEL_CURRENT
+            } else {
+                LABEL
+            };
+            self.label(buffer, rect, panel_type.icon, panel_type.name, color);
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        areas: &[(LeafId, Rect)],
+        kinds: &[&str],
+        registry: &Registry,
+        context: &Context,
+    ) -> wgpu::CommandBuffer {
+        let mut buffer = DrawBuffer::new();
+        self.draw(areas, kinds, registry, &mut buffer);
+        let text = self.text.as_mut().unwrap();
+        text.layout(&mut buffer);
+        text.prepare(&context.global.queue);
+        buffer.remap_to_ndc(context.size());
+
+        let device = &context.global.device;
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Header Render Encoder"),
+        });
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Header Vertex Buffer"),
+            contents: bytemuck::cast_slice(&buffer.vertices_pure),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Header Index Buffer"),
+            contents: bytemuck::cast_slice(&buffer.indices_pure),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        let text_buffers = (!buffer.indices_text.is_empty()).then(|| {
+            let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Header Text Vertex Buffer"),
+                contents: bytemuck::cast_slice(&buffer.vertices_text),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+            let indices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Header Text Index Buffer"),
+                contents: bytemuck::cast_slice(&buffer.indices_text),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            (vertices, indices)
+        });
+
+        let mut renderpass = context.begin_render_pass(
+            &mut encoder,
+            &wgpu::RenderPassDescriptor {
+                label: Some("Header Render Pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &context.local.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            },
+        );
+        renderpass.set_pipeline(self.pipeline.as_ref().unwrap());
+        renderpass.set_vertex_buffer(0, vertex_buffer.slice(..));
+        renderpass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
+        renderpass.draw_indexed(0..buffer.indices_pure.len() as u32, 0, 0..1);
+        if let Some((vertices, indices)) = &text_buffers {
+            self.text.as_ref().unwrap().draw(
+                &mut renderpass,
+                vertices,
+                indices,
+                buffer.indices_text.len() as u32,
+            );
+        }
+        drop(renderpass);
+        encoder.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::layout::{Layout, LayoutState, Orientation};
+
+    fn areas() -> Vec<(LeafId, Rect)> {
+        let state = LayoutState::Split {
+            orient: Orientation::Horizontal,
+            weights: vec![1.0, 2.0],
+            children: vec![LayoutState::Leaf; 2],
+        };
+        let rect = Rect::from_corner(Pnt2::ZERO, Pnt2::new(600.0, 300.0));
+        Layout::from_state(&state, rect).unwrap().leaf_rects()
+    }
+
+    #[test]
+    fn test_content() {
+        let area = areas()[0].1;
+        assert_eq!(content(area).max.y, 300.0 - HEIGHT);
+        assert_eq!(strip(area).min.y, content(area).max.y);
+        let small = Rect::from_corner(Pnt2::ZERO, Pnt2::new(10.0, 10.0));
+        assert_eq!(content(small).extent().y, 0.0);
+    }
+
+    #[test]
+    fn test_click() {
+        let areas = areas();
+        let mut header = Header::default();
+        let button = Pnt2::new(210.0, 290.0);
+        let strip = Pnt2::new(590.0, 290.0);
+        assert_eq!(
+            header.click(&areas, 3, Pnt2::new(100.0, 100.0)),
+            Click::Ignored
+        );
+        assert_eq!(header.click(&areas, 3, strip), Click::Used);
+        assert!(!header.is_open());
+
+        assert_eq!(header.click(&areas, 3, button), Click::Used);
+        assert!(header.is_open());
+        let second = Pnt2::new(210.0, 300.0 - HEIGHT - ROW_HEIGHT * 1.5);
+        header.hover(&areas, 3, second);
+        assert_eq!(header.menu.unwrap().hovered, Some(1));
+        assert_eq!(
+            header.click(&areas, 3, second),
+            Click::Select(areas[1].0, 1)
+        );
+        assert!(!header.is_open());
+
+        // clicks outside the menu only close it
+        header.click(&areas, 3, button);
+        let below = Pnt2::new(210.0, 300.0 - HEIGHT - ROW_HEIGHT * 3.5);
+        assert_eq!(header.click(&areas, 3, below), Click::Used);
+        assert!(!header.is_open());
+    }
+}
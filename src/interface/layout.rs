@@ -1,83 +1,559 @@
-use crate::prim::{Rect, Vec2};
+use std::collections::HashMap;
 
-#[derive(Debug, PartialEq)]
-enum Orientation {
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+
+use crate::prim::{Pnt2, Rect, Vec2};
+
+/// Smallest share of a split a dragged child can shrink to, so no area
+/// vanishes completely.
+const MIN_FRACTION: f64 = 0.01;
+
+/// Direction in which the children of a split are placed.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Orientation {
+    /// Side by side, from left to right.
     Horizontal,
+    /// Stacked, from top to bottom.
     Vertical,
 }
 
+/// Identifies an area of a layout for as long as it is not removed, ids
+/// are never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LeafId(u32);
+
 enum Node {
-    Inner(Layout),
-    Leaf(Rect),
+    Inner(Split),
+    Leaf(LeafId, Rect),
 }
 
-pub struct Layout {
+impl Node {
+    fn rect(&self) -> Rect {
+        match self {
+            Node::Inner(split) => split.rect,
+            Node::Leaf(_, rect) => *rect,
+        }
+    }
+
+    /// Smallest size the node can have without a leaf going below its
+    /// minimum.
+    fn min_size(&self, min_sizes: &HashMap<LeafId, Vec2>) -> Vec2 {
+        match self {
+            Node::Leaf(id, _) => min_sizes.get(id).copied().unwrap_or_default(),
+            Node::Inner(split) => {
+                let sizes = split.children.iter().map(|x| x.min_size(min_sizes));
+                sizes.fold(Vec2::default(), |acc, size| match split.orient {
+                    Orientation::Horizontal => Vec2::new(acc.x + size.x, acc.y.max(size.y)),
+                    Orientation::Vertical => Vec2::new(acc.x.max(size.x), acc.y + size.y),
+                })
+            }
+        }
+    }
+}
+
+/// Boundary between two neighbouring children of a split, which can be
+/// dragged to move the space between them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Splitter {
+    /// Child indices leading from the top level split to the split.
+    path: Vec<usize>,
+    /// The boundary follows the child with this index.
+    index: usize,
+}
+
+/// Structure and proportions of a layout without its areas, as saved in
+/// project files.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LayoutState {
+    Leaf,
+    Split {
+        orient: Orientation,
+        weights: Vec<f64>,
+        children: Vec<LayoutState>,
+    },
+}
+
+/// Children sharing the area of a split in proportion to their weights.
+struct Split {
     children: Vec<Node>,
     weights: Vec<f64>,
     orient: Orientation,
     rect: Rect,
 }
 
-impl Layout {
-    pub fn new(rect: Rect) -> Layout {
-        Layout {
+/// Result of removing a leaf below a split.
+enum Removal {
+    NotFound,
+    Done,
+    /// The leaf is the only child of the split.
+    Last,
+}
+
+impl Split {
+    fn new(orient: Orientation) -> Split {
+        Split {
             children: vec![],
             weights: vec![],
-            orient: Orientation::Horizontal,
-            rect,
+            orient,
+            rect: Rect::empty(),
         }
     }
 
-    pub fn grow(&mut self) {
-        self.grow_with_weight(1.0);
+    fn resize(&mut self, rect: Rect) {
+        self.rect = rect;
+        let (start, end) = match self.orient {
+            Orientation::Horizontal => (rect.min.x, rect.max.x),
+            Orientation::Vertical => (rect.max.y, rect.min.y),
+        };
+        let total_weight = self.weights.iter().sum::<f64>();
+        let count = self.children.len();
+        let mut sum = 0.0;
+        let mut from = start;
+        for (i, (weight, child)) in self
+            .weights
+            .iter()
+            .zip(self.children.iter_mut())
+            .enumerate()
+        {
+            sum += weight;
+            // boundaries fall on whole pixels, and neighbours share them so
+            // they neither overlap nor leave a gap
+            let to = if i + 1 == count {
+                end
+            } else {
+                (start + (end - start) * sum / total_weight)
+                    .round()
+                    .clamp(start.min(end), start.max(end))
+            };
+            let child_rect = match self.orient {
+                Orientation::Horizontal => {
+                    Rect::from_corner(Pnt2::new(from, rect.min.y), Pnt2::new(to, rect.max.y))
+                }
+                Orientation::Vertical => {
+                    Rect::from_corner(Pnt2::new(rect.min.x, to), Pnt2::new(rect.max.x, from))
+                }
+            };
+            match child {
+                Node::Leaf(_, leaf) => *leaf = child_rect,
+                Node::Inner(split) => split.resize(child_rect),
+            }
+            from = to;
+        }
     }
 
-    pub fn grow_with_weight(&mut self, w: f64) {
-        self.children.push(Node::Leaf(Rect::empty()));
-        self.weights.push(w);
-        self.resize(self.rect);
+    fn at(&self, path: &[usize]) -> Option<&Split> {
+        match path.split_first() {
+            None => Some(self),
+            Some((&i, rest)) => match self.children.get(i)? {
+                Node::Inner(split) => split.at(rest),
+                Node::Leaf(..) => None,
+            },
+        }
     }
 
-    fn resize(&mut self, rect: Rect) {
-        let total_weight = self.weights.iter().sum::<f64>();
-        let width = self.weights.iter().map(|x| x / total_weight);
-        let left: Vec<f64> = std::iter::once(0.0)
-            .chain(width.scan(0.0, |acc, x| {
-                *acc += x;
-                Some(*acc)
-            }))
-            .collect();
-
-        let offset = match self.orient {
-            Orientation::Horizontal => Vec2::new(1.0, 0.0),
-            Orientation::Vertical => Vec2::new(0.0, 1.0),
-        } * rect.extent();
-        for (span, child) in left.windows(2).zip(self.children.iter_mut()) {
-            let child_rect = Rect::from_corner(
-                rect.bottomleft() + offset * span[0],
-                rect.topleft() + offset * span[1],
-            );
-            match child {
-                Node::Leaf(leaf) => *leaf = child_rect,
-                Node::Inner(layout) => layout.resize(rect),
+    fn at_mut(&mut self, path: &[usize]) -> Option<&mut Split> {
+        match path.split_first() {
+            None => Some(self),
+            Some((&i, rest)) => match self.children.get_mut(i)? {
+                Node::Inner(split) => split.at_mut(rest),
+                Node::Leaf(..) => None,
+            },
+        }
+    }
+
+    /// Position of the boundary after child `index` along the split.
+    fn boundary(&self, index: usize) -> f64 {
+        let rect = self.children[index].rect();
+        match self.orient {
+            Orientation::Horizontal => rect.max.x,
+            Orientation::Vertical => rect.min.y,
+        }
+    }
+
+    fn splitter_at(&self, pos: Pnt2, tolerance: Vec2, path: &mut Vec<usize>) -> Option<Splitter> {
+        let rect = self.rect;
+        for index in 0..self.children.len().saturating_sub(1) {
+            let boundary = self.boundary(index);
+            let hit = match self.orient {
+                Orientation::Horizontal => {
+                    (pos.x - boundary).abs() <= tolerance.x
+                        && pos.y >= rect.min.y
+                        && pos.y < rect.max.y
+                }
+                Orientation::Vertical => {
+                    (pos.y - boundary).abs() <= tolerance.y
+                        && pos.x >= rect.min.x
+                        && pos.x < rect.max.x
+                }
+            };
+            if hit {
+                let path = path.clone();
+                return Some(Splitter { path, index });
+            }
+        }
+        for (i, child) in self.children.iter().enumerate() {
+            if let Node::Inner(split) = child {
+                path.push(i);
+                let found = split.splitter_at(pos, tolerance, path);
+                path.pop();
+                if found.is_some() {
+                    return found;
+                }
             }
         }
+        None
     }
 
-    pub fn leaves(&self) -> Vec<Rect> {
-        let mut result = vec![];
+    fn leaves(&self, result: &mut Vec<(LeafId, Rect)>) {
         for child in self.children.iter() {
             match child {
-                Node::Inner(layout) => {
-                    result.extend(layout.leaves());
+                Node::Inner(split) => split.leaves(result),
+                Node::Leaf(id, rect) => result.push((*id, *rect)),
+            }
+        }
+    }
+
+    fn split(&mut self, id: LeafId, orient: Orientation, new: LeafId) -> bool {
+        for i in 0..self.children.len() {
+            match &mut self.children[i] {
+                Node::Inner(split) => {
+                    if split.split(id, orient, new) {
+                        return true;
+                    }
+                }
+                Node::Leaf(leaf, _) if *leaf == id => {
+                    if self.orient == orient || self.children.len() == 1 {
+                        // the new leaf takes half of the space of the old one
+                        self.orient = orient;
+                        self.weights[i] /= 2.0;
+                        self.weights.insert(i + 1, self.weights[i]);
+                        self.children.insert(i + 1, Node::Leaf(new, Rect::empty()));
+                    } else {
+                        let mut split = Split::new(orient);
+                        split.children = vec![
+                            Node::Leaf(id, Rect::empty()),
+                            Node::Leaf(new, Rect::empty()),
+                        ];
+                        split.weights = vec![1.0, 1.0];
+                        self.children[i] = Node::Inner(split);
+                    }
+                    return true;
                 }
-                Node::Leaf(rect) => {
-                    result.push(*rect);
+                Node::Leaf(..) => {}
+            }
+        }
+        false
+    }
+
+    fn remove(&mut self, id: LeafId) -> Removal {
+        for i in 0..self.children.len() {
+            match &mut self.children[i] {
+                Node::Inner(split) => match split.remove(id) {
+                    Removal::NotFound => continue,
+                    Removal::Done => {
+                        if split.children.len() == 1 {
+                            self.collapse(i);
+                        }
+                        return Removal::Done;
+                    }
+                    // a split holding nothing but the leaf goes with it
+                    Removal::Last => return self.remove_child(i),
+                },
+                Node::Leaf(leaf, _) if *leaf == id => return self.remove_child(i),
+                Node::Leaf(..) => {}
+            }
+        }
+        Removal::NotFound
+    }
+
+    /// Removes the child at `index` unless it is the only one. The
+    /// neighbour before it, or after it for the first one, takes over its
+    /// space.
+    fn remove_child(&mut self, index: usize) -> Removal {
+        if self.children.len() == 1 {
+            return Removal::Last;
+        }
+        self.children.remove(index);
+        let weight = self.weights.remove(index);
+        self.weights[index.saturating_sub(1)] += weight;
+        Removal::Done
+    }
+
+    /// Replaces the child split at `index`, which has a single child left,
+    /// with that child. A split in the same direction is merged into this
+    /// one, keeping the proportions.
+    fn collapse(&mut self, index: usize) {
+        let mut split = match self.children.remove(index) {
+            Node::Inner(split) => split,
+            Node::Leaf(..) => unreachable!(),
+        };
+        let weight = self.weights.remove(index);
+        match split.children.pop().unwrap() {
+            Node::Inner(inner) if inner.orient == self.orient => {
+                let total = inner.weights.iter().sum::<f64>();
+                for (offset, (child, w)) in
+                    inner.children.into_iter().zip(inner.weights).enumerate()
+                {
+                    self.children.insert(index + offset, child);
+                    self.weights.insert(index + offset, weight * w / total);
                 }
             }
+            child => {
+                self.children.insert(index, child);
+                self.weights.insert(index, weight);
+            }
         }
+    }
+
+    fn state(&self) -> LayoutState {
+        LayoutState::Split {
+            orient: self.orient,
+            weights: self.weights.clone(),
+            children: self
+                .children
+                .iter()
+                .map(|child| match child {
+                    Node::Inner(split) => split.state(),
+                    Node::Leaf(..) => LayoutState::Leaf,
+                })
+                .collect(),
+        }
+    }
+
+    /// Nested splits need at least two children, the top level one at
+    /// least one.
+    fn from_state(state: &LayoutState, nested: bool, next_id: &mut u32) -> Result<Split> {
+        let (orient, weights, children) = match state {
+            LayoutState::Split {
+                orient,
+                weights,
+                children,
+            } => (orient, weights, children),
+            LayoutState::Leaf => bail!("expected a split, found a leaf"),
+        };
+        if weights.len() != children.len() {
+            bail!(
+                "split has {} weights for {} children",
+                weights.len(),
+                children.len()
+            );
+        }
+        let min = if nested { 2 } else { 1 };
+        if children.len() < min {
+            bail!(
+                "split has {} children, expected at least {}",
+                children.len(),
+                min
+            );
+        }
+        if weights.iter().any(|&w| !(w > 0.0 && w.is_finite())) {
+            bail!("split weights must be positive, got {:?}", weights);
+        }
+        let mut split = Split::new(*orient);
+        split.weights = weights.clone();
+        for child in children {
+            split.children.push(match child {
+                LayoutState::Leaf => {
+                    *next_id += 1;
+                    Node::Leaf(LeafId(*next_id - 1), Rect::empty())
+                }
+                inner => Node::Inner(Split::from_state(inner, true, next_id)?),
+            });
+        }
+        Ok(split)
+    }
+}
+
+/// Divides a rectangle into areas, the leaves of a tree of horizontal and
+/// vertical splits.
+///
+/// The rectangle is given in physical pixels with y pointing up, usually
+/// the whole window with its origin at the bottom-left corner. Areas are
+/// rounded to whole pixels.
+pub struct Layout {
+    root: Split,
+    rect: Rect,
+    next_id: u32,
+    min_sizes: HashMap<LeafId, Vec2>,
+}
+
+impl Layout {
+    pub fn new(rect: Rect) -> Layout {
+        Layout {
+            root: Split::new(Orientation::Horizontal),
+            rect,
+            next_id: 0,
+            min_sizes: HashMap::new(),
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn resize(&mut self, rect: Rect) {
+        self.rect = rect;
+        self.root.resize(rect);
+    }
+
+    fn next_id(&mut self) -> LeafId {
+        self.next_id += 1;
+        LeafId(self.next_id - 1)
+    }
+
+    /// Divides the leaf in two along `orient`, returning the new leaf which
+    /// is placed right of or below the old one. Returns `None` if there is
+    /// no such leaf.
+    pub fn split(&mut self, id: LeafId, orient: Orientation) -> Option<LeafId> {
+        let new = LeafId(self.next_id);
+        if !self.root.split(id, orient, new) {
+            return None;
+        }
+        self.next_id();
+        self.resize(self.rect);
+        Some(new)
+    }
+
+    /// Removes the leaf and gives its space to a neighbour, returns whether
+    /// it was removed. The last leaf cannot be removed.
+    pub fn remove(&mut self, id: LeafId) -> bool {
+        match self.root.remove(id) {
+            Removal::Done => {}
+            Removal::NotFound | Removal::Last => return false,
+        }
+        // a top level split left with a single split is replaced by it
+        if self.root.children.len() == 1 {
+            if let Node::Inner(_) = self.root.children[0] {
+                match self.root.children.pop() {
+                    Some(Node::Inner(split)) => self.root = split,
+                    _ => unreachable!(),
+                }
+            }
+        }
+        self.min_sizes.remove(&id);
+        self.resize(self.rect);
+        true
+    }
+
+    /// Size below which dragging a splitter does not shrink the leaf, zero
+    /// by default.
+    pub fn set_min_size(&mut self, id: LeafId, size: Vec2) {
+        self.min_sizes.insert(id, size);
+    }
+
+    /// The splitter within `tolerance` of `pos`, preferring outer splits.
+    pub fn splitter_at(&self, pos: Pnt2, tolerance: Vec2) -> Option<Splitter> {
+        self.root.splitter_at(pos, tolerance, &mut vec![])
+    }
+
+    /// Orientation of the split the splitter divides, its boundary runs
+    /// across it.
+    pub fn splitter_orientation(&self, splitter: &Splitter) -> Option<Orientation> {
+        self.root.at(&splitter.path).map(|split| split.orient)
+    }
+
+    /// Position of the splitter along its split, x for horizontal splits
+    /// and y for vertical ones.
+    pub fn splitter_position(&self, splitter: &Splitter) -> Option<f64> {
+        let split = self.root.at(&splitter.path)?;
+        (splitter.index + 1 < split.children.len()).then(|| split.boundary(splitter.index))
+    }
+
+    /// Moves the splitter by `delta` along its split, in the direction of
+    /// the x or y axis, resizing the two children next to it. The move stops
+    /// where either child would get smaller than its minimum size. Returns
+    /// whether anything changed.
+    pub fn drag(&mut self, splitter: &Splitter, delta: f64) -> bool {
+        let min_sizes = &self.min_sizes;
+        let split = match self.root.at_mut(&splitter.path) {
+            Some(split) if splitter.index + 1 < split.children.len() => split,
+            _ => return false,
+        };
+        let (i, orient) = (splitter.index, split.orient);
+        let axis = |v: Vec2| match orient {
+            Orientation::Horizontal => v.x,
+            Orientation::Vertical => v.y,
+        };
+        let extent = axis(split.rect.extent());
+        if extent <= 0.0 {
+            return false;
+        }
+        let (a, b) = (&split.children[i], &split.children[i + 1]);
+        let (size_a, size_b) = (axis(a.rect().extent()), axis(b.rect().extent()));
+        let min_a = axis(a.min_size(min_sizes)).max(extent * MIN_FRACTION);
+        let min_b = axis(b.min_size(min_sizes)).max(extent * MIN_FRACTION);
+
+        // children of vertical splits run downwards
+        let delta = match orient {
+            Orientation::Horizontal => delta,
+            Orientation::Vertical => -delta,
+        };
+        // a child already below its minimum may not shrink further, but is
+        // not forced to grow either
+        let delta = delta.clamp((min_a - size_a).min(0.0), (size_b - min_b).max(0.0));
+        if delta == 0.0 {
+            return false;
+        }
+        let total_weight = split.weights.iter().sum::<f64>();
+        split.weights[i] = (size_a + delta) / extent * total_weight;
+        split.weights[i + 1] = (size_b - delta) / extent * total_weight;
+        self.resize(self.rect);
+        true
+    }
+
+    /// Gives all children of the split the same size.
+    pub fn equalize(&mut self, splitter: &Splitter) -> bool {
+        let split = match self.root.at_mut(&splitter.path) {
+            Some(split) => split,
+            None => return false,
+        };
+        let weight = split.weights.iter().sum::<f64>() / split.weights.len() as f64;
+        split.weights.iter_mut().for_each(|w| *w = weight);
+        self.resize(self.rect);
+        true
+    }
+
+    /// Leaf ids and areas in order, from left to right and top to bottom
+    /// within each split.
+    pub fn leaf_rects(&self) -> Vec<(LeafId, Rect)> {
+        let mut result = vec![];
+        self.root.leaves(&mut result);
         result
     }
+
+    pub fn leaf_rect(&self, id: LeafId) -> Option<Rect> {
+        self.leaf_rects()
+            .into_iter()
+            .find(|&(leaf, _)| leaf == id)
+            .map(|(_, rect)| rect)
+    }
+
+    pub fn leaf_ids(&self) -> Vec<LeafId> {
+        self.leaf_rects().into_iter().map(|(id, _)| id).collect()
+    }
+
+    pub fn state(&self) -> LayoutState {
+        self.root.state()
+    }
+
+    /// Rebuilds a layout saved with [`Layout::state`] to fill `rect`. Leaves
+    /// get new ids, in the order of [`Layout::leaf_ids`].
+    pub fn from_state(state: &LayoutState, rect: Rect) -> Result<Layout> {
+        let mut next_id = 0;
+        let root = match state {
+            LayoutState::Leaf => bail!("the layout root must be a split"),
+            split => Split::from_state(split, false, &mut next_id)?,
+        };
+        let mut layout = Layout {
+            root,
+            rect,
+            next_id,
+            min_sizes: HashMap::new(),
+        };
+        layout.resize(rect);
+        Ok(layout)
+    }
 }
 
 #[cfg(test)]
@@ -85,39 +561,280 @@ mod tests {
     use super::*;
     use crate::prim::Pnt2;
 
+    fn rect(min: (f64, f64), max: (f64, f64)) -> Rect {
+        Rect::from_corner(Pnt2::new(min.0, min.1), Pnt2::new(max.0, max.1))
+    }
+
+    fn square() -> Rect {
+        rect((0.0, 0.0), (1000.0, 1000.0))
+    }
+
+    /// Leaves side by side with the given weights, filling `rect`.
+    fn row(rect: Rect, weights: &[f64]) -> Layout {
+        let state = LayoutState::Split {
+            orient: Orientation::Horizontal,
+            weights: weights.to_vec(),
+            children: vec![LayoutState::Leaf; weights.len()],
+        };
+        Layout::from_state(&state, rect).unwrap()
+    }
+
+    fn rects(layout: &Layout) -> Vec<Rect> {
+        layout
+            .leaf_rects()
+            .into_iter()
+            .map(|(_, rect)| rect)
+            .collect()
+    }
+
     #[test]
     fn test_resize_average() {
-        let mut root = Layout::new(Rect::from_corner(
-            Pnt2::new(-1.0, -1.0),
-            Pnt2::new(1.0, 1.0),
-        ));
-        root.grow();
-        root.grow();
-        let leaves = root.leaves();
+        let root = row(rect((0.0, 0.0), (800.0, 600.0)), &[1.0, 1.0]);
+        let leaves = rects(&root);
         assert_eq!(leaves.len(), 2);
-        assert_eq!(leaves[0].min, Pnt2::new(-1.0, -1.0));
-        assert_eq!(leaves[0].max, Pnt2::new(0.0, 1.0));
-        assert_eq!(leaves[1].min, Pnt2::new(0.0, -1.0));
-        assert_eq!(leaves[1].max, Pnt2::new(1.0, 1.0));
+        assert_eq!(leaves[0], rect((0.0, 0.0), (400.0, 600.0)));
+        assert_eq!(leaves[1], rect((400.0, 0.0), (800.0, 600.0)));
     }
 
     #[test]
     fn test_resize_weighted() {
-        let mut root = Layout::new(Rect::from_corner(
-            Pnt2::new(-1.0, -1.0),
-            Pnt2::new(1.0, 1.0),
-        ));
-        root.grow_with_weight(1.0);
-        root.grow_with_weight(2.0);
-        root.grow_with_weight(1.0);
-
-        let leaves = root.leaves();
+        let root = row(rect((0.0, 0.0), (800.0, 600.0)), &[1.0, 2.0, 1.0]);
+        let leaves = rects(&root);
         assert_eq!(leaves.len(), 3);
-        assert_eq!(leaves[0].min, Pnt2::new(-1.0, -1.0));
-        assert_eq!(leaves[0].max, Pnt2::new(-0.5, 1.0));
-        assert_eq!(leaves[1].min, Pnt2::new(-0.5, -1.0));
-        assert_eq!(leaves[1].max, Pnt2::new(0.5, 1.0));
-        assert_eq!(leaves[2].min, Pnt2::new(0.5, -1.0));
-        assert_eq!(leaves[2].max, Pnt2::new(1.0, 1.0));
+        assert_eq!(leaves[0], rect((0.0, 0.0), (200.0, 600.0)));
+        assert_eq!(leaves[1], rect((200.0, 0.0), (600.0, 600.0)));
+        assert_eq!(leaves[2], rect((600.0, 0.0), (800.0, 600.0)));
+    }
+
+    /// Checks that the leaves have whole pixel edges and cover the layout
+    /// exactly once.
+    fn assert_tiled(layout: &Layout) {
+        let leaves = rects(layout);
+        let mut area = 0.0;
+        for (i, a) in leaves.iter().enumerate() {
+            for v in [a.min.x, a.min.y, a.max.x, a.max.y] {
+                assert_eq!(v, v.round(), "{:?}", a);
+            }
+            assert!(a.min.x <= a.max.x && a.min.y <= a.max.y, "{:?}", a);
+            area += a.extent().x * a.extent().y;
+            for b in &leaves[i + 1..] {
+                let overlap = Vec2::new(
+                    a.max.x.min(b.max.x) - a.min.x.max(b.min.x),
+                    a.max.y.min(b.max.y) - a.min.y.max(b.min.y),
+                );
+                assert!(overlap.x <= 0.0 || overlap.y <= 0.0, "{:?} {:?}", a, b);
+            }
+        }
+        let extent = layout.rect().extent();
+        assert_eq!(area, extent.x * extent.y);
+    }
+
+    #[test]
+    fn test_resize_nested() {
+        let mut root = row(rect((0.0, 0.0), (1000.0, 800.0)), &[1.0]);
+        let a = root.leaf_ids()[0];
+        let b = root.split(a, Orientation::Horizontal).unwrap();
+        let c = root.split(b, Orientation::Vertical).unwrap();
+        let d = root.split(c, Orientation::Horizontal).unwrap();
+        // nested splits divide their own area, not the whole layout
+        assert_eq!(root.leaf_rect(a), Some(rect((0.0, 0.0), (500.0, 800.0))));
+        assert_eq!(
+            root.leaf_rect(b),
+            Some(rect((500.0, 400.0), (1000.0, 800.0)))
+        );
+        assert_eq!(root.leaf_rect(c), Some(rect((500.0, 0.0), (750.0, 400.0))));
+        assert_eq!(root.leaf_rect(d), Some(rect((750.0, 0.0), (1000.0, 400.0))));
+        assert_tiled(&root);
+    }
+
+    #[test]
+    fn test_resize_rounding() {
+        let mut root = row(Rect::empty(), &[1.0, 1.0, 1.5]);
+        let (a, b) = (root.leaf_ids()[0], root.leaf_ids()[1]);
+        let c = root.split(a, Orientation::Vertical).unwrap();
+        root.split(c, Orientation::Vertical).unwrap();
+        let d = root.split(b, Orientation::Vertical).unwrap();
+        root.split(d, Orientation::Horizontal).unwrap();
+        for width in 1..60 {
+            for height in [1.0, 7.0, 33.0, 767.0] {
+                root.resize(rect((0.0, 0.0), (width as f64, height)));
+                assert_tiled(&root);
+            }
+        }
+
+        let thirds = row(rect((0.0, 0.0), (1000.0, 10.0)), &[1.0; 3]);
+        let widths: Vec<_> = rects(&thirds).iter().map(|x| x.extent().x).collect();
+        assert_eq!(widths, vec![333.0, 334.0, 333.0]);
+    }
+
+    #[test]
+    fn test_state() {
+        let rect = square();
+        let mut root = row(rect, &[1.0, 3.0]);
+        root.split(root.leaf_ids()[1], Orientation::Vertical);
+        let state = root.state();
+        let restored = Layout::from_state(&state, rect).unwrap();
+        assert_eq!(restored.state(), state);
+        assert_eq!(restored.leaf_rects(), root.leaf_rects());
+
+        let invalid = LayoutState::Split {
+            orient: Orientation::Vertical,
+            weights: vec![1.0],
+            children: vec![],
+        };
+        assert!(Layout::from_state(&invalid, rect).is_err());
+        assert!(Layout::from_state(&LayoutState::Leaf, rect).is_err());
+    }
+
+    #[test]
+    fn test_state_child_counts() {
+        let split = |weights: Vec<f64>, children| LayoutState::Split {
+            orient: Orientation::Horizontal,
+            weights,
+            children,
+        };
+        let single = split(vec![1.0], vec![LayoutState::Leaf]);
+        assert_eq!(
+            rects(&Layout::from_state(&single, square()).unwrap()),
+            vec![square()]
+        );
+
+        // an empty layout, and nested splits of fewer than two children
+        assert!(Layout::from_state(&split(vec![], vec![]), square()).is_err());
+        let nested = |child| split(vec![1.0, 1.0], vec![LayoutState::Leaf, child]);
+        let empty = split(vec![], vec![]);
+        assert!(Layout::from_state(&nested(empty), square()).is_err());
+        let lone = split(vec![1.0], vec![LayoutState::Leaf]);
+        assert!(Layout::from_state(&nested(lone), square()).is_err());
+        let pair = split(vec![1.0, 1.0], vec![LayoutState::Leaf; 2]);
+        assert!(Layout::from_state(&nested(pair), square()).is_ok());
+    }
+
+    #[test]
+    fn test_split() {
+        let mut root = row(square(), &[1.0]);
+        let left = root.leaf_ids()[0];
+        let right = root.split(left, Orientation::Horizontal).unwrap();
+        let bottom = root.split(right, Orientation::Vertical).unwrap();
+        assert_eq!(root.leaf_ids(), vec![left, right, bottom]);
+        assert_eq!(
+            rects(&root),
+            vec![
+                rect((0.0, 0.0), (500.0, 1000.0)),
+                rect((500.0, 500.0), (1000.0, 1000.0)),
+                rect((500.0, 0.0), (1000.0, 500.0)),
+            ]
+        );
+        // splitting along the parent's direction halves the leaf in place
+        let middle = root.split(right, Orientation::Vertical).unwrap();
+        assert_eq!(root.leaf_ids(), vec![left, right, middle, bottom]);
+        assert_eq!(
+            root.leaf_rect(middle),
+            Some(rect((500.0, 500.0), (1000.0, 750.0)))
+        );
+        assert_eq!(root.split(LeafId(99), Orientation::Vertical), None);
+    }
+
+    #[test]
+    fn test_remove() {
+        let mut root = row(square(), &[1.0, 1.0]);
+        let (a, b) = (root.leaf_ids()[0], root.leaf_ids()[1]);
+        let c = root.split(b, Orientation::Vertical).unwrap();
+        let d = root.split(c, Orientation::Horizontal).unwrap();
+
+        // the space goes to the neighbour before the leaf
+        assert!(root.remove(d));
+        assert!(!root.remove(d));
+        assert_eq!(root.leaf_rect(c), Some(rect((500.0, 0.0), (1000.0, 500.0))));
+        // the split left with b alone is collapsed into the top level
+        assert!(root.remove(c));
+        assert_eq!(
+            root.state(),
+            Layout::from_state(&root.state(), square()).unwrap().state()
+        );
+        assert_eq!(
+            root.state(),
+            LayoutState::Split {
+                orient: Orientation::Horizontal,
+                weights: vec![1.0, 1.0],
+                children: vec![LayoutState::Leaf, LayoutState::Leaf],
+            }
+        );
+        assert!(root.remove(a));
+        assert_eq!(root.leaf_rects(), vec![(b, square())]);
+        assert!(!root.remove(b));
+        assert_eq!(root.leaf_rect(b), Some(square()));
+    }
+
+    #[test]
+    fn test_remove_merges_splits() {
+        let mut root = row(square(), &[1.0]);
+        let a = root.leaf_ids()[0];
+        let b = root.split(a, Orientation::Vertical).unwrap();
+        let c = root.split(b, Orientation::Horizontal).unwrap();
+        let d = root.split(c, Orientation::Vertical).unwrap();
+        // removing b leaves the vertical split of c and d inside the
+        // vertical split of a, which takes its children over
+        assert!(root.remove(b));
+        assert_eq!(
+            root.state(),
+            LayoutState::Split {
+                orient: Orientation::Vertical,
+                weights: vec![0.5, 0.25, 0.25],
+                children: vec![LayoutState::Leaf; 3],
+            }
+        );
+        assert_eq!(root.leaf_ids(), vec![a, c, d]);
+    }
+
+    #[test]
+    fn test_drag() {
+        let mut root = row(square(), &[1.0, 1.0]);
+        let (a, b) = (root.leaf_ids()[0], root.leaf_ids()[1]);
+        let c = root.split(b, Orientation::Vertical).unwrap();
+
+        let tolerance = Vec2::new(25.0, 25.0);
+        assert_eq!(root.splitter_at(Pnt2::new(750.0, 750.0), tolerance), None);
+        let outer = root
+            .splitter_at(Pnt2::new(510.0, 750.0), tolerance)
+            .unwrap();
+        assert_eq!(
+            root.splitter_orientation(&outer),
+            Some(Orientation::Horizontal)
+        );
+        assert!(root.drag(&outer, 250.0));
+        assert_eq!(root.splitter_position(&outer), Some(750.0));
+        assert_eq!(root.leaf_rect(c).unwrap().min.x, 750.0);
+
+        // the inner splitter runs across the right column
+        let inner = root
+            .splitter_at(Pnt2::new(875.0, 500.0), tolerance)
+            .unwrap();
+        assert_eq!(
+            root.splitter_orientation(&inner),
+            Some(Orientation::Vertical)
+        );
+        assert!(root.drag(&inner, 250.0));
+        assert_eq!(root.leaf_rect(b).unwrap().extent().y, 250.0);
+        assert_eq!(root.leaf_rect(c).unwrap().extent().y, 750.0);
+
+        // dragging stops at the minimum size of the leaves next to it
+        root.set_min_size(a, Vec2::new(200.0, 0.0));
+        root.drag(&outer, -5000.0);
+        assert_eq!(root.leaf_rect(a).unwrap().extent().x, 200.0);
+        assert!(!root.drag(&outer, -50.0));
+        root.drag(&outer, 5000.0);
+        assert_eq!(root.leaf_rect(b).unwrap().extent().x, 1000.0 * MIN_FRACTION);
+
+        // the minimum of a split covers all its leaves
+        root.set_min_size(b, Vec2::new(150.0, 0.0));
+        root.set_min_size(c, Vec2::new(250.0, 0.0));
+        assert!(!root.drag(&outer, 50.0));
+        root.drag(&outer, -5000.0);
+        root.drag(&outer, 5000.0);
+        assert_eq!(root.leaf_rect(c).unwrap().extent().x, 250.0);
+
+        assert!(root.equalize(&outer));
+        assert_eq!(root.leaf_rect(a), Some(rect((0.0, 0.0), (500.0, 1000.0))));
     }
 }
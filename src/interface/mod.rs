@@ -1,12 +1,26 @@
 mod context;
+mod dispatch;
 mod draw;
+mod event;
+mod font;
+mod header;
 mod layout;
+mod offscreen;
 mod panel;
+mod registry;
+mod text;
 mod texture;
 mod window;
+mod workspace;
 
 pub use context::*;
 pub use draw::DrawBuffer;
-pub use panel::Panel;
-pub use window::Window;
+pub use event::*;
+pub use layout::LayoutState;
+pub use offscreen::render_panel;
+pub use panel::{Panel, PanelState};
+pub use registry::PanelType;
+pub use text::TextRenderer;
 pub use texture::Texture;
+pub use window::Window;
+pub use workspace::Workspace;
@@ -0,0 +1,230 @@
+use std::num::NonZeroU32;
+use std::rc::Rc;
+
+use anyhow::{Context as _, Result};
+
+use super::context::*;
+use super::font::Font;
+use super::panel::Panel;
+use crate::prim::{Pnt2, Rect, Vec2};
+use crate::scene::SharedScene;
+
+impl ContextGlobal {
+    /// Context without a window, for rendering into images of the given
+    /// size. Falls back to a software adapter when there is no GPU.
+    pub async fn init_headless(
+        size: winit::dpi::PhysicalSize<u32>,
+        scene: SharedScene,
+    ) -> Result<ContextGlobal> {
+        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let mut adapter = None;
+        for force_fallback_adapter in [false, true] {
+            adapter = instance
+                .request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::default(),
+                    compatible_surface: None,
+                    force_fallback_adapter,
+                })
+                .await;
+            if adapter.is_some() {
+                break;
+            }
+        }
+        let adapter = adapter.context("no graphics adapter found")?;
+
+        let (device, queue) = adapter
+            .request_device(
+                &wgpu::DeviceDescriptor {
+                    features: wgpu::Features::empty(),
+                    limits: wgpu::Limits::downlevel_defaults(),
+                    label: None,
+                },
+                None,
+            )
+            .await?;
+
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            width: size.width.max(1),
+            height: size.height.max(1),
+            present_mode: wgpu::PresentMode::Fifo,
+        };
+
+        Ok(ContextGlobal {
+            surface: None,
+            device,
+            queue,
+            config,
+            scale_factor: 1.0,
+            scene,
+            font: Rc::new(Font::default_font()),
+        })
+    }
+}
+
+/// Texture standing in for a window surface, with the size and format the
+/// context is configured for.
+pub struct Offscreen {
+    texture: wgpu::Texture,
+    frame: ContextFrame,
+}
+
+impl Offscreen {
+    pub fn new(context: &ContextGlobal) -> Offscreen {
+        let config = &context.config;
+        let texture = context.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Texture"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Offscreen {
+            texture,
+            frame: ContextFrame { view },
+        }
+    }
+
+    pub fn frame(&self) -> &ContextFrame {
+        &self.frame
+    }
+
+    /// Copies the texture into an image, waiting for the work submitted so
+    /// far. Expects an RGBA format, as set up by
+    /// [`ContextGlobal::init_headless`].
+    pub fn read(&self, context: &ContextGlobal) -> Result<image::RgbaImage> {
+        let (width, height) = (context.config.width, context.config.height);
+        // rows of a buffer copy are padded to a fixed alignment
+        let row = 4 * width;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row = row.div_ceil(align) * align;
+
+        let buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Offscreen Readback Buffer"),
+            size: (padded_row * height) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        let mut encoder = context
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("Offscreen Readback Encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: NonZeroU32::new(padded_row),
+                    rows_per_image: NonZeroU32::new(height),
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+        context.queue.submit(Some(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let mapping = slice.map_async(wgpu::MapMode::Read);
+        context.device.poll(wgpu::Maintain::Wait);
+        pollster::block_on(mapping)?;
+        let data = slice.get_mapped_range();
+        let pixels = data
+            .chunks_exact(padded_row as usize)
+            .flat_map(|x| &x[..row as usize])
+            .copied()
+            .collect();
+        drop(data);
+        buffer.unmap();
+        Ok(image::RgbaImage::from_raw(width, height, pixels).unwrap())
+    }
+}
+
+/// Draws an initialized panel filling the configured size into an image.
+pub fn render_panel(panel: &mut dyn Panel, context: &ContextGlobal) -> Result<image::RgbaImage> {
+    let size = Vec2::new(context.config.width as f64, context.config.height as f64);
+    panel.resize(size, context);
+    let offscreen = Offscreen::new(context);
+    let frame = Context {
+        global: context,
+        local: offscreen.frame(),
+        area: Rect::from_corner(Pnt2::ZERO, Pnt2::new(size.x, size.y)),
+    };
+    context.queue.submit(Some(panel.render(&frame)));
+    offscreen.read(context)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::Scene;
+
+    /// Panel clearing the whole target to one color.
+    struct Fill([f64; 3]);
+
+    impl Panel for Fill {
+        fn kind(&self) -> &'static str {
+            "fill"
+        }
+
+        fn init(&mut self, _context: &ContextGlobal) {}
+
+        fn render(&mut self, context: &Context) -> wgpu::CommandBuffer {
+            let mut encoder =
+                context
+                    .global
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("Fill Render Encoder"),
+                    });
+            let [r, g, b] = self.0;
+            context.begin_render_pass(
+                &mut encoder,
+                &wgpu::RenderPassDescriptor {
+                    label: Some("Fill Render Pass"),
+                    color_attachments: &[wgpu::RenderPassColorAttachment {
+                        view: &context.local.view,
+                        resolve_target: None,
+                        ops: wgpu::Operations {
+                            load: wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a: 1.0 }),
+                            store: true,
+                        },
+                    }],
+                    depth_stencil_attachment: None,
+                },
+            );
+            encoder.finish()
+        }
+    }
+
+    fn headless(width: u32, height: u32) -> ContextGlobal {
+        let size = winit::dpi::PhysicalSize::new(width, height);
+        pollster::block_on(ContextGlobal::init_headless(size, Scene::new().shared())).unwrap()
+    }
+
+    #[test]
+    #[ignore = "needs a graphics adapter"]
+    fn test_render_panel() {
+        let context = headless(70, 30);
+        let image = render_panel(&mut Fill([1.0, 0.0, 0.0]), &context).unwrap();
+        assert_eq!(image.dimensions(), (70, 30));
+        assert!(image.pixels().all(|x| x.0 == [255, 0, 0, 255]));
+    }
+}
@@ -1,8 +1,46 @@
-use crate::prim::Rect;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
 use super::context::*;
+use super::event::Event;
+use crate::prim::Vec2;
+
+/// Saved state of a panel, as stored in project files.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PanelState {
+    /// [`Panel::kind`] of the panel that saved the state.
+    pub kind: String,
+    /// Produced by [`Panel::save_state`].
+    pub state: String,
+}
 
 pub trait Panel {
+    /// Name of the panel type, unique among panel types.
+    fn kind(&self) -> &'static str;
+
     fn init(&mut self, context: &ContextGlobal);
 
-    fn render(&self, rect: Rect, context: &Context) -> wgpu::CommandBuffer;
+    /// Called after `init` and whenever the size of the panel in pixels
+    /// changes.
+    fn resize(&mut self, _size: Vec2, _context: &ContextGlobal) {}
+
+    /// Draws the panel, see [`Context::begin_render_pass`] for the
+    /// coordinates.
+    fn render(&mut self, context: &Context) -> wgpu::CommandBuffer;
+
+    /// Called with input targeted at this panel, see [`Event`] for the
+    /// coordinate convention.
+    fn handle_event(&mut self, _event: &Event, _context: &ContextGlobal) {}
+
+    /// Panel specific state to keep in project files, such as the camera of
+    /// a viewport. Panels encode it as they like, usually as RON.
+    fn save_state(&self) -> String {
+        String::new()
+    }
+
+    /// Restores state from [`Panel::save_state`], may be called before
+    /// `init`.
+    fn load_state(&mut self, _state: &str) -> Result<()> {
+        Ok(())
+    }
 }
@@ -0,0 +1,45 @@
+use super::panel::Panel;
+
+/// Kind of panel that can be put in a layout area.
+#[derive(Clone, Copy)]
+pub struct PanelType {
+    /// [`Panel::kind`] of the panels created.
+    pub kind: &'static str,
+    /// Shown in area headers.
+    pub name: &'static str,
+    pub icon: char,
+    pub create: fn() -> Box<dyn Panel>,
+}
+
+/// Panel types known to a window, in the order they are offered.
+#[derive(Clone, Default)]
+pub struct Registry {
+    types: Vec<PanelType>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry::default()
+    }
+
+    /// Adds a panel type, replacing the one of the same kind.
+    pub fn register(&mut self, panel_type: PanelType) {
+        match self.types.iter_mut().find(|x| x.kind == panel_type.kind) {
+            Some(known) => *known = panel_type,
+            None => self.types.push(panel_type),
+        }
+    }
+
+    pub fn get(&self, kind: &str) -> Option<&PanelType> {
+        self.types.iter().find(|x| x.kind == kind)
+    }
+
+    pub fn types(&self) -> &[PanelType] {
+        &self.types
+    }
+
+    /// Creates a panel of the given kind, if it is known.
+    pub fn create(&self, kind: &str) -> Option<Box<dyn Panel>> {
+        self.get(kind).map(|x| (x.create)())
+    }
+}
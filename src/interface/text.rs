@@ -0,0 +1,259 @@
+use std::rc::Rc;
+
+use anyhow::*;
+
+use super::context::*;
+use super::draw::{DrawBuffer, TextRun};
+use super::font::{Font, GlyphAtlas, GlyphSlot};
+use super::texture::Texture;
+
+/// Side of the square glyph atlas in pixels.
+const ATLAS_SIZE: u32 = 512;
+
+/// Lays out the text runs of a `DrawBuffer` and draws them from a glyph
+/// atlas. Each panel drawing text owns one, next to its other pipelines.
+pub struct TextRenderer {
+    font: Rc<Font>,
+    atlas: GlyphAtlas,
+    texture: Texture,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl TextRenderer {
+    pub fn new(context: &ContextGlobal) -> TextRenderer {
+        let device = &context.device;
+        let atlas = GlyphAtlas::new(ATLAS_SIZE);
+        let texture = Texture::from_image(
+            device,
+            &context.queue,
+            &image::DynamicImage::ImageRgba8(atlas.image.clone()),
+            Some("Glyph Atlas"),
+        )
+        .unwrap();
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+            label: Some("text_bind_group_layout"),
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            layout: &bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&texture.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                },
+            ],
+            label: Some("text_bind_group"),
+        });
+
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("Text Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("text.wgsl").into()),
+        });
+        let render_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Text Render Pipeline Layout"),
+                bind_group_layouts: &[&bind_group_layout],
+                push_constant_ranges: &[],
+            });
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Text Render Pipeline"),
+            layout: Some(&render_pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[VertexText::desc()],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format: context.config.format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: 1,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None,
+        });
+
+        TextRenderer {
+            font: context.font.clone(),
+            atlas,
+            texture,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// Turns the pending text runs of `buffer` into textured quads, in the
+    /// pixel space the runs were given in.
+    pub fn layout(&mut self, buffer: &mut DrawBuffer) {
+        if let Err(error) = layout_buffer(&self.font, &mut self.atlas, buffer) {
+            log::error!("{:#}", error);
+        }
+    }
+
+    /// Uploads glyphs rasterized since the last frame.
+    pub fn prepare(&mut self, queue: &wgpu::Queue) {
+        if self.atlas.take_dirty() {
+            self.texture.write(queue, &self.atlas.image);
+        }
+    }
+
+    pub fn draw<'a>(
+        &'a self,
+        renderpass: &mut wgpu::RenderPass<'a>,
+        vertices: &'a wgpu::Buffer,
+        indices: &'a wgpu::Buffer,
+        count: u32,
+    ) {
+        renderpass.set_pipeline(&self.pipeline);
+        renderpass.set_bind_group(0, &self.bind_group, &[]);
+        renderpass.set_vertex_buffer(0, vertices.slice(..));
+        renderpass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint16);
+        renderpass.draw_indexed(0..count, 0, 0..1);
+    }
+}
+
+/// Lays the text runs of `buffer` out. Fails without adding any quads if
+/// the glyphs of the runs do not fit into the atlas together.
+fn layout_buffer(font: &Font, atlas: &mut GlyphAtlas, buffer: &mut DrawBuffer) -> Result<()> {
+    let runs = std::mem::take(&mut buffer.texts);
+    let (vertices, indices) = (buffer.vertices_text.len(), buffer.indices_text.len());
+    // a refill midway moves the glyphs of the quads placed before, the
+    // second pass finds them in the new atlas
+    for _ in 0..2 {
+        let generation = atlas.generation();
+        layout_runs(font, atlas, &runs, buffer);
+        if atlas.generation() == generation {
+            return Ok(());
+        }
+        buffer.vertices_text.truncate(vertices);
+        buffer.indices_text.truncate(indices);
+    }
+    bail!("the text of the frame does not fit into the glyph atlas")
+}
+
+/// Appends the quads of `runs` to `buffer`, rasterizing missing glyphs into
+/// `atlas`.
+fn layout_runs(font: &Font, atlas: &mut GlyphAtlas, runs: &[TextRun], buffer: &mut DrawBuffer) {
+    let atlas_size = atlas.size() as f64;
+    for run in runs {
+        let color = [run.color.r as f32, run.color.g as f32, run.color.b as f32];
+        let (x, y) = (run.pos.x.round(), run.pos.y.round());
+        for shaped in font.shape(&run.text, run.size) {
+            let glyph = match atlas.glyph(font, shaped.id, run.size) {
+                GlyphSlot::Glyph(glyph) => glyph,
+                GlyphSlot::Empty | GlyphSlot::TooLarge => continue,
+            };
+            let min = (x + shaped.x.round() + glyph.offset.x, y + glyph.offset.y);
+            let extent = glyph.region.extent();
+            let max = (min.0 + extent.x, min.1 + extent.y);
+            let uv_min = glyph.region.min / atlas_size;
+            let uv_max = glyph.region.max / atlas_size;
+
+            let base = buffer.vertices_text.len() as u16;
+            let corners = [
+                ([min.0, min.1], [uv_min.x, uv_max.y]),
+                ([max.0, min.1], [uv_max.x, uv_max.y]),
+                ([min.0, max.1], [uv_min.x, uv_min.y]),
+                ([max.0, max.1], [uv_max.x, uv_min.y]),
+            ];
+            for (position, uv) in corners {
+                buffer.vertices_text.push(VertexText {
+                    position: [position[0] as f32, position[1] as f32, 0.0],
+                    uv: [uv[0] as f32, uv[1] as f32],
+                    color,
+                });
+            }
+            buffer
+                .indices_text
+                .extend([base, base + 1, base + 2, base + 2, base + 1, base + 3]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prim::{Pnt2, RGB};
+
+    #[test]
+    fn test_layout_after_refill() {
+        let font = Font::default_font();
+        let mut atlas = GlyphAtlas::new(32);
+        for c in "wxyz".chars() {
+            atlas.glyph(&font, font.shape(&c.to_string(), 20.0)[0].id, 20.0);
+        }
+
+        // x is cached, b no longer fits and refills the atlas
+        let mut buffer = DrawBuffer::new();
+        buffer.text(Pnt2::ZERO, "xab", 20.0, RGB::WHITE);
+        let generation = atlas.generation();
+        layout_buffer(&font, &mut atlas, &mut buffer).unwrap();
+        assert_eq!(atlas.generation(), generation + 1);
+
+        // every quad samples its glyph where the atlas holds it now
+        assert_eq!(buffer.vertices_text.len(), 12);
+        let uv = |p: Pnt2| [(p.x / 32.0) as f32, (p.y / 32.0) as f32];
+        for (quad, glyph) in buffer.vertices_text.chunks(4).zip(font.shape("xab", 20.0)) {
+            let region = match atlas.glyph(&font, glyph.id, 20.0) {
+                GlyphSlot::Glyph(glyph) => glyph.region,
+                slot => panic!("expected a glyph, got {:?}", slot),
+            };
+            assert_eq!(quad[2].uv, uv(region.min));
+            assert_eq!(quad[1].uv, uv(region.max));
+        }
+    }
+
+    #[test]
+    fn test_layout_too_much_text() {
+        let font = Font::default_font();
+        let mut atlas = GlyphAtlas::new(32);
+        let mut buffer = DrawBuffer::new();
+        buffer.text(Pnt2::ZERO, "abcdefgh", 20.0, RGB::WHITE);
+        assert!(layout_buffer(&font, &mut atlas, &mut buffer).is_err());
+        assert!(buffer.vertices_text.is_empty() && buffer.indices_text.is_empty());
+    }
+}
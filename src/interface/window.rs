@@ -1,62 +1,496 @@
-use pollster;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context as _, Result};
 use winit::{
-    event::{Event, WindowEvent},
+    dpi::PhysicalPosition,
+    event::{
+        ElementState, Event as WinitEvent, ModifiersState, MouseButton, MouseScrollDelta,
+        VirtualKeyCode, WindowEvent,
+    },
     event_loop::{ControlFlow, EventLoop},
-    window::WindowBuilder,
+    window::{CursorIcon, Window as WinitWindow, WindowBuilder},
 };
 
-use wgpu;
-
 use super::context::*;
-use super::layout::Layout;
-use super::panel::Panel;
-use crate::prim::{Pnt2, Rect};
+use super::dispatch::Dispatcher;
+use super::event::Event;
+use super::header::{self, Click, Header};
+use super::layout::{Layout, LeafId, Orientation, Splitter};
+use super::offscreen::Offscreen;
+use super::panel::{Panel, PanelState};
+use super::registry::{PanelType, Registry};
+use super::workspace::{Workspace, Workspaces};
+use crate::io::{self, Project};
+use crate::prim::{Pnt2, Rect, Vec2};
+use crate::scene::{Scene, SharedScene};
+
+/// Pixels scrolled by touchpads that count as one wheel line.
+const PIXELS_PER_LINE: f64 = 20.0;
+/// Logical pixels from a splitter within which it can be grabbed.
+const SPLITTER_TOLERANCE: f64 = 4.0;
+/// Logical pixels below which dragging a splitter does not shrink a panel.
+const MIN_PANEL_SIZE: f64 = 32.0;
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Distance from a splitter within which it can be grabbed, in physical
+/// pixels.
+fn splitter_tolerance(context: &ContextGlobal) -> Vec2 {
+    let tolerance = SPLITTER_TOLERANCE * context.scale_factor;
+    Vec2::new(tolerance, tolerance)
+}
 
 pub struct Window {
+    /// Covers the window in physical pixels, origin at the bottom-left
+    /// corner.
     layout: Layout,
-    panels: Vec<Box<dyn Panel>>,
+    /// Each panel with the layout area it fills.
+    panels: Vec<(LeafId, Box<dyn Panel>)>,
+    dispatcher: Dispatcher,
+    scene: SharedScene,
+    /// Where ctrl+S saves the project.
+    project_path: PathBuf,
+    modifiers: ModifiersState,
+    /// Cursor position in window pixels.
+    cursor: Option<Pnt2>,
+    /// Splitter being dragged.
+    drag: Option<Splitter>,
+    /// Last splitter clicked, to detect double clicks.
+    last_click: Option<(Instant, Splitter)>,
+    cursor_icon: CursorIcon,
+    registry: Registry,
+    header: Header,
+    workspace_name: String,
+    workspaces: Workspaces,
+    /// Where the workspaces are kept between sessions.
+    workspaces_path: Option<PathBuf>,
 }
 
 impl Window {
     pub fn new() -> Window {
         Window {
-            layout: Layout::new(Rect::from_corner(
-                Pnt2::new(-1.0, -1.0),
-                Pnt2::new(1.0, 1.0),
-            )),
+            layout: Layout::new(Rect::empty()),
             panels: vec![],
+            dispatcher: Dispatcher::new(),
+            scene: Scene::new().shared(),
+            project_path: PathBuf::from("untitled.void"),
+            modifiers: ModifiersState::empty(),
+            cursor: None,
+            drag: None,
+            last_click: None,
+            cursor_icon: CursorIcon::Default,
+            registry: Registry::new(),
+            header: Header::default(),
+            workspace_name: String::new(),
+            workspaces: Workspaces::default(),
+            workspaces_path: Workspaces::default_path(),
+        }
+    }
+
+    /// Divides the area of `leaf` and puts `panel` right of or below the
+    /// panel already there. With a context, the panel is initialized and
+    /// the panels are resized to their new areas.
+    pub fn split(
+        &mut self,
+        leaf: LeafId,
+        orient: Orientation,
+        mut panel: Box<dyn Panel>,
+        context: Option<&ContextGlobal>,
+    ) -> Option<LeafId> {
+        let new = self.layout.split(leaf, orient)?;
+        if let Some(context) = context {
+            panel.init(context);
+        }
+        self.panels.push((new, panel));
+        self.dispatcher.reset();
+        if let Some(context) = context {
+            self.resize_panels(context);
+        }
+        Some(new)
+    }
+
+    /// Removes the panel in `leaf`, its neighbour takes over the space.
+    /// The last panel cannot be closed.
+    pub fn close(&mut self, leaf: LeafId, context: Option<&ContextGlobal>) -> bool {
+        if !self.layout.remove(leaf) {
+            return false;
+        }
+        self.panels.retain(|(id, _)| *id != leaf);
+        self.dispatcher.reset();
+        self.header.close();
+        if let Some(context) = context {
+            self.resize_panels(context);
+        }
+        true
+    }
+
+    /// Area under the cursor.
+    fn hovered_leaf(&self) -> Option<LeafId> {
+        self.dispatcher.hover().map(|index| self.panels[index].0)
+    }
+
+    pub fn panel(&self, leaf: LeafId) -> Option<&dyn Panel> {
+        self.panels
+            .iter()
+            .find(|(id, _)| *id == leaf)
+            .map(|(_, panel)| panel.as_ref())
+    }
+
+    /// Panels in layout order, from left to right and top to bottom.
+    fn ordered_panels(&self) -> Vec<&dyn Panel> {
+        self.layout
+            .leaf_ids()
+            .into_iter()
+            .filter_map(|leaf| self.panel(leaf))
+            .collect()
+    }
+
+    pub fn scene(&self) -> SharedScene {
+        self.scene.clone()
+    }
+
+    pub fn set_project_path(&mut self, path: impl Into<PathBuf>) {
+        self.project_path = path.into();
+    }
+
+    /// The scene and the workspace showing it.
+    pub fn project(&self) -> Result<Project> {
+        let panels = self
+            .ordered_panels()
+            .into_iter()
+            .map(|panel| PanelState {
+                kind: panel.kind().to_string(),
+                state: panel.save_state(),
+            })
+            .collect();
+        Project::new(&self.scene.borrow(), self.layout.state(), panels)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.project()?.save(path)
+    }
+
+    /// Replaces the scene, layout and panels with the ones saved in
+    /// `project`. Everything is loaded before any of it is applied, so an
+    /// invalid project leaves the window as it was.
+    pub fn open(&mut self, project: &Project) -> Result<()> {
+        let workspace = Workspace {
+            name: self.workspace_name.clone(),
+            layout: project.layout.clone(),
+            panels: project.panels.iter().map(|x| x.kind.clone()).collect(),
+        };
+        let layout = self.check_workspace(&workspace)?;
+        let mut panels = vec![];
+        for saved in &project.panels {
+            let mut panel = match self.registry.create(&saved.kind) {
+                Some(panel) => panel,
+                None => bail!("unknown panel type {:?}", saved.kind),
+            };
+            panel
+                .load_state(&saved.state)
+                .with_context(|| format!("invalid {} state", saved.kind))?;
+            panels.push(panel);
+        }
+        let mut scene = Scene::new();
+        project.scene.load(&mut scene)?;
+
+        *self.scene.borrow_mut() = scene;
+        self.set_panels(&workspace.name, layout, panels, None);
+        Ok(())
+    }
+
+    /// Makes a panel type available to workspaces and to the menu in each
+    /// area header.
+    pub fn register(&mut self, panel_type: PanelType) {
+        self.registry.register(panel_type);
+    }
+
+    /// Replaces the panel in `leaf` with a new one of the given kind,
+    /// initialized with `context` if the window runs.
+    pub fn set_panel_kind(
+        &mut self,
+        leaf: LeafId,
+        kind: &str,
+        context: Option<&ContextGlobal>,
+    ) -> Result<()> {
+        let index = match self.panels.iter().position(|(id, _)| *id == leaf) {
+            Some(index) => index,
+            None => bail!("no panel in area {:?}", leaf),
+        };
+        if self.panels[index].1.kind() == kind {
+            return Ok(());
+        }
+        let mut panel = match self.registry.create(kind) {
+            Some(panel) => panel,
+            None => bail!("unknown panel type {:?}", kind),
+        };
+        if let Some(context) = context {
+            panel.init(context);
+        }
+        self.panels[index].1 = panel;
+        self.dispatcher.reset();
+        if let Some(context) = context {
+            self.resize_panels(context);
+        }
+        Ok(())
+    }
+
+    /// The current layout with the kind of panel in each area.
+    pub fn workspace(&self) -> Workspace {
+        Workspace {
+            name: self.workspace_name.clone(),
+            layout: self.layout.state(),
+            panels: self
+                .ordered_panels()
+                .iter()
+                .map(|panel| panel.kind().to_string())
+                .collect(),
+        }
+    }
+
+    /// Fails unless the layout is valid and each panel can be created.
+    fn check_workspace(&self, workspace: &Workspace) -> Result<Layout> {
+        let layout = Layout::from_state(&workspace.layout, self.layout.rect())
+            .with_context(|| format!("invalid layout in workspace {}", workspace.name))?;
+        if layout.leaf_ids().len() != workspace.panels.len() {
+            bail!(
+                "workspace {} has {} panels for {} areas",
+                workspace.name,
+                workspace.panels.len(),
+                layout.leaf_ids().len()
+            );
+        }
+        for kind in &workspace.panels {
+            let known = self.panels.iter().any(|(_, x)| x.kind() == kind)
+                || self.registry.get(kind).is_some();
+            if !known {
+                bail!("unknown panel type {:?}", kind);
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Replaces the layout and panels. Panels of the same kind are kept in
+    /// order, so their state carries over, and missing ones are created and
+    /// initialized with `context` if the window runs.
+    pub fn set_workspace(
+        &mut self,
+        workspace: &Workspace,
+        context: Option<&ContextGlobal>,
+    ) -> Result<()> {
+        let layout = self.check_workspace(workspace)?;
+        self.apply_workspace(workspace, layout, context);
+        Ok(())
+    }
+
+    fn apply_workspace(
+        &mut self,
+        workspace: &Workspace,
+        layout: Layout,
+        context: Option<&ContextGlobal>,
+    ) {
+        let order = self.layout.leaf_ids();
+        self.panels
+            .sort_by_key(|(leaf, _)| order.iter().position(|x| x == leaf));
+        let mut old: Vec<Option<Box<dyn Panel>>> =
+            self.panels.drain(..).map(|(_, x)| Some(x)).collect();
+
+        let mut panels = vec![];
+        for kind in &workspace.panels {
+            let reused = old
+                .iter_mut()
+                .find(|x| x.as_ref().is_some_and(|x| x.kind() == kind))
+                .and_then(|x| x.take());
+            let panel = match reused {
+                Some(panel) => panel,
+                None => {
+                    let mut panel = self.registry.create(kind).unwrap();
+                    if let Some(context) = context {
+                        panel.init(context);
+                    }
+                    panel
+                }
+            };
+            panels.push(panel);
         }
+        self.set_panels(&workspace.name, layout, panels, context);
     }
 
-    pub fn append(&mut self, panel: Box<dyn Panel>) {
-        self.panels.push(panel);
-        self.layout.grow();
+    /// Puts `panels` into the leaves of `layout`, in order.
+    fn set_panels(
+        &mut self,
+        name: &str,
+        layout: Layout,
+        panels: Vec<Box<dyn Panel>>,
+        context: Option<&ContextGlobal>,
+    ) {
+        self.panels = layout.leaf_ids().into_iter().zip(panels).collect();
+        self.layout = layout;
+        self.workspace_name = name.to_string();
+        self.dispatcher.reset();
+        self.drag = None;
+        self.last_click = None;
+        self.header.close();
+        if let Some(context) = context {
+            self.resize_panels(context);
+        }
     }
 
-    pub fn size(&self) -> usize {
-        self.panels.len()
+    /// Sets the workspace in use when the application last quit, or the
+    /// first preset.
+    pub fn restore_workspace(&mut self) {
+        if let Some(path) = &self.workspaces_path {
+            match Workspaces::load(path) {
+                Ok(workspaces) => self.workspaces = workspaces,
+                Err(error) => log::error!("{:#}", error),
+            }
+        }
+        if let Some(last) = self.workspaces.last.clone() {
+            match self.set_workspace(&last, None) {
+                Ok(()) => return,
+                Err(error) => log::error!("{:#}", error),
+            }
+        }
+        let preset = &Workspace::presets()[0];
+        if let Err(error) = self.set_workspace(preset, None) {
+            log::error!("{:#}", error);
+        }
+    }
+
+    /// Keeps the current workspace to restore it on the next start.
+    fn save_workspaces(&mut self) {
+        self.workspaces.last = Some(self.workspace());
+        if let Some(path) = &self.workspaces_path {
+            if let Err(error) = self.workspaces.save(path) {
+                log::error!("{:#}", error);
+            }
+        }
+    }
+
+    /// Handles ctrl shortcuts, returns whether the key was used.
+    ///
+    /// Ctrl+S saves the project, ctrl+shift+S saves the current workspace
+    /// as a new one, ctrl+E exports the scene as binary glTF next to the
+    /// project, and ctrl+1 to ctrl+9 switch to the presets followed by the
+    /// saved workspaces. Ctrl+backslash splits the area under the cursor side
+    /// by side and ctrl+shift+backslash on top of each other, with a new
+    /// panel of the same kind. Ctrl+W closes the area.
+    fn shortcut(&mut self, key: VirtualKeyCode, context: &ContextGlobal) -> bool {
+        const DIGITS: [VirtualKeyCode; 9] = [
+            VirtualKeyCode::Key1,
+            VirtualKeyCode::Key2,
+            VirtualKeyCode::Key3,
+            VirtualKeyCode::Key4,
+            VirtualKeyCode::Key5,
+            VirtualKeyCode::Key6,
+            VirtualKeyCode::Key7,
+            VirtualKeyCode::Key8,
+            VirtualKeyCode::Key9,
+        ];
+        if key == VirtualKeyCode::S && !self.modifiers.shift() {
+            match self.save(&self.project_path) {
+                Ok(()) => log::info!("saved {}", self.project_path.display()),
+                Err(error) => log::error!("{:#}", error),
+            }
+        } else if key == VirtualKeyCode::S {
+            let mut workspace = self.workspace();
+            workspace.name = format!("Workspace {}", self.workspaces.saved.len() + 1);
+            log::info!("saved workspace {}", workspace.name);
+            self.workspace_name = workspace.name.clone();
+            self.workspaces.add(workspace);
+            self.save_workspaces();
+        } else if key == VirtualKeyCode::Backslash {
+            let orient = if self.modifiers.shift() {
+                Orientation::Vertical
+            } else {
+                Orientation::Horizontal
+            };
+            if let Some(leaf) = self.hovered_leaf() {
+                let kind = self.panel(leaf).unwrap().kind();
+                if let Some(panel) = self.registry.create(kind) {
+                    self.split(leaf, orient, panel, Some(context));
+                }
+            }
+        } else if key == VirtualKeyCode::W {
+            if let Some(leaf) = self.hovered_leaf() {
+                self.close(leaf, Some(context));
+            }
+        } else if key == VirtualKeyCode::E {
+            let path = self.project_path.with_extension("glb");
+            match io::export(&path, &self.scene.borrow()) {
+                Ok(()) => log::info!("exported {}", path.display()),
+                Err(error) => log::error!("{:#}", error),
+            }
+        } else if let Some(index) = DIGITS.iter().position(|&x| x == key) {
+            let workspace = match self.workspaces.all().into_iter().nth(index) {
+                Some(workspace) => workspace,
+                None => return true,
+            };
+            match self.set_workspace(&workspace, Some(context)) {
+                Ok(()) => log::info!("workspace {}", workspace.name),
+                Err(error) => log::error!("{:#}", error),
+            }
+        } else {
+            return false;
+        }
+        true
     }
 
     pub fn run(mut self) {
-        env_logger::init();
         let event_loop = EventLoop::new();
         let window = WindowBuilder::new().build(&event_loop).unwrap();
 
-        let context = pollster::block_on(ContextGlobal::init_wgpu(&window));
-        self.panels.iter_mut().for_each(|x| x.init(&context));
+        let mut context = pollster::block_on(ContextGlobal::init_wgpu(&window, self.scene.clone()));
+        self.init(&context);
 
         event_loop.run(move |event, _, contrl_flow| {
             *contrl_flow = ControlFlow::Wait;
 
             match event {
-                Event::WindowEvent {
+                WinitEvent::WindowEvent {
                     event: WindowEvent::CloseRequested,
                     window_id,
-                } if window_id == window.id() => *contrl_flow = ControlFlow::Exit,
-                Event::RedrawRequested(window_id) if window_id == window.id() => {
-                    self.render(&context);
+                } if window_id == window.id() => {
+                    self.save_workspaces();
+                    *contrl_flow = ControlFlow::Exit
                 }
-                Event::MainEventsCleared => {
+                WinitEvent::WindowEvent {
+                    event: WindowEvent::Resized(size),
+                    window_id,
+                } if window_id == window.id() => {
+                    context.resize(size);
+                    self.resize_panels(&context);
+                }
+                WinitEvent::WindowEvent {
+                    event:
+                        WindowEvent::ScaleFactorChanged {
+                            scale_factor,
+                            new_inner_size,
+                        },
+                    window_id,
+                } if window_id == window.id() => {
+                    context.scale_factor = scale_factor;
+                    context.resize(*new_inner_size);
+                    self.resize_panels(&context);
+                }
+                WinitEvent::WindowEvent { event, window_id } if window_id == window.id() => {
+                    self.input(&event, &window, &context);
+                }
+                WinitEvent::RedrawRequested(window_id) if window_id == window.id() => {
+                    let size = window.inner_size();
+                    if size.width == 0 || size.height == 0 {
+                        return;
+                    }
+                    match self.render(&context) {
+                        Ok(_) => {}
+                        Err(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
+                            context.reconfigure()
+                        }
+                        Err(wgpu::SurfaceError::OutOfMemory) => *contrl_flow = ControlFlow::Exit,
+                        Err(e) => log::error!("{:?}", e),
+                    }
+                }
+                WinitEvent::MainEventsCleared => {
                     window.request_redraw();
                 }
                 _ => {}
@@ -64,22 +498,452 @@ impl Window {
         });
     }
 
-    pub fn render(&self, context_global: &ContextGlobal) {
-        let output = context_global.surface.get_current_texture().unwrap();
+    fn resize_panels(&mut self, context: &ContextGlobal) {
+        let config = &context.config;
+        self.layout.resize(Rect::from_corner(
+            Pnt2::ZERO,
+            Pnt2::new(config.width as f64, config.height as f64),
+        ));
+        let min = MIN_PANEL_SIZE * context.scale_factor;
+        let min_size = Vec2::new(min, min + header::HEIGHT);
+        let rects = self.panel_rects();
+        for ((leaf, panel), rect) in self.panels.iter_mut().zip(rects) {
+            self.layout.set_min_size(*leaf, min_size);
+            panel.resize(rect.extent(), context);
+        }
+    }
+
+    /// Layout areas of the panels, headers included.
+    fn areas(&self) -> Vec<(LeafId, Rect)> {
+        self.panels
+            .iter()
+            .map(|(leaf, _)| (*leaf, self.layout.leaf_rect(*leaf).unwrap()))
+            .collect()
+    }
+
+    /// Panel areas below the headers.
+    fn panel_rects(&self) -> Vec<Rect> {
+        self.areas()
+            .into_iter()
+            .map(|(_, area)| header::content(area))
+            .collect()
+    }
+
+    fn set_cursor_icon(&mut self, window: &WinitWindow, icon: CursorIcon) {
+        if self.cursor_icon != icon {
+            window.set_cursor_icon(icon);
+            self.cursor_icon = icon;
+        }
+    }
+
+    /// Drags splitters between panels, returns whether the event was used.
+    fn splitter_input(
+        &mut self,
+        event: &WindowEvent,
+        window: &WinitWindow,
+        context: &ContextGlobal,
+    ) -> bool {
+        let height = window.inner_size().height as f64;
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                let pos = Pnt2::new(position.x, height - position.y);
+                self.cursor = Some(pos);
+                if let Some(splitter) = &self.drag {
+                    let target = match self.layout.splitter_orientation(splitter) {
+                        Some(Orientation::Horizontal) => pos.x,
+                        Some(Orientation::Vertical) => pos.y,
+                        None => return true,
+                    };
+                    let delta = target - self.layout.splitter_position(splitter).unwrap();
+                    if self.layout.drag(splitter, delta) {
+                        self.resize_panels(context);
+                    }
+                    return true;
+                }
+                if self.dispatcher.capture().is_none() {
+                    let icon = match self.hovered_splitter(context) {
+                        Some(Orientation::Horizontal) => CursorIcon::EwResize,
+                        Some(Orientation::Vertical) => CursorIcon::NsResize,
+                        None => CursorIcon::Default,
+                    };
+                    self.set_cursor_icon(window, icon);
+                }
+                false
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+                if self.drag.is_none() {
+                    self.set_cursor_icon(window, CursorIcon::Default);
+                }
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if self.dispatcher.capture().is_none() && !self.header.is_open() => {
+                // an open menu covers the splitters
+                let splitter = self
+                    .cursor
+                    .and_then(|pos| self.layout.splitter_at(pos, splitter_tolerance(context)));
+                let splitter = match splitter {
+                    Some(splitter) => splitter,
+                    None => return false,
+                };
+                let now = Instant::now();
+                match self.last_click.take() {
+                    Some((time, last)) if last == splitter && now - time < DOUBLE_CLICK => {
+                        self.layout.equalize(&splitter);
+                        self.resize_panels(context);
+                    }
+                    _ => {
+                        self.last_click = Some((now, splitter.clone()));
+                        self.drag = Some(splitter);
+                    }
+                }
+                true
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Released,
+                button: MouseButton::Left,
+                ..
+            } => self.drag.take().is_some(),
+            _ => false,
+        }
+    }
+
+    /// Handles the panel type menus in the area headers, returns whether
+    /// the event was used.
+    fn header_input(
+        &mut self,
+        event: &WindowEvent,
+        window: &WinitWindow,
+        context: &ContextGlobal,
+    ) -> bool {
+        let size = window.inner_size();
+        let cursor = |position: &PhysicalPosition<f64>| {
+            Pnt2::new(position.x, size.height as f64 - position.y)
+        };
+        let count = self.registry.types().len();
+        match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.header.hover(&self.areas(), count, cursor(position));
+                false
+            }
+            WindowEvent::MouseInput {
+                state: ElementState::Pressed,
+                button: MouseButton::Left,
+                ..
+            } if self.dispatcher.capture().is_none() => {
+                let pos = match self.cursor {
+                    Some(pos) => pos,
+                    None => return false,
+                };
+                match self.header.click(&self.areas(), count, pos) {
+                    Click::Ignored => false,
+                    Click::Used => true,
+                    Click::Select(leaf, index) => {
+                        let kind = self.registry.types()[index].kind;
+                        if let Err(error) = self.set_panel_kind(leaf, kind, Some(context)) {
+                            log::error!("{:#}", error);
+                        }
+                        true
+                    }
+                }
+            }
+            _ => false,
+        }
+    }
+
+    fn hovered_splitter(&self, context: &ContextGlobal) -> Option<Orientation> {
+        let splitter = self
+            .layout
+            .splitter_at(self.cursor?, splitter_tolerance(context))?;
+        self.layout.splitter_orientation(&splitter)
+    }
+
+    fn input(&mut self, event: &WindowEvent, window: &WinitWindow, context: &ContextGlobal) {
+        if self.splitter_input(event, window, context) || self.header_input(event, window, context)
+        {
+            return;
+        }
+        let size = window.inner_size();
+        let rects = self.panel_rects();
+        let cursor = |position: &PhysicalPosition<f64>| {
+            Pnt2::new(position.x, size.height as f64 - position.y)
+        };
+
+        let routed = match event {
+            WindowEvent::CursorMoved { position, .. } => {
+                self.dispatcher.cursor_moved(&rects, cursor(position))
+            }
+            WindowEvent::CursorLeft { .. } => self.dispatcher.cursor_left(),
+            WindowEvent::MouseInput { state, button, .. } => {
+                self.dispatcher.mouse_input(&rects, *button, *state)
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x as f64, *y as f64),
+                    MouseScrollDelta::PixelDelta(p) => {
+                        Vec2::new(p.x / PIXELS_PER_LINE, p.y / PIXELS_PER_LINE)
+                    }
+                };
+                self.dispatcher.mouse_wheel(&rects, delta)
+            }
+            WindowEvent::KeyboardInput { input, .. }
+                if input.state == ElementState::Pressed
+                    && self.modifiers.ctrl()
+                    && input
+                        .virtual_keycode
+                        .is_some_and(|key| self.shortcut(key, context)) =>
+            {
+                vec![]
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                self.dispatcher.keyboard(Event::Keyboard(*input))
+            }
+            WindowEvent::ReceivedCharacter(c) => self.dispatcher.keyboard(Event::Character(*c)),
+            WindowEvent::ModifiersChanged(state) => {
+                self.modifiers = *state;
+                self.dispatcher.modifiers_changed(self.panels.len(), *state)
+            }
+            _ => vec![],
+        };
+
+        for (index, event) in routed {
+            self.panels[index].1.handle_event(&event, context);
+        }
+    }
+
+    /// Prepares the panels for drawing with `context` and lays them out
+    /// for its size.
+    pub fn init(&mut self, context: &ContextGlobal) {
+        self.panels.iter_mut().for_each(|(_, x)| x.init(context));
+        self.header.init(context);
+        self.resize_panels(context);
+    }
+
+    pub fn render(&mut self, context_global: &ContextGlobal) -> Result<(), wgpu::SurfaceError> {
+        let surface = context_global.surface.as_ref().unwrap();
+        let output = surface.get_current_texture()?;
         let view = output
             .texture
             .create_view(&wgpu::TextureViewDescriptor::default());
+        let frame = ContextFrame { view };
+        context_global
+            .queue
+            .submit(self.draw(context_global, &frame));
+        output.present();
+        Ok(())
+    }
+
+    /// Draws the window into an image of the size `context` is configured
+    /// for, usually a headless one. Call [`Window::init`] first.
+    pub fn render_image(&mut self, context: &ContextGlobal) -> Result<image::RgbaImage> {
+        let offscreen = Offscreen::new(context);
+        context.queue.submit(self.draw(context, offscreen.frame()));
+        offscreen.read(context)
+    }
+
+    fn draw(
+        &mut self,
+        context_global: &ContextGlobal,
+        frame: &ContextFrame,
+    ) -> Vec<wgpu::CommandBuffer> {
+        let areas = self.areas();
+        let mut commands = vec![];
+        for ((_, panel), (_, area)) in self.panels.iter_mut().zip(&areas) {
+            let area = header::content(*area);
+            // nothing of a panel squeezed to nothing can be seen
+            if area.extent().x < 1.0 || area.extent().y < 1.0 {
+                continue;
+            }
+            let context = Context {
+                global: context_global,
+                local: frame,
+                area,
+            };
+            commands.push(panel.render(&context));
+        }
         let context = Context {
-            global: &context_global,
-            local: ContextFrame { view: view },
+            global: context_global,
+            local: frame,
+            area: self.layout.rect(),
         };
+        let kinds: Vec<_> = self.panels.iter().map(|(_, x)| x.kind()).collect();
+        commands.push(self.header.render(&areas, &kinds, &self.registry, &context));
+        commands
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Panel remembering its creation order, to tell instances apart.
+    struct Dummy {
+        kind: &'static str,
+        serial: usize,
+    }
+
+    impl Panel for Dummy {
+        fn kind(&self) -> &'static str {
+            self.kind
+        }
+
+        fn init(&mut self, _context: &ContextGlobal) {}
+
+        fn render(&mut self, _context: &Context) -> wgpu::CommandBuffer {
+            unreachable!()
+        }
+
+        fn save_state(&self) -> String {
+            self.serial.to_string()
+        }
+
+        fn load_state(&mut self, state: &str) -> Result<()> {
+            self.serial = state.parse()?;
+            Ok(())
+        }
+    }
+
+    fn create(kind: &'static str) -> Box<dyn Panel> {
+        static SERIAL: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let serial = SERIAL.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        Box::new(Dummy { kind, serial })
+    }
 
-        context_global.queue.submit(
-            self.panels
+    fn window() -> Window {
+        let mut window = Window::new();
+        window.register(PanelType {
+            kind: "outliner",
+            name: "Outliner",
+            icon: '≡',
+            create: || create("outliner"),
+        });
+        window.register(PanelType {
+            kind: "viewport",
+            name: "Viewport",
+            icon: '▣',
+            create: || create("viewport"),
+        });
+        window
+    }
+
+    fn serials(window: &Window) -> Vec<String> {
+        window
+            .ordered_panels()
+            .iter()
+            .map(|x| x.save_state())
+            .collect()
+    }
+
+    #[test]
+    fn test_set_workspace() {
+        let mut window = window();
+        let presets = Workspace::presets();
+        window.set_workspace(&presets[0], None).unwrap();
+        assert_eq!(window.workspace(), presets[0]);
+        let before = serials(&window);
+
+        // the outliner and the first viewport are kept, in layout order
+        window.set_workspace(&presets[1], None).unwrap();
+        assert_eq!(window.workspace(), presets[1]);
+        let after = serials(&window);
+        assert_eq!((&after[0], &after[2]), (&before[1], &before[0]));
+        assert!(!before.contains(&after[1]));
+
+        let mut unknown = presets[0].clone();
+        unknown.panels[0] = "timeline".to_string();
+        let error = window.set_workspace(&unknown, None).unwrap_err();
+        assert!(format!("{:#}", error).contains("timeline"));
+        let mut mismatched = presets[0].clone();
+        mismatched.panels.pop();
+        assert!(window.set_workspace(&mismatched, None).is_err());
+        assert_eq!(window.workspace(), presets[1]);
+    }
+
+    #[test]
+    fn test_set_panel_kind() {
+        let mut window = window();
+        let preset = &Workspace::presets()[0];
+        window.set_workspace(preset, None).unwrap();
+        let before = serials(&window);
+        let leaves = window.layout.leaf_ids();
+
+        window.set_panel_kind(leaves[0], "viewport", None).unwrap();
+        assert_eq!(window.workspace().panels, vec!["viewport", "viewport"]);
+        let after = serials(&window);
+        assert_ne!(after[0], before[0]);
+        assert_eq!(after[1], before[1]);
+
+        // the same kind keeps the panel
+        window.set_panel_kind(leaves[0], "viewport", None).unwrap();
+        assert_eq!(serials(&window), after);
+        assert!(window.set_panel_kind(leaves[0], "timeline", None).is_err());
+        assert_eq!(serials(&window), after);
+    }
+
+    #[test]
+    fn test_split_and_close() {
+        let mut window = window();
+        window
+            .set_workspace(&Workspace::presets()[0], None)
+            .unwrap();
+        let leaves = window.layout.leaf_ids();
+        let before = serials(&window);
+
+        let new = window
+            .split(leaves[1], Orientation::Vertical, create("outliner"), None)
+            .unwrap();
+        assert_eq!(window.layout.leaf_ids(), vec![leaves[0], leaves[1], new]);
+        assert_eq!(window.panel(new).unwrap().kind(), "outliner");
+        assert_eq!(serials(&window)[..2], before[..]);
+
+        assert!(window.close(leaves[1], None));
+        assert!(window.panel(leaves[1]).is_none());
+        assert!(window
+            .split(leaves[1], Orientation::Vertical, create("outliner"), None)
+            .is_none());
+        assert_eq!(serials(&window)[0], before[0]);
+        assert!(window.close(leaves[0], None));
+        assert_eq!(window.layout.leaf_ids(), vec![new]);
+        // the last panel stays
+        assert!(!window.close(new, None));
+        assert!(window.panel(new).is_some());
+    }
+
+    #[test]
+    fn test_open() {
+        let mut window = window();
+        window
+            .set_workspace(&Workspace::presets()[0], None)
+            .unwrap();
+        window.scene().borrow_mut().add("Cube", None);
+        let mut project = window.project().unwrap();
+        project.panels[0].state = "1000".to_string();
+
+        let mut opened = self::window();
+        opened.scene().borrow_mut().add("Old", None);
+        opened.open(&project).unwrap();
+        assert_eq!(
+            opened.workspace().panels,
+            project
+                .panels
                 .iter()
-                .zip(self.layout.leaves())
-                .map(|(panel, rect)| panel.render(rect, &context)),
+                .map(|x| x.kind.clone())
+                .collect::<Vec<_>>()
         );
-        output.present();
+        assert_eq!(serials(&opened)[0], "1000");
+        assert_eq!(opened.scene().borrow().len(), 1);
+
+        // nothing changes if a panel state is invalid
+        window.scene().borrow_mut().add("Sphere", None);
+        let before = serials(&window);
+        let mut broken = project.clone();
+        broken.panels[1].state = "camera".to_string();
+        assert!(window.open(&broken).is_err());
+        assert_eq!(serials(&window), before);
+        assert_eq!(window.scene().borrow().len(), 2);
     }
 }
@@ -0,0 +1,161 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+
+use super::layout::{LayoutState, Orientation};
+
+/// Arrangement of panel types, without the state of the panels.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Workspace {
+    pub name: String,
+    pub layout: LayoutState,
+    /// [`Panel::kind`](super::Panel::kind) of the panel in each leaf, in
+    /// layout order.
+    pub panels: Vec<String>,
+}
+
+fn split(orient: Orientation, children: Vec<(f64, LayoutState)>) -> LayoutState {
+    let (weights, children) = children.into_iter().unzip();
+    LayoutState::Split {
+        orient,
+        weights,
+        children,
+    }
+}
+
+impl Workspace {
+    /// Built-in workspaces, the first one is used when nothing else is
+    /// known.
+    pub fn presets() -> Vec<Workspace> {
+        use LayoutState::Leaf;
+        use Orientation::*;
+        let panels = |kinds: &[&str]| kinds.iter().map(|x| x.to_string()).collect();
+        vec![
+            Workspace {
+                name: "Modeling".to_string(),
+                layout: split(Horizontal, vec![(1.0, Leaf), (4.0, Leaf)]),
+                panels: panels(&["outliner", "viewport"]),
+            },
+            Workspace {
+                name: "Shading".to_string(),
+                layout: split(
+                    Horizontal,
+                    vec![
+                        (3.0, Leaf),
+                        (2.0, split(Vertical, vec![(1.0, Leaf), (1.0, Leaf)])),
+                    ],
+                ),
+                panels: panels(&["viewport", "viewport", "outliner"]),
+            },
+            Workspace {
+                name: "Animation".to_string(),
+                layout: split(
+                    Vertical,
+                    vec![
+                        (3.0, split(Horizontal, vec![(1.0, Leaf), (4.0, Leaf)])),
+                        (1.0, Leaf),
+                    ],
+                ),
+                panels: panels(&["outliner", "viewport", "viewport"]),
+            },
+        ]
+    }
+}
+
+/// Workspaces kept between sessions: the layout in use when the
+/// application last quit and the ones saved by the user.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Workspaces {
+    pub last: Option<Workspace>,
+    pub saved: Vec<Workspace>,
+}
+
+impl Workspaces {
+    /// File the workspaces are kept in, in the user's configuration
+    /// directory.
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("void").join("workspaces.ron"))
+    }
+
+    /// Reads the workspaces, a missing file gives none.
+    pub fn load(path: &Path) -> Result<Workspaces> {
+        let text = match std::fs::read_to_string(path) {
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Workspaces::default())
+            }
+            result => result.with_context(|| format!("failed to read {}", path.display()))?,
+        };
+        ron::from_str(&text).with_context(|| format!("failed to load {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let config = ron::ser::PrettyConfig::new().indentor("  ".to_string());
+        let text = ron::ser::to_string_pretty(self, config)?;
+        std::fs::write(path, text).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    /// Presets followed by the workspaces saved by the user.
+    pub fn all(&self) -> Vec<Workspace> {
+        let mut result = Workspace::presets();
+        result.extend(self.saved.iter().cloned());
+        result
+    }
+
+    /// Saves `workspace`, replacing a saved one with the same name.
+    pub fn add(&mut self, workspace: Workspace) {
+        match self.saved.iter_mut().find(|x| x.name == workspace.name) {
+            Some(saved) => *saved = workspace,
+            None => self.saved.push(workspace),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface::layout::Layout;
+    use crate::prim::{Pnt2, Rect};
+
+    #[test]
+    fn test_presets() {
+        let rect = Rect::from_corner(Pnt2::ZERO, Pnt2::new(800.0, 600.0));
+        for preset in Workspace::presets() {
+            let layout = Layout::from_state(&preset.layout, rect).unwrap();
+            assert_eq!(
+                layout.leaf_ids().len(),
+                preset.panels.len(),
+                "{}",
+                preset.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config").join("workspaces.ron");
+        assert_eq!(Workspaces::load(&path).unwrap(), Workspaces::default());
+
+        let mut workspaces = Workspaces::default();
+        let mut custom = Workspace::presets().pop().unwrap();
+        custom.name = "Custom".to_string();
+        workspaces.add(custom.clone());
+        custom.panels.reverse();
+        workspaces.add(custom.clone());
+        workspaces.last = Some(custom);
+        assert_eq!(workspaces.saved.len(), 1);
+        assert_eq!(workspaces.all().len(), Workspace::presets().len() + 1);
+
+        workspaces.save(&path).unwrap();
+        assert_eq!(Workspaces::load(&path).unwrap(), workspaces);
+
+        std::fs::write(&path, "(last: Some(").unwrap();
+        let error = Workspaces::load(&path).unwrap_err();
+        assert!(format!("{:#}", error).contains("workspaces.ron"));
+    }
+}
@@ -0,0 +1,891 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use ::gltf::image::Format;
+use ::gltf::mesh::Mode;
+use anyhow::*;
+use serde_json::{json, Value};
+
+use crate::prim::{Mat4, Pnt3, Quat, Vec2, Vec3, Vec4, RGB};
+use crate::scene::{Image, ImageId, Material, MaterialId, Mesh, MeshId, NodeId, Scene, Transform};
+
+/// Converts the pixels decoded by the gltf crate to 8 bit RGBA.
+fn rgba_image(data: &::gltf::image::Data) -> Result<image::RgbaImage> {
+    let channels = match data.format {
+        Format::R8 | Format::R16 => 1,
+        Format::R8G8 | Format::R16G16 => 2,
+        Format::R8G8B8 | Format::R16G16B16 | Format::R32G32B32FLOAT => 3,
+        Format::R8G8B8A8 | Format::R16G16B16A16 | Format::R32G32B32A32FLOAT => 4,
+    };
+    let values: Vec<u8> = match data.format {
+        Format::R8 | Format::R8G8 | Format::R8G8B8 | Format::R8G8B8A8 => data.pixels.clone(),
+        Format::R16 | Format::R16G16 | Format::R16G16B16 | Format::R16G16B16A16 => data
+            .pixels
+            .chunks_exact(2)
+            .map(|x| (u16::from_le_bytes([x[0], x[1]]) >> 8) as u8)
+            .collect(),
+        Format::R32G32B32FLOAT | Format::R32G32B32A32FLOAT => data
+            .pixels
+            .chunks_exact(4)
+            .map(|x| {
+                let value = f32::from_le_bytes([x[0], x[1], x[2], x[3]]);
+                (value.clamp(0.0, 1.0) * 255.0).round() as u8
+            })
+            .collect(),
+    };
+    let pixels = values
+        .chunks_exact(channels)
+        .flat_map(|x| match channels {
+            1 => [x[0], x[0], x[0], 255],
+            2 => [x[0], x[0], x[0], x[1]],
+            3 => [x[0], x[1], x[2], 255],
+            _ => [x[0], x[1], x[2], x[3]],
+        })
+        .collect();
+    image::RgbaImage::from_raw(data.width, data.height, pixels)
+        .context("image data does not match its size")
+}
+
+fn color(c: [f32; 3]) -> RGB {
+    RGB::new(c[0] as f64, c[1] as f64, c[2] as f64)
+}
+
+fn read_mesh(primitive: &::gltf::Primitive, buffers: &[::gltf::buffer::Data]) -> Result<Mesh> {
+    let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+    let positions: Vec<Pnt3> = reader
+        .read_positions()
+        .context("primitive has no positions")?
+        .map(|[x, y, z]| Pnt3::new(x as f64, y as f64, z as f64))
+        .collect();
+    let count = positions.len();
+
+    let indices: Vec<u32> = match reader.read_indices() {
+        Some(indices) => indices.into_u32().collect(),
+        None => (0..count as u32).collect(),
+    };
+    let indices = match primitive.mode() {
+        Mode::Triangles => indices,
+        Mode::TriangleStrip => (2..indices.len())
+            .flat_map(|i| match i % 2 {
+                0 => [indices[i - 2], indices[i - 1], indices[i]],
+                _ => [indices[i - 1], indices[i - 2], indices[i]],
+            })
+            .collect(),
+        Mode::TriangleFan => (2..indices.len())
+            .flat_map(|i| [indices[0], indices[i - 1], indices[i]])
+            .collect(),
+        mode => bail!("unsupported primitive mode {:?}", mode),
+    };
+
+    let mut mesh = Mesh {
+        positions,
+        uvs: match reader.read_tex_coords(0) {
+            Some(uvs) => uvs
+                .into_f32()
+                .map(|[u, v]| Vec2::new(u as f64, v as f64))
+                .collect(),
+            None => vec![Vec2::default(); count],
+        },
+        colors: reader
+            .read_colors(0)
+            .map(|colors| colors.into_rgb_f32().map(color).collect()),
+        indices,
+        ..Mesh::default()
+    };
+    match reader.read_normals() {
+        Some(normals) => {
+            mesh.normals = normals
+                .map(|[x, y, z]| Vec3::new(x as f64, y as f64, z as f64))
+                .collect()
+        }
+        None => mesh.compute_normals(),
+    }
+    mesh.validate()?;
+    Ok(mesh)
+}
+
+/// Node transform, with matrices split by [`Mat4::decompose`] so a mirroring
+/// matrix keeps its negative scale.
+fn transform(transform: ::gltf::scene::Transform) -> Transform {
+    let vector = |v: [f32; 3]| Vec3::new(v[0] as f64, v[1] as f64, v[2] as f64);
+    match transform {
+        ::gltf::scene::Transform::Matrix { matrix } => {
+            let column =
+                |c: [f32; 4]| Vec4::new(c[0] as f64, c[1] as f64, c[2] as f64, c[3] as f64);
+            let (translation, rotation, scale) = Mat4::from_columns(
+                column(matrix[0]),
+                column(matrix[1]),
+                column(matrix[2]),
+                column(matrix[3]),
+            )
+            .decompose();
+            Transform {
+                translation,
+                rotation,
+                scale,
+            }
+        }
+        ::gltf::scene::Transform::Decomposed {
+            translation,
+            rotation,
+            scale,
+        } => Transform {
+            translation: vector(translation),
+            rotation: Quat::new(
+                rotation[0] as f64,
+                rotation[1] as f64,
+                rotation[2] as f64,
+                rotation[3] as f64,
+            ),
+            scale: vector(scale),
+        },
+    }
+}
+
+/// Maps glTF indices to the scene items created for them, so shared data is
+/// only added once.
+struct Importer<'a> {
+    document: &'a ::gltf::Document,
+    buffers: &'a [::gltf::buffer::Data],
+    images: &'a [::gltf::image::Data],
+    dir: &'a Path,
+    scene: &'a mut Scene,
+    image_ids: HashMap<usize, ImageId>,
+    material_ids: HashMap<usize, MaterialId>,
+    mesh_ids: HashMap<(usize, usize), MeshId>,
+}
+
+impl Importer<'_> {
+    fn image(&mut self, texture: ::gltf::Texture) -> Result<ImageId> {
+        let source = texture.source();
+        let index = source.index();
+        if let Some(&id) = self.image_ids.get(&index) {
+            return Ok(id);
+        }
+        let (name, path) = match source.source() {
+            ::gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => {
+                (uri.to_string(), Some(self.dir.join(uri)))
+            }
+            _ => (format!("image{}", index), None),
+        };
+        let image = Image {
+            name: source.name().map_or(name, str::to_string),
+            path,
+            image: rgba_image(&self.images[index])
+                .with_context(|| format!("invalid image {}", index))?,
+        };
+        let id = self.scene.add_image(image);
+        self.image_ids.insert(index, id);
+        Ok(id)
+    }
+
+    fn material(&mut self, material: ::gltf::Material) -> Result<Option<MaterialId>> {
+        let index = match material.index() {
+            Some(index) => index,
+            // primitives without material use the glTF default one
+            None => return Ok(None),
+        };
+        if let Some(&id) = self.material_ids.get(&index) {
+            return Ok(Some(id));
+        }
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, a] = pbr.base_color_factor();
+        let result = Material {
+            name: material
+                .name()
+                .map_or_else(|| format!("material{}", index), str::to_string),
+            base_color: color([r, g, b]),
+            opacity: a as f64,
+            base_color_texture: match pbr.base_color_texture() {
+                Some(info) => Some(self.image(info.texture())?),
+                None => None,
+            },
+            metallic: pbr.metallic_factor() as f64,
+            roughness: pbr.roughness_factor() as f64,
+            metallic_roughness_texture: match pbr.metallic_roughness_texture() {
+                Some(info) => Some(self.image(info.texture())?),
+                None => None,
+            },
+            normal_texture: match material.normal_texture() {
+                Some(normal) => Some(self.image(normal.texture())?),
+                None => None,
+            },
+            emissive: color(material.emissive_factor()),
+            double_sided: material.double_sided(),
+        };
+        let id = self.scene.add_material(result);
+        self.material_ids.insert(index, id);
+        Ok(Some(id))
+    }
+
+    fn primitive(&mut self, mesh: &::gltf::Mesh, primitive: &::gltf::Primitive) -> Result<MeshId> {
+        let key = (mesh.index(), primitive.index());
+        if let Some(&id) = self.mesh_ids.get(&key) {
+            return Ok(id);
+        }
+        let data = read_mesh(primitive, self.buffers)
+            .with_context(|| format!("invalid primitive {} of mesh {}", key.1, key.0))?;
+        let id = self.scene.add_mesh(data);
+        self.mesh_ids.insert(key, id);
+        Ok(id)
+    }
+
+    fn node(&mut self, node: ::gltf::Node, parent: Option<NodeId>) -> Result<NodeId> {
+        let name = node
+            .name()
+            .map_or_else(|| format!("node{}", node.index()), str::to_string);
+        let id = self.scene.add(name, parent);
+
+        self.scene.set_transform(id, transform(node.transform()));
+
+        if let Some(mesh) = node.mesh() {
+            let primitives: Vec<_> = mesh.primitives().collect();
+            for primitive in &primitives {
+                let target = match primitives.len() {
+                    1 => id,
+                    _ => {
+                        let name = format!(
+                            "{}.{}",
+                            mesh.name().unwrap_or("primitive"),
+                            primitive.index()
+                        );
+                        self.scene.add(name, Some(id))
+                    }
+                };
+                let mesh_id = self.primitive(&mesh, primitive)?;
+                let material = self.material(primitive.material())?;
+                self.scene.set_mesh(target, Some(mesh_id));
+                self.scene.set_material(target, material);
+            }
+        }
+
+        for child in node.children() {
+            self.node(child, Some(id))?;
+        }
+        Ok(id)
+    }
+}
+
+/// Adds the default scene of a `.gltf` or `.glb` file to `scene`, returning
+/// its root nodes. Buffers and images may be embedded, data URIs, or files
+/// next to it. Meshes with several primitives get one child node per
+/// primitive.
+pub fn import_gltf(path: &Path, scene: &mut Scene) -> Result<Vec<NodeId>> {
+    let (document, buffers, images) =
+        ::gltf::import(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let mut importer = Importer {
+        document: &document,
+        buffers: &buffers,
+        images: &images,
+        dir: path.parent().unwrap_or_else(|| Path::new("")),
+        scene,
+        image_ids: HashMap::new(),
+        material_ids: HashMap::new(),
+        mesh_ids: HashMap::new(),
+    };
+    let roots: Vec<::gltf::Node> = match importer
+        .document
+        .default_scene()
+        .or_else(|| importer.document.scenes().next())
+    {
+        Some(default) => default.nodes().collect(),
+        None => vec![],
+    };
+    roots
+        .into_iter()
+        .map(|node| importer.node(node, None))
+        .collect::<Result<_>>()
+        .with_context(|| format!("failed to import {}", path.display()))
+}
+
+/// Binary buffer and JSON arrays of a document being written.
+#[derive(Default)]
+struct Exporter {
+    buffer: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    textures: Vec<Value>,
+    images: Vec<Value>,
+    nodes: Vec<Value>,
+    mesh_indices: HashMap<(MeshId, Option<MaterialId>), usize>,
+    material_indices: HashMap<MaterialId, usize>,
+    texture_indices: HashMap<ImageId, usize>,
+    /// PNG files to write next to a `.gltf` file, empty for GLB where
+    /// images go to the buffer.
+    files: Option<Vec<(String, Vec<u8>)>>,
+}
+
+impl Exporter {
+    /// Appends `bytes` to the buffer as a new view, returning its index.
+    fn view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        while !self.buffer.len().is_multiple_of(4) {
+            self.buffer.push(0);
+        }
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.buffer.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.buffer.extend_from_slice(bytes);
+        self.views.push(view);
+        self.views.len() - 1
+    }
+
+    fn accessor(&mut self, values: &[f32], kind: &str, width: usize, bounds: bool) -> usize {
+        let bytes: Vec<u8> = values.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let view = self.view(&bytes, Some(ARRAY_BUFFER));
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len() / width,
+            "type": kind,
+        });
+        if bounds {
+            let column = |i: usize| values.iter().skip(i).step_by(width);
+            let min: Vec<f32> = (0..width)
+                .map(|i| column(i).copied().fold(f32::INFINITY, f32::min))
+                .collect();
+            let max: Vec<f32> = (0..width)
+                .map(|i| column(i).copied().fold(f32::NEG_INFINITY, f32::max))
+                .collect();
+            accessor["min"] = json!(min);
+            accessor["max"] = json!(max);
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn texture(&mut self, scene: &Scene, id: ImageId) -> Result<Option<usize>> {
+        if let Some(&index) = self.texture_indices.get(&id) {
+            return Ok(Some(index));
+        }
+        let image = match scene.image(id) {
+            Some(image) => image,
+            None => return Ok(None),
+        };
+        let mut png = std::io::Cursor::new(vec![]);
+        image::DynamicImage::ImageRgba8(image.image.clone())
+            .write_to(&mut png, image::ImageOutputFormat::Png)
+            .with_context(|| format!("failed to encode image {:?}", image.name))?;
+        let png = png.into_inner();
+
+        let index = self.images.len();
+        let mut result = json!({ "name": image.name });
+        match &mut self.files {
+            Some(files) => {
+                let file = format!("{}.png", index);
+                result["uri"] = json!(file);
+                files.push((file, png));
+            }
+            None => {
+                result["bufferView"] = json!(self.view(&png, None));
+                result["mimeType"] = json!("image/png");
+            }
+        }
+        self.images.push(result);
+        self.textures.push(json!({ "source": index }));
+        self.texture_indices.insert(id, self.textures.len() - 1);
+        Ok(Some(self.textures.len() - 1))
+    }
+
+    fn material(&mut self, scene: &Scene, id: MaterialId) -> Result<Option<usize>> {
+        if let Some(&index) = self.material_indices.get(&id) {
+            return Ok(Some(index));
+        }
+        let material = match scene.material(id) {
+            Some(material) => material,
+            None => return Ok(None),
+        };
+        let color = material.base_color;
+        let emissive = material.emissive;
+        let mut pbr = json!({
+            "baseColorFactor": [color.r, color.g, color.b, material.opacity],
+            "metallicFactor": material.metallic,
+            "roughnessFactor": material.roughness,
+        });
+        if let Some(texture) = material.base_color_texture {
+            if let Some(index) = self.texture(scene, texture)? {
+                pbr["baseColorTexture"] = json!({ "index": index });
+            }
+        }
+        if let Some(texture) = material.metallic_roughness_texture {
+            if let Some(index) = self.texture(scene, texture)? {
+                pbr["metallicRoughnessTexture"] = json!({ "index": index });
+            }
+        }
+        let mut result = json!({
+            "name": material.name,
+            "pbrMetallicRoughness": pbr,
+            "emissiveFactor": [emissive.r, emissive.g, emissive.b],
+            "doubleSided": material.double_sided,
+        });
+        if material.opacity < 1.0 {
+            result["alphaMode"] = json!("BLEND");
+        }
+        if let Some(texture) = material.normal_texture {
+            if let Some(index) = self.texture(scene, texture)? {
+                result["normalTexture"] = json!({ "index": index });
+            }
+        }
+        self.materials.push(result);
+        self.material_indices.insert(id, self.materials.len() - 1);
+        Ok(Some(self.materials.len() - 1))
+    }
+
+    fn mesh(&mut self, scene: &Scene, id: MeshId, material: Option<MaterialId>) -> Result<usize> {
+        if let Some(&index) = self.mesh_indices.get(&(id, material)) {
+            return Ok(index);
+        }
+        let mesh = scene.mesh(id).unwrap();
+        mesh.validate()?;
+        let positions: Vec<f32> = mesh
+            .positions
+            .iter()
+            .flat_map(|p| [p.x as f32, p.y as f32, p.z as f32])
+            .collect();
+        let normals: Vec<f32> = mesh
+            .normals
+            .iter()
+            .flat_map(|n| [n.x as f32, n.y as f32, n.z as f32])
+            .collect();
+        let uvs: Vec<f32> = mesh
+            .uvs
+            .iter()
+            .flat_map(|uv| [uv.x as f32, uv.y as f32])
+            .collect();
+        let mut attributes = json!({
+            "POSITION": self.accessor(&positions, "VEC3", 3, true),
+            "NORMAL": self.accessor(&normals, "VEC3", 3, false),
+            "TEXCOORD_0": self.accessor(&uvs, "VEC2", 2, false),
+        });
+        if let Some(colors) = &mesh.colors {
+            let colors: Vec<f32> = colors
+                .iter()
+                .flat_map(|c| [c.r as f32, c.g as f32, c.b as f32])
+                .collect();
+            attributes["COLOR_0"] = json!(self.accessor(&colors, "VEC3", 3, false));
+        }
+
+        let bytes: Vec<u8> = mesh.indices.iter().flat_map(|x| x.to_le_bytes()).collect();
+        let view = self.view(&bytes, Some(ELEMENT_ARRAY_BUFFER));
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_INT,
+            "count": mesh.indices.len(),
+            "type": "SCALAR",
+        }));
+        let mut primitive = json!({
+            "attributes": attributes,
+            "indices": self.accessors.len() - 1,
+        });
+        if let Some(index) = match material {
+            Some(material) => self.material(scene, material)?,
+            None => None,
+        } {
+            primitive["material"] = json!(index);
+        }
+        self.meshes.push(json!({ "primitives": [primitive] }));
+        self.mesh_indices
+            .insert((id, material), self.meshes.len() - 1);
+        Ok(self.meshes.len() - 1)
+    }
+
+    /// Writes the node and its subtree, returning the index of the node.
+    fn node(&mut self, scene: &Scene, id: NodeId) -> Result<usize> {
+        let node = scene.node(id).unwrap();
+        let index = self.nodes.len();
+        self.nodes.push(json!({ "name": node.name() }));
+
+        let transform = node.transform();
+        let (t, r, s) = (transform.translation, transform.rotation, transform.scale);
+        let mut result = json!({
+            "name": node.name(),
+            "translation": [t.x, t.y, t.z],
+            "rotation": [r.x, r.y, r.z, r.w],
+            "scale": [s.x, s.y, s.z],
+        });
+        if let Some(mesh) = node.mesh().filter(|&x| scene.mesh(x).is_some()) {
+            let mesh = self
+                .mesh(scene, mesh, node.material())
+                .with_context(|| format!("invalid mesh on node {:?}", node.name()))?;
+            result["mesh"] = json!(mesh);
+        }
+        let children = node
+            .children()
+            .iter()
+            .map(|&child| self.node(scene, child))
+            .collect::<Result<Vec<_>>>()?;
+        if !children.is_empty() {
+            result["children"] = json!(children);
+        }
+        self.nodes[index] = result;
+        Ok(index)
+    }
+
+    fn document(&self, roots: Vec<usize>, buffer_uri: Option<String>) -> Value {
+        let mut buffer = json!({ "byteLength": self.buffer.len() });
+        if let Some(uri) = buffer_uri {
+            buffer["uri"] = json!(uri);
+        }
+        let mut document = json!({
+            "asset": { "version": "2.0", "generator": "void" },
+            "scene": 0,
+            "scenes": [{ "nodes": roots }],
+            "nodes": self.nodes,
+        });
+        let arrays = [
+            ("meshes", &self.meshes),
+            ("materials", &self.materials),
+            ("textures", &self.textures),
+            ("images", &self.images),
+            ("accessors", &self.accessors),
+            ("bufferViews", &self.views),
+        ];
+        for (key, values) in arrays {
+            if !values.is_empty() {
+                document[key] = json!(values);
+            }
+        }
+        if !self.buffer.is_empty() {
+            document["buffers"] = json!([buffer]);
+        }
+        document
+    }
+}
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// Appends a GLB chunk, padded to 4 bytes with `padding`.
+fn chunk(glb: &mut Vec<u8>, kind: &[u8; 4], data: &[u8], padding: u8) {
+    let length = data.len().div_ceil(4) * 4;
+    glb.extend((length as u32).to_le_bytes());
+    glb.extend(kind);
+    glb.extend(data);
+    glb.resize(glb.len() + length - data.len(), padding);
+}
+
+/// Writes the scene as a `.glb` file with everything embedded, or as a
+/// `.gltf` file with the buffer and PNG images written next to it, named
+/// after it.
+pub fn export_gltf(path: &Path, scene: &Scene) -> Result<()> {
+    let binary = path
+        .extension()
+        .is_some_and(|x| x.eq_ignore_ascii_case("glb"));
+    let stem = path
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "scene".to_string());
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut exporter = Exporter {
+        files: (!binary).then(Vec::new),
+        ..Exporter::default()
+    };
+    let roots = scene
+        .roots()
+        .iter()
+        .map(|&root| exporter.node(scene, root))
+        .collect::<Result<Vec<_>>>()?;
+
+    let write = |path: &Path, bytes: &[u8]| {
+        std::fs::write(path, bytes).with_context(|| format!("failed to write {}", path.display()))
+    };
+    if binary {
+        let json = serde_json::to_vec(&exporter.document(roots, None))?;
+        let mut glb = vec![];
+        glb.extend(b"glTF");
+        glb.extend(2u32.to_le_bytes());
+        glb.extend(0u32.to_le_bytes());
+        chunk(&mut glb, b"JSON", &json, b' ');
+        if !exporter.buffer.is_empty() {
+            chunk(&mut glb, b"BIN\0", &exporter.buffer, 0);
+        }
+        let length = (glb.len() as u32).to_le_bytes();
+        glb[8..12].copy_from_slice(&length);
+        return write(path, &glb);
+    }
+
+    // images are named by index until here, prefix them to avoid clashes
+    let files = exporter.files.take().unwrap();
+    for image in &mut exporter.images {
+        let uri = format!("{}_{}", stem, image["uri"].as_str().unwrap());
+        image["uri"] = json!(uri);
+    }
+    let buffer_uri = format!("{}.bin", stem);
+    let document = exporter.document(roots, Some(buffer_uri.clone()));
+    if !exporter.buffer.is_empty() {
+        write(&dir.join(&buffer_uri), &exporter.buffer)?;
+    }
+    for (file, png) in files {
+        write(&dir.join(format!("{}_{}", stem, file)), &png)?;
+    }
+    write(path, &serde_json::to_vec_pretty(&document)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use std::fs;
+
+    fn data_uri(mime: &str, bytes: &[u8]) -> String {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(bytes);
+        format!("data:{};base64,{}", mime, encoded)
+    }
+
+    fn png(color: [u8; 4]) -> Vec<u8> {
+        let image = image::RgbaImage::from_pixel(2, 2, image::Rgba(color));
+        let mut bytes = std::io::Cursor::new(vec![]);
+        image::DynamicImage::ImageRgba8(image)
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .unwrap();
+        bytes.into_inner()
+    }
+
+    /// A triangle with positions and indices in a data URI and uvs in an
+    /// external buffer, one embedded and one external image.
+    fn write_sample(dir: &Path) -> std::path::PathBuf {
+        let mut inline = vec![];
+        for p in [[0.0f32, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]] {
+            p.iter().for_each(|x| inline.extend(x.to_le_bytes()));
+        }
+        for i in [0u16, 1, 2, 0] {
+            inline.extend(i.to_le_bytes());
+        }
+        let mut external = vec![];
+        for uv in [[0.0f32, 0.0], [1.0, 0.0], [0.0, 1.0]] {
+            uv.iter().for_each(|x| external.extend(x.to_le_bytes()));
+        }
+        fs::write(dir.join("extra.bin"), &external).unwrap();
+        fs::write(dir.join("normal.png"), png([128, 128, 255, 255])).unwrap();
+
+        let json = format!(
+            r#"{{
+  "asset": {{"version": "2.0"}},
+  "scene": 0,
+  "scenes": [{{"nodes": [0, 2]}}],
+  "nodes": [
+    {{"name": "parent", "translation": [0, 0, 1], "rotation": [0, 0, 0.7071068, 0.7071068], "children": [1]}},
+    {{"name": "child", "mesh": 0, "translation": [1, 0, 0]}},
+    {{"mesh": 1, "matrix": [-2, 0, 0, 0, 0, 2, 0, 0, 0, 0, 2, 0, 0, 3, 0, 1]}}
+  ],
+  "meshes": [
+    {{"name": "triangle", "primitives": [{{"attributes": {{"POSITION": 0, "TEXCOORD_0": 2}}, "indices": 1, "material": 0}}]}},
+    {{"name": "pair", "primitives": [
+      {{"attributes": {{"POSITION": 0}}, "indices": 1}},
+      {{"attributes": {{"POSITION": 0}}, "mode": 5, "material": 0}}
+    ]}}
+  ],
+  "materials": [{{
+    "name": "paint",
+    "pbrMetallicRoughness": {{"baseColorFactor": [1, 0.5, 0.25, 0.5], "baseColorTexture": {{"index": 0}}, "metallicFactor": 0.25, "roughnessFactor": 0.75}},
+    "normalTexture": {{"index": 1}},
+    "emissiveFactor": [0, 0, 1],
+    "doubleSided": true
+  }}],
+  "textures": [{{"source": 0}}, {{"source": 1}}],
+  "images": [{{"uri": "{}"}}, {{"uri": "normal.png"}}],
+  "accessors": [
+    {{"bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0]}},
+    {{"bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR"}},
+    {{"bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC2"}}
+  ],
+  "bufferViews": [
+    {{"buffer": 0, "byteOffset": 0, "byteLength": 36}},
+    {{"buffer": 0, "byteOffset": 36, "byteLength": 6}},
+    {{"buffer": 1, "byteLength": 24}}
+  ],
+  "buffers": [
+    {{"byteLength": {}, "uri": "{}"}},
+    {{"byteLength": 24, "uri": "extra.bin"}}
+  ]
+}}"#,
+            data_uri("image/png", &png([255, 0, 0, 255])),
+            inline.len(),
+            data_uri("application/octet-stream", &inline),
+        );
+        let path = dir.join("sample.gltf");
+        fs::write(&path, json).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_sample(dir.path());
+        let mut scene = Scene::new();
+        let roots = import_gltf(&path, &mut scene).unwrap();
+        assert_eq!(roots.len(), 2);
+
+        let parent = scene.node(roots[0]).unwrap();
+        assert_eq!(parent.name(), "parent");
+        let rotation = parent.transform().rotation;
+        let expected = Quat::from_axis_angle(Vec3::Z, std::f64::consts::FRAC_PI_2);
+        assert!((rotation.dot(expected) - 1.0).abs() < 1e-6);
+        let child = parent.children()[0];
+        let origin = scene.world_matrix(child).transform_point(Pnt3::ZERO);
+        assert!((origin - Pnt3::new(0.0, 1.0, 1.0)).length() < 1e-6);
+
+        let node = scene.node(child).unwrap();
+        let mesh = scene.mesh(node.mesh().unwrap()).unwrap();
+        assert_eq!(mesh.triangle_count(), 1);
+        assert_eq!(mesh.uvs[1], Vec2::new(1.0, 0.0));
+        assert_eq!(mesh.normals[0], Vec3::Z);
+
+        let material = scene.material(node.material().unwrap()).unwrap();
+        assert_eq!(material.name, "paint");
+        assert_eq!(material.base_color, RGB::new(1.0, 0.5, 0.25));
+        assert_eq!(
+            (material.opacity, material.metallic, material.roughness),
+            (0.5, 0.25, 0.75)
+        );
+        assert_eq!(material.emissive, RGB::BLUE);
+        assert!(material.double_sided);
+        let embedded = scene.image(material.base_color_texture.unwrap()).unwrap();
+        assert_eq!(embedded.path, None);
+        assert_eq!(
+            embedded.image.get_pixel(0, 0),
+            &image::Rgba([255, 0, 0, 255])
+        );
+        let external = scene.image(material.normal_texture.unwrap()).unwrap();
+        assert_eq!(external.path, Some(dir.path().join("normal.png")));
+
+        // a mirroring node matrix is decomposed with a negative scale, and
+        // each primitive gets a child
+        let multi = scene.node(roots[1]).unwrap();
+        assert_eq!(multi.name(), "node2");
+        assert_eq!(multi.transform().scale, Vec3::new(-2.0, 2.0, 2.0));
+        assert_eq!(multi.transform().rotation, Quat::IDENTITY);
+        assert_eq!(multi.transform().translation, Vec3::new(0.0, 3.0, 0.0));
+        let parts: Vec<_> = multi
+            .children()
+            .iter()
+            .map(|&x| scene.node(x).unwrap())
+            .collect();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[0].material(), None);
+        assert_eq!(parts[1].material(), node.material());
+        // the strip of three vertices is one triangle
+        let strip = scene.mesh(parts[1].mesh().unwrap()).unwrap();
+        assert_eq!(strip.indices, vec![0, 1, 2]);
+        // primitives shared between meshes are imported once per primitive
+        assert_eq!(scene.meshes().count(), 3);
+        assert_eq!(scene.images().count(), 2);
+    }
+
+    #[test]
+    fn test_missing_buffer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_sample(dir.path());
+        fs::remove_file(dir.path().join("extra.bin")).unwrap();
+        let error = import_gltf(&path, &mut Scene::new()).unwrap_err();
+        assert!(format!("{:#}", error).contains("sample.gltf"));
+    }
+
+    fn sample_scene() -> Scene {
+        let mut scene = Scene::new();
+        let parent = scene.add("parent", None);
+        scene.set_transform(
+            parent,
+            Transform {
+                translation: Vec3::new(1.0, 2.0, 3.0),
+                rotation: Quat::from_euler(Vec3::new(0.5, -0.25, 1.0)),
+                scale: Vec3::new(1.0, 2.0, 0.5),
+            },
+        );
+        let mut mesh = Mesh::cube(2.0);
+        mesh.colors = Some(vec![RGB::new(1.0, 0.5, 0.25); mesh.vertex_count()]);
+        let mesh = scene.add_mesh(mesh);
+        let image = scene.add_image(Image {
+            name: "checker".to_string(),
+            path: None,
+            image: image::RgbaImage::from_fn(2, 2, |x, y| {
+                image::Rgba([x as u8 * 255, y as u8 * 255, 0, 255])
+            }),
+        });
+        let material = scene.add_material(Material {
+            base_color: RGB::new(0.25, 0.5, 1.0),
+            opacity: 0.5,
+            base_color_texture: Some(image),
+            metallic: 0.75,
+            emissive: RGB::new(0.0, 0.5, 0.0),
+            double_sided: true,
+            ..Material::new("blue paint")
+        });
+        for (name, material) in [("first", Some(material)), ("second", None)] {
+            let child = scene.add(name, Some(parent));
+            scene.set_mesh(child, Some(mesh));
+            scene.set_material(child, material);
+        }
+        scene.add("empty", None);
+        scene
+    }
+
+    fn assert_round_trip(file: &str) {
+        let scene = sample_scene();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(file);
+        export_gltf(&path, &scene).unwrap();
+
+        let mut imported = Scene::new();
+        let roots = import_gltf(&path, &mut imported).unwrap();
+        let names = |scene: &Scene, ids: &[NodeId]| -> Vec<String> {
+            ids.iter()
+                .map(|&x| scene.node(x).unwrap().name().to_string())
+                .collect()
+        };
+        assert_eq!(names(&imported, &roots), names(&scene, scene.roots()));
+
+        let (original, result) = (scene.roots()[0], roots[0]);
+        let (a, b) = (scene.world_matrix(original), imported.world_matrix(result));
+        for col in 0..4 {
+            for row in 0..4 {
+                assert!((a.e(col, row) - b.e(col, row)).abs() < 1e-6);
+            }
+        }
+        let original = scene.node(original).unwrap().children();
+        let children = imported.node(result).unwrap().children();
+        assert_eq!(names(&imported, children), names(&scene, original));
+
+        let first = imported.node(children[0]).unwrap();
+        let second = imported.node(children[1]).unwrap();
+        let mesh = imported.mesh(first.mesh().unwrap()).unwrap();
+        assert_eq!(mesh, scene.mesh(scene.meshes().next().unwrap().0).unwrap());
+        assert_eq!(second.material(), None);
+        assert!(imported.mesh(second.mesh().unwrap()).is_some());
+
+        let material = imported.material(first.material().unwrap()).unwrap();
+        let expected = scene.materials().next().unwrap().1;
+        assert_eq!(material.name, expected.name);
+        assert_eq!(material.base_color, expected.base_color);
+        assert_eq!(
+            (material.opacity, material.metallic, material.roughness),
+            (expected.opacity, expected.metallic, expected.roughness)
+        );
+        assert_eq!(material.emissive, expected.emissive);
+        assert!(material.double_sided);
+        let image = imported
+            .image(material.base_color_texture.unwrap())
+            .unwrap();
+        assert_eq!(image.name, "checker");
+        assert_eq!(image.image, scene.images().next().unwrap().1.image);
+    }
+
+    #[test]
+    fn test_round_trip_gltf() {
+        assert_round_trip("out.gltf");
+    }
+
+    #[test]
+    fn test_round_trip_glb() {
+        assert_round_trip("out.glb");
+    }
+}
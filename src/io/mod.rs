@@ -0,0 +1,65 @@
+mod gltf;
+mod obj;
+mod project;
+
+use std::path::Path;
+
+use anyhow::*;
+
+use crate::scene::{NodeId, Scene};
+
+pub use self::gltf::{export_gltf, import_gltf};
+pub use obj::{export_obj, import_obj};
+pub use project::{is_project, Project};
+
+/// Imports a file into `scene` according to its extension, returning the
+/// top level nodes added.
+pub fn import(path: &Path, scene: &mut Scene) -> Result<Vec<NodeId>> {
+    let extension = path
+        .extension()
+        .map(|x| x.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "obj" => import_obj(path, scene),
+        "gltf" | "glb" => import_gltf(path, scene),
+        _ => bail!("unsupported file type {}", path.display()),
+    }
+}
+
+/// Exports `scene` to a file according to its extension.
+pub fn export(path: &Path, scene: &Scene) -> Result<()> {
+    let extension = path
+        .extension()
+        .map(|x| x.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "obj" => export_obj(path, scene),
+        "gltf" | "glb" => export_gltf(path, scene),
+        _ => bail!("unsupported file type {}", path.display()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::Mesh;
+
+    #[test]
+    fn test_by_extension() {
+        let mut scene = Scene::new();
+        let mesh = scene.add_mesh(Mesh::cube(1.0));
+        let node = scene.add("cube", None);
+        scene.set_mesh(node, Some(mesh));
+
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["cube.obj", "cube.gltf", "cube.GLB"] {
+            let path = dir.path().join(name);
+            export(&path, &scene).unwrap();
+            let mut imported = Scene::new();
+            assert_eq!(import(&path, &mut imported).unwrap().len(), 1);
+        }
+        let path = dir.path().join("cube.fbx");
+        assert!(export(&path, &scene).is_err());
+        assert!(import(&path, &mut Scene::new()).is_err());
+    }
+}
@@ -0,0 +1,826 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::*;
+
+use crate::prim::{Pnt3, Vec2, Vec3, RGB};
+use crate::scene::{Image, ImageId, Material, MaterialId, Mesh, NodeId, Scene};
+
+/// Zero based attribute indices of a face corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct Corner {
+    v: usize,
+    vt: Option<usize>,
+    vn: Option<usize>,
+}
+
+/// Faces of a group sharing one material.
+struct Part {
+    material: Option<(String, usize)>,
+    triangles: Vec<[Corner; 3]>,
+}
+
+/// Faces following an `o` or `g` statement.
+struct Group {
+    name: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Default)]
+struct Obj {
+    positions: Vec<Pnt3>,
+    /// Vertex colors of the `v x y z r g b` extension, dropped unless every
+    /// position has one.
+    colors: Vec<Option<RGB>>,
+    uvs: Vec<Vec2>,
+    normals: Vec<Vec3>,
+    groups: Vec<Group>,
+    libraries: Vec<(String, usize)>,
+}
+
+fn location(file: &Path, line: usize) -> String {
+    format!("{}:{}", file.display(), line)
+}
+
+fn numbers<const N: usize>(args: &[&str]) -> Result<[f64; N]> {
+    ensure!(
+        args.len() >= N,
+        "expected {} numbers, found {}",
+        N,
+        args.len()
+    );
+    let mut result = [0.0; N];
+    for (x, arg) in result.iter_mut().zip(args) {
+        *x = arg
+            .parse()
+            .with_context(|| format!("invalid number {:?}", arg))?;
+    }
+    Ok(result)
+}
+
+/// Resolves a one based, possibly negative, OBJ index into `count` items.
+fn index(arg: &str, count: usize) -> Result<usize> {
+    let i: i64 = arg
+        .parse()
+        .with_context(|| format!("invalid index {:?}", arg))?;
+    let resolved = match i {
+        i if i > 0 => i - 1,
+        i if i < 0 => count as i64 + i,
+        _ => bail!("index 0 is not allowed"),
+    };
+    ensure!(
+        (0..count as i64).contains(&resolved),
+        "index {} is out of range for {} items",
+        i,
+        count
+    );
+    Ok(resolved as usize)
+}
+
+impl Obj {
+    fn parse(source: &str, file: &Path) -> Result<Obj> {
+        let mut obj = Obj::default();
+        let default_name = file
+            .file_stem()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        obj.groups.push(Group {
+            name: default_name,
+            parts: vec![],
+        });
+        for (number, line) in source.lines().enumerate() {
+            obj.parse_line(line, number + 1)
+                .with_context(|| location(file, number + 1))?;
+        }
+        Ok(obj)
+    }
+
+    fn parse_line(&mut self, line: &str, number: usize) -> Result<()> {
+        let line = line.split('#').next().unwrap().trim();
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        match keyword {
+            "v" => {
+                let [x, y, z] = numbers(&args)?;
+                self.positions.push(Pnt3::new(x, y, z));
+                let color = match args.len() {
+                    6.. => {
+                        let [r, g, b] = numbers(&args[3..])?;
+                        Some(RGB::new(r, g, b))
+                    }
+                    _ => None,
+                };
+                self.colors.push(color);
+            }
+            "vt" => {
+                let [u] = numbers(&args)?;
+                let v = match args.get(1) {
+                    Some(_) => numbers::<1>(&args[1..])?[0],
+                    None => 0.0,
+                };
+                self.uvs.push(Vec2::new(u, v));
+            }
+            "vn" => {
+                let [x, y, z] = numbers(&args)?;
+                self.normals.push(Vec3::new(x, y, z));
+            }
+            "f" => {
+                let corners = args
+                    .iter()
+                    .map(|arg| self.corner(arg))
+                    .collect::<Result<Vec<_>>>()?;
+                ensure!(
+                    corners.len() >= 3,
+                    "face needs at least 3 vertices, found {}",
+                    corners.len()
+                );
+                let points: Vec<Pnt3> = corners.iter().map(|c| self.positions[c.v]).collect();
+                let triangles = triangulate(&points)
+                    .into_iter()
+                    .map(|[a, b, c]| [corners[a], corners[b], corners[c]]);
+                let group = self.groups.last_mut().unwrap();
+                if group.parts.is_empty() {
+                    group.parts.push(Part {
+                        material: None,
+                        triangles: vec![],
+                    });
+                }
+                group.parts.last_mut().unwrap().triangles.extend(triangles);
+            }
+            "o" | "g" => {
+                let name = rest.to_string();
+                match self.groups.last_mut().unwrap() {
+                    // an object usually names the group that follows it
+                    group if group.parts.iter().all(|x| x.triangles.is_empty()) => {
+                        if !name.is_empty() {
+                            group.name = name;
+                        }
+                    }
+                    group => {
+                        // keep the material active across groups
+                        let material = group.parts.last().and_then(|x| x.material.clone());
+                        self.groups.push(Group {
+                            name,
+                            parts: vec![Part {
+                                material,
+                                triangles: vec![],
+                            }],
+                        });
+                    }
+                }
+            }
+            "usemtl" => {
+                ensure!(!rest.is_empty(), "missing material name");
+                let group = self.groups.last_mut().unwrap();
+                let material = Some((rest.to_string(), number));
+                match group.parts.last_mut() {
+                    Some(part) if part.triangles.is_empty() => part.material = material,
+                    _ => group.parts.push(Part {
+                        material,
+                        triangles: vec![],
+                    }),
+                }
+            }
+            "mtllib" => {
+                ensure!(!rest.is_empty(), "missing material library");
+                self.libraries.push((rest.to_string(), number));
+            }
+            // smoothing groups, lines, points and curves are not supported
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn corner(&self, arg: &str) -> Result<Corner> {
+        let mut fields = arg.split('/');
+        let v = index(fields.next().unwrap(), self.positions.len())?;
+        let vt = match fields.next() {
+            Some(x) if !x.is_empty() => Some(index(x, self.uvs.len())?),
+            _ => None,
+        };
+        let vn = match fields.next() {
+            Some(x) if !x.is_empty() => Some(index(x, self.normals.len())?),
+            _ => None,
+        };
+        Ok(Corner { v, vt, vn })
+    }
+
+    fn mesh(&self, triangles: &[[Corner; 3]]) -> Mesh {
+        let has_colors = self.colors.iter().all(Option::is_some);
+        let has_normals = triangles.iter().flatten().all(|x| x.vn.is_some());
+        let mut mesh = Mesh {
+            colors: has_colors.then(Vec::new),
+            ..Mesh::default()
+        };
+        let mut vertices = HashMap::new();
+        for corner in triangles.iter().flatten() {
+            let index = match vertices.get(corner) {
+                Some(&index) => index,
+                None => {
+                    let index = mesh.positions.len() as u32;
+                    mesh.positions.push(self.positions[corner.v]);
+                    mesh.uvs
+                        .push(corner.vt.map(|i| self.uvs[i]).unwrap_or_default());
+                    mesh.normals
+                        .push(corner.vn.map(|i| self.normals[i]).unwrap_or(Vec3::ZERO));
+                    if let Some(colors) = &mut mesh.colors {
+                        colors.push(self.colors[corner.v].unwrap());
+                    }
+                    vertices.insert(*corner, index);
+                    index
+                }
+            };
+            mesh.indices.push(index);
+        }
+        if !has_normals {
+            mesh.compute_normals();
+        }
+        mesh
+    }
+}
+
+/// Splits a planar polygon into triangles by ear clipping, keeping its
+/// winding. Falls back to a fan for degenerate polygons.
+fn triangulate(points: &[Pnt3]) -> Vec<[usize; 3]> {
+    let n = points.len();
+    let fan = |indices: &[usize]| -> Vec<[usize; 3]> {
+        (1..indices.len() - 1)
+            .map(|i| [indices[0], indices[i], indices[i + 1]])
+            .collect()
+    };
+    if n <= 3 {
+        return fan(&(0..n).collect::<Vec<_>>());
+    }
+
+    // project along the dominant axis of the Newell normal
+    let mut normal = Vec3::ZERO;
+    for i in 0..n {
+        let (a, b) = (points[i], points[(i + 1) % n]);
+        normal += Vec3::new(
+            (a.y - b.y) * (a.z + b.z),
+            (a.z - b.z) * (a.x + b.x),
+            (a.x - b.x) * (a.y + b.y),
+        );
+    }
+    if normal.length2() == 0.0 {
+        return fan(&(0..n).collect::<Vec<_>>());
+    }
+    // drop the dominant axis, flipping one coordinate when the polygon
+    // faces away so it winds counter-clockwise in the plane
+    let (ax, ay, az) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+    let projected: Vec<Vec2> = points
+        .iter()
+        .map(|p| {
+            if az >= ax && az >= ay {
+                Vec2::new(p.x, p.y * normal.z.signum())
+            } else if ax >= ay {
+                Vec2::new(p.y, p.z * normal.x.signum())
+            } else {
+                Vec2::new(p.z, p.x * normal.y.signum())
+            }
+        })
+        .collect();
+    let p = |i: usize| projected[i];
+
+    let mut remaining: Vec<usize> = (0..n).collect();
+    let mut triangles = vec![];
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let ear = (0..count).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + count - 1) % count],
+                remaining[i],
+                remaining[(i + 1) % count],
+            );
+            let (pa, pb, pc) = (p(a), p(b), p(c));
+            if (pb - pa).cross(pc - pb) <= 0.0 {
+                return false;
+            }
+            remaining
+                .iter()
+                .all(|&x| x == a || x == b || x == c || !inside_triangle(p(x), pa, pb, pc))
+        });
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + count - 1) % count],
+                    remaining[i],
+                    remaining[(i + 1) % count],
+                ]);
+                remaining.remove(i);
+            }
+            None => break,
+        }
+    }
+    triangles.extend(fan(&remaining));
+    triangles
+}
+
+fn inside_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    (b - a).cross(p - a) >= 0.0 && (c - b).cross(p - b) >= 0.0 && (a - c).cross(p - c) >= 0.0
+}
+
+/// Strips the options of a texture map statement, leaving the file name.
+fn texture_file(rest: &str) -> &str {
+    let mut rest = rest;
+    while rest.starts_with('-') {
+        let (_option, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        rest = tail.trim_start();
+        // option values are numbers or on/off switches, possibly several
+        while let Some((value, tail)) = rest.split_once(char::is_whitespace) {
+            if value.parse::<f64>().is_err() && value != "on" && value != "off" {
+                break;
+            }
+            rest = tail.trim_start();
+        }
+    }
+    rest.trim()
+}
+
+/// Material library being read, textures are loaded into the scene as they
+/// are found.
+struct Mtl<'a> {
+    dir: &'a Path,
+    scene: &'a mut Scene,
+    images: &'a mut HashMap<PathBuf, ImageId>,
+    materials: Vec<Material>,
+}
+
+impl Mtl<'_> {
+    fn parse(
+        source: &str,
+        file: &Path,
+        scene: &mut Scene,
+        images: &mut HashMap<PathBuf, ImageId>,
+    ) -> Result<Vec<Material>> {
+        let mut mtl = Mtl {
+            dir: file.parent().unwrap_or_else(|| Path::new("")),
+            scene,
+            images,
+            materials: vec![],
+        };
+        for (number, line) in source.lines().enumerate() {
+            mtl.parse_line(line)
+                .with_context(|| location(file, number + 1))?;
+        }
+        Ok(mtl.materials)
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let line = line.split('#').next().unwrap().trim();
+        let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let rest = rest.trim();
+        let args: Vec<&str> = rest.split_whitespace().collect();
+        if keyword == "newmtl" {
+            ensure!(!rest.is_empty(), "missing material name");
+            self.materials.push(Material::new(rest));
+            return Ok(());
+        }
+        let texture = match keyword {
+            "map_Kd" => Some(self.texture(rest)?),
+            _ => None,
+        };
+        let material = match (keyword, self.materials.last_mut()) {
+            (_, Some(material)) => material,
+            ("Kd" | "d" | "Tr" | "map_Kd", None) => bail!("{} before newmtl", keyword),
+            (_, None) => return Ok(()),
+        };
+        match keyword {
+            "Kd" => {
+                let [r, g, b] = numbers(&args)?;
+                material.base_color = RGB::new(r, g, b);
+            }
+            "d" => material.opacity = numbers::<1>(&args)?[0],
+            "Tr" => material.opacity = 1.0 - numbers::<1>(&args)?[0],
+            "map_Kd" => material.base_color_texture = texture,
+            // other maps and the Phong parameters have no equivalent yet
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn texture(&mut self, rest: &str) -> Result<ImageId> {
+        let name = texture_file(rest);
+        ensure!(!name.is_empty(), "missing texture file");
+        let path = self.dir.join(name.replace('\\', "/"));
+        if let Some(&id) = self.images.get(&path) {
+            return Ok(id);
+        }
+        let image =
+            image::open(&path).with_context(|| format!("failed to load {}", path.display()))?;
+        let id = self.scene.add_image(Image {
+            name: name.to_string(),
+            path: Some(path.clone()),
+            image: image.to_rgba8(),
+        });
+        self.images.insert(path, id);
+        Ok(id)
+    }
+}
+
+/// Adds the objects of an OBJ file to `scene` as top level nodes, returned
+/// in file order. Polygons are triangulated, and groups using several
+/// materials get one child node per material.
+pub fn import_obj(path: &Path, scene: &mut Scene) -> Result<Vec<NodeId>> {
+    let source =
+        fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+    let obj = Obj::parse(&source, path)?;
+
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut materials: HashMap<String, MaterialId> = HashMap::new();
+    let mut images = HashMap::new();
+    for (library, number) in &obj.libraries {
+        let file = dir.join(library);
+        let source = fs::read_to_string(&file)
+            .with_context(|| format!("failed to read material library {}", file.display()))
+            .with_context(|| location(path, *number))?;
+        for material in Mtl::parse(&source, &file, scene, &mut images)? {
+            let name = material.name.clone();
+            materials.insert(name, scene.add_material(material));
+        }
+    }
+
+    let mut roots = vec![];
+    for group in &obj.groups {
+        let parts: Vec<&Part> = group
+            .parts
+            .iter()
+            .filter(|x| !x.triangles.is_empty())
+            .collect();
+        if parts.is_empty() {
+            continue;
+        }
+        let node = scene.add(group.name.as_str(), None);
+        roots.push(node);
+        for part in &parts {
+            let material = match &part.material {
+                Some((name, number)) => Some(
+                    *materials
+                        .get(name)
+                        .with_context(|| format!("unknown material {:?}", name))
+                        .with_context(|| location(path, *number))?,
+                ),
+                None => None,
+            };
+            let target = match parts.len() {
+                1 => node,
+                _ => {
+                    let name = part.material.as_ref().map_or("default", |x| x.0.as_str());
+                    scene.add(name, Some(node))
+                }
+            };
+            let mesh = scene.add_mesh(obj.mesh(&part.triangles));
+            scene.set_mesh(target, Some(mesh));
+            scene.set_material(target, material);
+        }
+    }
+    Ok(roots)
+}
+
+/// Writes every mesh node of `scene` as an object of an OBJ file, with world
+/// transforms applied since OBJ has no hierarchy. Materials go to an MTL
+/// file next to it, and textures not loaded from disk are saved as PNG.
+pub fn export_obj(path: &Path, scene: &Scene) -> Result<()> {
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    let stem = path
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "scene".to_string());
+    let mtl_path = path.with_extension("mtl");
+
+    // material names must be unique within the library
+    let mut names: HashMap<MaterialId, String> = HashMap::new();
+    let mut used: Vec<MaterialId> = vec![];
+    for (id, _) in scene.walk() {
+        let node = scene.node(id).unwrap();
+        if let (Some(_), Some(material)) = (node.mesh(), node.material()) {
+            if scene.material(material).is_some() && !names.contains_key(&material) {
+                let base = scene
+                    .material(material)
+                    .unwrap()
+                    .name
+                    .replace(char::is_whitespace, "_");
+                let base = if base.is_empty() {
+                    "material".to_string()
+                } else {
+                    base
+                };
+                let mut name = base.clone();
+                let mut suffix = 1;
+                while names.values().any(|x| *x == name) {
+                    name = format!("{}.{}", base, suffix);
+                    suffix += 1;
+                }
+                names.insert(material, name);
+                used.push(material);
+            }
+        }
+    }
+
+    let mut obj = String::new();
+    if !used.is_empty() {
+        writeln!(
+            obj,
+            "mtllib {}",
+            mtl_path.file_name().unwrap().to_string_lossy()
+        )?;
+    }
+    let mut offset = 1;
+    for (id, _) in scene.walk() {
+        let node = scene.node(id).unwrap();
+        let mesh = match node.mesh().and_then(|x| scene.mesh(x)) {
+            Some(mesh) => mesh,
+            None => continue,
+        };
+        mesh.validate()
+            .with_context(|| format!("invalid mesh on node {:?}", node.name()))?;
+        let world = scene.world_matrix(id);
+        let normal_matrix = world.normal_matrix();
+        let (x, y, z) = (
+            world.transform_vector(Vec3::X),
+            world.transform_vector(Vec3::Y),
+            world.transform_vector(Vec3::Z),
+        );
+        let mirrored = x.dot(y.cross(z)) < 0.0;
+
+        let name = if node.name().is_empty() {
+            "object"
+        } else {
+            node.name()
+        };
+        writeln!(obj, "o {}", name)?;
+        for (i, &p) in mesh.positions.iter().enumerate() {
+            let p = world.transform_point(p);
+            match &mesh.colors {
+                Some(colors) => {
+                    let c = colors[i];
+                    writeln!(obj, "v {} {} {} {} {} {}", p.x, p.y, p.z, c.r, c.g, c.b)?
+                }
+                None => writeln!(obj, "v {} {} {}", p.x, p.y, p.z)?,
+            }
+        }
+        for uv in &mesh.uvs {
+            writeln!(obj, "vt {} {}", uv.x, uv.y)?;
+        }
+        for &n in &mesh.normals {
+            let n = normal_matrix.transform_vector(n);
+            let n = if n.length2() > 0.0 { n.normalize() } else { n };
+            writeln!(obj, "vn {} {} {}", n.x, n.y, n.z)?;
+        }
+        if let Some(material) = node.material().and_then(|x| names.get(&x)) {
+            writeln!(obj, "usemtl {}", material)?;
+        }
+        for triangle in mesh.indices.chunks_exact(3) {
+            let mut triangle = [triangle[0], triangle[1], triangle[2]];
+            if mirrored {
+                triangle.swap(1, 2);
+            }
+            let [a, b, c] = triangle.map(|i| i as usize + offset);
+            writeln!(obj, "f {a}/{a}/{a} {b}/{b}/{b} {c}/{c}/{c}")?;
+        }
+        offset += mesh.vertex_count();
+    }
+    fs::write(path, obj).with_context(|| format!("failed to write {}", path.display()))?;
+
+    if used.is_empty() {
+        return Ok(());
+    }
+    let mut mtl = String::new();
+    let mut textures: HashMap<ImageId, String> = HashMap::new();
+    for id in used {
+        let material = scene.material(id).unwrap();
+        let color = material.base_color;
+        writeln!(mtl, "newmtl {}", names[&id])?;
+        writeln!(mtl, "Kd {} {} {}", color.r, color.g, color.b)?;
+        writeln!(mtl, "d {}", material.opacity)?;
+        let image = material
+            .base_color_texture
+            .and_then(|x| scene.image(x).map(|image| (x, image)));
+        if let Some((image_id, image)) = image {
+            if !textures.contains_key(&image_id) {
+                let file = match &image.path {
+                    Some(path) if path.exists() => path
+                        .strip_prefix(dir)
+                        .unwrap_or(path)
+                        .to_string_lossy()
+                        .into_owned(),
+                    _ => {
+                        let file = format!("{}_{}.png", stem, textures.len());
+                        let path = dir.join(&file);
+                        image
+                            .image
+                            .save(&path)
+                            .with_context(|| format!("failed to write {}", path.display()))?;
+                        file
+                    }
+                };
+                textures.insert(image_id, file);
+            }
+            writeln!(mtl, "map_Kd {}", textures[&image_id])?;
+        }
+        writeln!(mtl)?;
+    }
+    fs::write(&mtl_path, mtl).with_context(|| format!("failed to write {}", mtl_path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::Transform;
+
+    fn area(points: &[Pnt3], triangles: &[[usize; 3]]) -> f64 {
+        triangles
+            .iter()
+            .map(|&[a, b, c]| (points[b] - points[a]).cross(points[c] - points[a]).z / 2.0)
+            .sum()
+    }
+
+    #[test]
+    fn test_triangulate() {
+        // an L shape, concave at the fourth corner
+        let points = [
+            Pnt3::new(0.0, 0.0, 0.0),
+            Pnt3::new(2.0, 0.0, 0.0),
+            Pnt3::new(2.0, 1.0, 0.0),
+            Pnt3::new(1.0, 1.0, 0.0),
+            Pnt3::new(1.0, 2.0, 0.0),
+            Pnt3::new(0.0, 2.0, 0.0),
+        ];
+        let triangles = triangulate(&points);
+        assert_eq!(triangles.len(), 4);
+        assert_eq!(area(&points, &triangles), 3.0);
+
+        // the same outline seen from behind keeps its clockwise winding
+        let reversed: Vec<Pnt3> = points.iter().rev().copied().collect();
+        let triangles = triangulate(&reversed);
+        assert_eq!(area(&reversed, &triangles), -3.0);
+    }
+
+    #[test]
+    fn test_import() {
+        let dir = tempfile::tempdir().unwrap();
+        let obj = "\
+mtllib scene.mtl
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+o Quad
+usemtl red
+f 1/1/1 2/2/1 3/3/1 4/4/1
+g Strip
+usemtl red
+f -4 -3 -2
+usemtl textured
+f -4 -2 -1
+";
+        let mtl = "\
+newmtl red
+Kd 1 0 0
+d 0.5
+
+newmtl textured
+map_Kd -s 1 1 1 checker.png
+";
+        fs::write(dir.path().join("scene.obj"), obj).unwrap();
+        fs::write(dir.path().join("scene.mtl"), mtl).unwrap();
+        image::RgbaImage::new(2, 2)
+            .save(dir.path().join("checker.png"))
+            .unwrap();
+
+        let mut scene = Scene::new();
+        let roots = import_obj(&dir.path().join("scene.obj"), &mut scene).unwrap();
+        assert_eq!(roots.len(), 2);
+
+        let quad = scene.node(roots[0]).unwrap();
+        assert_eq!(quad.name(), "Quad");
+        let mesh = scene.mesh(quad.mesh().unwrap()).unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        assert_eq!(mesh.triangle_count(), 2);
+        let corner = mesh
+            .positions
+            .iter()
+            .position(|&p| p == Pnt3::new(1.0, 1.0, 0.0))
+            .unwrap();
+        assert_eq!(mesh.uvs[corner], Vec2::new(1.0, 1.0));
+        assert!(mesh.normals.iter().all(|&n| n == Vec3::Z));
+        let red = scene.material(quad.material().unwrap()).unwrap();
+        assert_eq!((red.base_color, red.opacity), (RGB::RED, 0.5));
+
+        // a group with two materials gets a child node for each
+        let strip = scene.node(roots[1]).unwrap();
+        assert_eq!(strip.name(), "Strip");
+        assert!(strip.mesh().is_none());
+        assert_eq!(strip.children().len(), 2);
+        let textured = scene.node(strip.children()[1]).unwrap();
+        assert_eq!(textured.name(), "textured");
+        let material = scene.material(textured.material().unwrap()).unwrap();
+        let image = scene.image(material.base_color_texture.unwrap()).unwrap();
+        assert_eq!(image.image.dimensions(), (2, 2));
+        // faces without normals get computed ones
+        let mesh = scene.mesh(textured.mesh().unwrap()).unwrap();
+        assert_eq!(mesh.normals[0], Vec3::Z);
+    }
+
+    #[test]
+    fn test_errors_report_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let cases = [
+            ("v 0 0 0\nv 1 0 0\nf 1 2 3\n", "bad.obj:3"),
+            ("v 0 0\n", "bad.obj:1"),
+            ("\nmtllib missing.mtl\n", "bad.obj:2"),
+            ("v 0 0 0\nusemtl nothing\nf 1 1 1\n", "bad.obj:2"),
+        ];
+        for (source, expected) in cases {
+            let path = dir.path().join("bad.obj");
+            fs::write(&path, source).unwrap();
+            let error = import_obj(&path, &mut Scene::new()).unwrap_err();
+            let message = format!("{:#}", error);
+            assert!(message.contains(expected), "{}", message);
+        }
+        assert!(import_obj(&dir.path().join("none.obj"), &mut Scene::new()).is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut scene = Scene::new();
+        let parent = scene.add("parent", None);
+        scene.set_transform(
+            parent,
+            Transform {
+                translation: Vec3::new(0.0, 0.0, 5.0),
+                ..Transform::IDENTITY
+            },
+        );
+        let cube = scene.add("cube", Some(parent));
+        scene.set_transform(
+            cube,
+            Transform {
+                scale: Vec3::new(-1.0, 1.0, 1.0),
+                translation: Vec3::new(1.0, 0.0, 0.0),
+                ..Transform::IDENTITY
+            },
+        );
+        let mesh = scene.add_mesh(Mesh::cube(2.0));
+        scene.set_mesh(cube, Some(mesh));
+        let image = scene.add_image(Image {
+            name: "checker".to_string(),
+            path: None,
+            image: image::RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 255])),
+        });
+        let material = scene.add_material(Material {
+            base_color: RGB::new(0.25, 0.5, 1.0),
+            base_color_texture: Some(image),
+            ..Material::new("blue paint")
+        });
+        scene.set_material(cube, Some(material));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.obj");
+        export_obj(&path, &scene).unwrap();
+
+        let mut imported = Scene::new();
+        let roots = import_obj(&path, &mut imported).unwrap();
+        assert_eq!(roots.len(), 1);
+        let node = imported.node(roots[0]).unwrap();
+        assert_eq!(node.name(), "cube");
+        let result = imported.mesh(node.mesh().unwrap()).unwrap();
+        let original = scene.mesh(mesh).unwrap();
+        assert_eq!(result.vertex_count(), original.vertex_count());
+        assert_eq!(result.triangle_count(), original.triangle_count());
+        // vertices may be reordered, but each keeps its attributes
+        let world = scene.world_matrix(cube);
+        for (p, uv) in result.positions.iter().zip(&result.uvs) {
+            assert!(original
+                .positions
+                .iter()
+                .zip(&original.uvs)
+                .any(|(a, b)| world.transform_point(*a) == *p && b == uv));
+        }
+
+        // the mirrored transform keeps triangles facing outwards
+        let center = Pnt3::new(1.0, 0.0, 5.0);
+        for triangle in result.indices.chunks_exact(3) {
+            let p = |i: usize| result.positions[triangle[i] as usize];
+            let normal = (p(1) - p(0)).cross(p(2) - p(0));
+            assert!(normal.dot(p(0) - center) > 0.0);
+            assert!(normal.dot(result.normals[triangle[0] as usize]) > 0.0);
+        }
+
+        let material = imported.material(node.material().unwrap()).unwrap();
+        assert_eq!(material.name, "blue_paint");
+        assert_eq!(material.base_color, RGB::new(0.25, 0.5, 1.0));
+        let image = imported
+            .image(material.base_color_texture.unwrap())
+            .unwrap();
+        assert_eq!(image.image.get_pixel(1, 1), &image::Rgba([255, 0, 0, 255]));
+    }
+}
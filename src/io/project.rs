@@ -0,0 +1,592 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::path::{Path, PathBuf};
+
+use anyhow::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::interface::{LayoutState, PanelState};
+use crate::prim::RGB;
+use crate::scene::{Image, ImageId, Material, MaterialId, Mesh, MeshId, NodeId, Scene, Transform};
+
+/// Format version written by this build. Bump it whenever the saved
+/// structures change, and teach [`upgrade`] to read the previous one.
+pub const VERSION: u32 = 2;
+
+/// Start of binary project files, followed by the bincode encoded project.
+const MAGIC: &[u8; 4] = b"VOID";
+
+/// Extension of binary project files.
+pub const BINARY_EXTENSION: &str = "voidb";
+/// Extension of text project files.
+pub const TEXT_EXTENSION: &str = "void";
+
+/// Everything needed to restore a session: the scene and the workspace
+/// showing it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    /// First, so it can be read before knowing how to decode the rest.
+    pub version: u32,
+    pub scene: SceneData,
+    pub layout: LayoutState,
+    /// Panels in layout order, the state of viewports holds their camera.
+    pub panels: Vec<PanelState>,
+}
+
+/// Scene contents with ids replaced by indices into the lists.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SceneData {
+    roots: Vec<NodeData>,
+    meshes: Vec<Mesh>,
+    materials: Vec<MaterialData>,
+    images: Vec<ImageData>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct NodeData {
+    name: String,
+    transform: Transform,
+    mesh: Option<usize>,
+    material: Option<usize>,
+    children: Vec<NodeData>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct MaterialData {
+    name: String,
+    base_color: RGB,
+    opacity: f64,
+    base_color_texture: Option<usize>,
+    metallic: f64,
+    roughness: f64,
+    metallic_roughness_texture: Option<usize>,
+    normal_texture: Option<usize>,
+    emissive: RGB,
+    double_sided: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct ImageData {
+    name: String,
+    path: Option<PathBuf>,
+    /// PNG encoded pixels, so projects do not depend on image files.
+    #[serde(with = "serde_bytes")]
+    png: Vec<u8>,
+}
+
+/// Ids sorted, so files do not depend on hash map order.
+fn sorted<T: Copy + Ord>(ids: impl Iterator<Item = T>) -> Vec<T> {
+    let mut ids: Vec<T> = ids.collect();
+    ids.sort();
+    ids
+}
+
+fn index_of<T: Copy + Eq + Hash>(ids: &[T]) -> HashMap<T, usize> {
+    ids.iter().enumerate().map(|(i, &id)| (id, i)).collect()
+}
+
+/// Fails if `index` is outside a list of `len` items.
+fn check(index: Option<usize>, len: usize, what: &str) -> Result<()> {
+    match index {
+        Some(i) if i >= len => bail!("refers to {} {}, but there are only {}", what, i, len),
+        _ => Ok(()),
+    }
+}
+
+fn node_data(
+    scene: &Scene,
+    id: NodeId,
+    meshes: &HashMap<MeshId, usize>,
+    materials: &HashMap<MaterialId, usize>,
+) -> NodeData {
+    let node = scene.node(id).unwrap();
+    NodeData {
+        name: node.name().to_string(),
+        transform: *node.transform(),
+        mesh: node.mesh().and_then(|x| meshes.get(&x).copied()),
+        material: node.material().and_then(|x| materials.get(&x).copied()),
+        children: node
+            .children()
+            .iter()
+            .map(|&child| node_data(scene, child, meshes, materials))
+            .collect(),
+    }
+}
+
+impl SceneData {
+    pub fn new(scene: &Scene) -> Result<SceneData> {
+        let meshes = sorted(scene.meshes().map(|(id, _)| id));
+        let materials = sorted(scene.materials().map(|(id, _)| id));
+        let images = sorted(scene.images().map(|(id, _)| id));
+        let (mesh_index, material_index) = (index_of(&meshes), index_of(&materials));
+        let image_index = index_of(&images);
+        let image = |id: Option<ImageId>| id.and_then(|x| image_index.get(&x).copied());
+
+        let mut data = SceneData {
+            meshes: meshes
+                .iter()
+                .map(|&id| scene.mesh(id).unwrap().clone())
+                .collect(),
+            ..SceneData::default()
+        };
+        for &id in &materials {
+            let material = scene.material(id).unwrap();
+            data.materials.push(MaterialData {
+                name: material.name.clone(),
+                base_color: material.base_color,
+                opacity: material.opacity,
+                base_color_texture: image(material.base_color_texture),
+                metallic: material.metallic,
+                roughness: material.roughness,
+                metallic_roughness_texture: image(material.metallic_roughness_texture),
+                normal_texture: image(material.normal_texture),
+                emissive: material.emissive,
+                double_sided: material.double_sided,
+            });
+        }
+        for &id in &images {
+            let image = scene.image(id).unwrap();
+            let mut png = std::io::Cursor::new(vec![]);
+            image::DynamicImage::ImageRgba8(image.image.clone())
+                .write_to(&mut png, image::ImageOutputFormat::Png)
+                .with_context(|| format!("failed to encode image {:?}", image.name))?;
+            data.images.push(ImageData {
+                name: image.name.clone(),
+                path: image.path.clone(),
+                png: png.into_inner(),
+            });
+        }
+
+        data.roots = scene
+            .roots()
+            .iter()
+            .map(|&root| node_data(scene, root, &mesh_index, &material_index))
+            .collect();
+        Ok(data)
+    }
+
+    /// Checks the indices and meshes, so a broken file is rejected before
+    /// anything is added to a scene.
+    fn validate(&self) -> Result<()> {
+        for (i, mesh) in self.meshes.iter().enumerate() {
+            mesh.validate()
+                .with_context(|| format!("invalid mesh {}", i))?;
+        }
+        for material in &self.materials {
+            let textures = [
+                material.base_color_texture,
+                material.metallic_roughness_texture,
+                material.normal_texture,
+            ];
+            for texture in textures {
+                check(texture, self.images.len(), "image")
+                    .with_context(|| format!("invalid material {:?}", material.name))?;
+            }
+        }
+        let mut pending: Vec<&NodeData> = self.roots.iter().collect();
+        while let Some(node) = pending.pop() {
+            check(node.mesh, self.meshes.len(), "mesh")
+                .and_then(|_| check(node.material, self.materials.len(), "material"))
+                .with_context(|| format!("invalid node {:?}", node.name))?;
+            pending.extend(&node.children);
+        }
+        Ok(())
+    }
+
+    /// Adds the saved nodes to `scene`, returning the top level ones.
+    pub fn load(&self, scene: &mut Scene) -> Result<Vec<NodeId>> {
+        self.validate()?;
+        let mut pixels = vec![];
+        for image in &self.images {
+            let decoded = image::load_from_memory_with_format(&image.png, image::ImageFormat::Png)
+                .with_context(|| format!("invalid image {:?}", image.name))?;
+            pixels.push(decoded.into_rgba8());
+        }
+
+        let images: Vec<ImageId> = self
+            .images
+            .iter()
+            .zip(pixels)
+            .map(|(data, image)| {
+                scene.add_image(Image {
+                    name: data.name.clone(),
+                    path: data.path.clone(),
+                    image,
+                })
+            })
+            .collect();
+        let image = |index: Option<usize>| index.map(|i| images[i]);
+        let materials: Vec<_> = self
+            .materials
+            .iter()
+            .map(|data| {
+                scene.add_material(Material {
+                    name: data.name.clone(),
+                    base_color: data.base_color,
+                    opacity: data.opacity,
+                    base_color_texture: image(data.base_color_texture),
+                    metallic: data.metallic,
+                    roughness: data.roughness,
+                    metallic_roughness_texture: image(data.metallic_roughness_texture),
+                    normal_texture: image(data.normal_texture),
+                    emissive: data.emissive,
+                    double_sided: data.double_sided,
+                })
+            })
+            .collect();
+        let meshes: Vec<_> = self
+            .meshes
+            .iter()
+            .map(|mesh| scene.add_mesh(mesh.clone()))
+            .collect();
+
+        let mut roots = vec![];
+        let mut pending: Vec<(&NodeData, Option<NodeId>)> =
+            self.roots.iter().rev().map(|x| (x, None)).collect();
+        while let Some((data, parent)) = pending.pop() {
+            let id = scene.add(data.name.clone(), parent);
+            scene.set_transform(id, data.transform);
+            scene.set_mesh(id, data.mesh.map(|i| meshes[i]));
+            scene.set_material(id, data.material.map(|i| materials[i]));
+            if parent.is_none() {
+                roots.push(id);
+            }
+            pending.extend(data.children.iter().rev().map(|x| (x, Some(id))));
+        }
+        Ok(roots)
+    }
+}
+
+/// Decoder of the project body in one of the file formats.
+enum Body<'a> {
+    Text(&'a str),
+    Binary(&'a [u8]),
+}
+
+impl Body<'_> {
+    fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        match self {
+            Body::Text(text) => Ok(ron::from_str(text)?),
+            Body::Binary(bytes) => Ok(bincode::deserialize(bytes)?),
+        }
+    }
+}
+
+/// Only the version of a project, read before the rest.
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+/// Format 1, which stored node rotations as Euler angles.
+mod v1 {
+    use serde::Deserialize;
+
+    use super::{ImageData, MaterialData};
+    use crate::interface::{LayoutState, PanelState};
+    use crate::prim::{Quat, Vec3};
+    use crate::scene::Mesh;
+
+    #[derive(Deserialize)]
+    pub struct Project {
+        version: u32,
+        scene: SceneData,
+        layout: LayoutState,
+        panels: Vec<PanelState>,
+    }
+
+    #[derive(Deserialize)]
+    struct SceneData {
+        roots: Vec<NodeData>,
+        meshes: Vec<Mesh>,
+        materials: Vec<MaterialData>,
+        images: Vec<ImageData>,
+    }
+
+    #[derive(Deserialize)]
+    struct NodeData {
+        name: String,
+        transform: Transform,
+        mesh: Option<usize>,
+        material: Option<usize>,
+        children: Vec<NodeData>,
+    }
+
+    #[derive(Deserialize)]
+    struct Transform {
+        translation: Vec3,
+        /// Euler angles in radians, applied around x, then y, then z.
+        rotation: Vec3,
+        scale: Vec3,
+    }
+
+    impl From<NodeData> for super::NodeData {
+        fn from(node: NodeData) -> Self {
+            let Transform {
+                translation,
+                rotation,
+                scale,
+            } = node.transform;
+            super::NodeData {
+                name: node.name,
+                transform: crate::scene::Transform {
+                    translation,
+                    rotation: Quat::from_euler(rotation),
+                    scale,
+                },
+                mesh: node.mesh,
+                material: node.material,
+                children: node.children.into_iter().map(Into::into).collect(),
+            }
+        }
+    }
+
+    impl From<Project> for super::Project {
+        fn from(project: Project) -> Self {
+            let scene = project.scene;
+            super::Project {
+                version: project.version,
+                scene: super::SceneData {
+                    roots: scene.roots.into_iter().map(Into::into).collect(),
+                    meshes: scene.meshes,
+                    materials: scene.materials,
+                    images: scene.images,
+                },
+                layout: project.layout,
+                panels: project.panels,
+            }
+        }
+    }
+}
+
+/// Decodes a project saved with format `version`. When the format changes,
+/// keep the previous structures in a module named after their version and
+/// add an arm here that decodes them and converts them to the next version,
+/// so old files keep loading.
+fn upgrade(version: u32, body: &Body) -> Result<Project> {
+    match version {
+        VERSION => body.decode(),
+        1 => body.decode::<v1::Project>().map(Into::into),
+        0 => bail!("invalid format version 0"),
+        _ => bail!(
+            "format version {} is newer than the supported version {}, \
+             the project was saved by a newer build",
+            version,
+            VERSION
+        ),
+    }
+}
+
+impl Project {
+    pub fn new(scene: &Scene, layout: LayoutState, panels: Vec<PanelState>) -> Result<Project> {
+        Ok(Project {
+            version: VERSION,
+            scene: SceneData::new(scene)?,
+            layout,
+            panels,
+        })
+    }
+
+    /// Encodes the project as RON text.
+    pub fn to_text(&self) -> Result<String> {
+        let config = ron::ser::PrettyConfig::new().indentor("  ".to_string());
+        Ok(ron::ser::to_string_pretty(self, config)?)
+    }
+
+    /// Encodes the project in the compact binary format.
+    pub fn to_binary(&self) -> Result<Vec<u8>> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(bincode::serialize(self)?);
+        Ok(bytes)
+    }
+
+    /// Decodes a project in either format, upgrading older versions.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Project> {
+        let body = match bytes.strip_prefix(MAGIC) {
+            Some(bytes) => Body::Binary(bytes),
+            None => Body::Text(
+                std::str::from_utf8(bytes).context("neither a binary project nor UTF-8 text")?,
+            ),
+        };
+        let header: Header = body.decode().context("missing format version")?;
+        let project = upgrade(header.version, &body)?;
+        Ok(Project {
+            version: VERSION,
+            ..project
+        })
+    }
+
+    /// Writes the project, in the binary format if the extension is
+    /// [`BINARY_EXTENSION`] and as text otherwise.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let binary = path
+            .extension()
+            .is_some_and(|x| x.eq_ignore_ascii_case(BINARY_EXTENSION));
+        let bytes = match binary {
+            true => self.to_binary()?,
+            false => self.to_text()?.into_bytes(),
+        };
+        std::fs::write(path, bytes).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn load(path: &Path) -> Result<Project> {
+        let bytes =
+            std::fs::read(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Project::from_bytes(&bytes).with_context(|| format!("failed to load {}", path.display()))
+    }
+}
+
+/// Whether `path` names a project file rather than a file to import.
+pub fn is_project(path: &Path) -> bool {
+    path.extension().is_some_and(|x| {
+        x.eq_ignore_ascii_case(TEXT_EXTENSION) || x.eq_ignore_ascii_case(BINARY_EXTENSION)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prim::{Quat, Vec3};
+
+    fn scene() -> Scene {
+        let mut scene = Scene::new();
+        let parent = scene.add("parent", None);
+        scene.set_transform(
+            parent,
+            Transform {
+                rotation: Quat::from_euler(Vec3::new(0.1, 0.2, 0.3)),
+                translation: Vec3::new(1.0, 2.0, 3.0),
+                ..Transform::IDENTITY
+            },
+        );
+        let mesh = scene.add_mesh(Mesh::cube(1.0));
+        let image = scene.add_image(Image {
+            name: "checker".to_string(),
+            path: Some(PathBuf::from("textures/checker.png")),
+            image: image::RgbaImage::from_fn(2, 2, |x, y| image::Rgba([x as u8, y as u8, 7, 255])),
+        });
+        let material = scene.add_material(Material {
+            base_color_texture: Some(image),
+            roughness: 0.25,
+            ..Material::new("paint")
+        });
+        for name in ["a", "b"] {
+            let child = scene.add(name, Some(parent));
+            scene.set_mesh(child, Some(mesh));
+            scene.set_material(child, Some(material));
+        }
+        let grandchild = scene.add("c", Some(scene.roots()[0]));
+        scene.add("d", Some(grandchild));
+        scene.add("other", None);
+        scene
+    }
+
+    fn project() -> Project {
+        let layout = LayoutState::Leaf;
+        let panels = vec![PanelState {
+            kind: "viewport".to_string(),
+            state: "(x: 1)".to_string(),
+        }];
+        Project::new(&scene(), layout, panels).unwrap()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let project = project();
+        let text = project.to_text().unwrap();
+        assert_eq!(Project::from_bytes(text.as_bytes()).unwrap(), project);
+        let binary = project.to_binary().unwrap();
+        assert!(binary.len() < text.len());
+        assert_eq!(Project::from_bytes(&binary).unwrap(), project);
+
+        // loading and saving again gives the same data
+        let mut loaded = Scene::new();
+        let roots = project.scene.load(&mut loaded).unwrap();
+        assert_eq!(roots.len(), 2);
+        assert_eq!(SceneData::new(&loaded).unwrap(), project.scene);
+        let names: Vec<&str> = loaded
+            .walk()
+            .into_iter()
+            .map(|(id, _)| loaded.node(id).unwrap().name())
+            .collect();
+        assert_eq!(names, ["parent", "a", "b", "c", "d", "other"]);
+        let image = loaded.images().next().unwrap().1;
+        assert_eq!(image.image.get_pixel(1, 0), &image::Rgba([1, 0, 7, 255]));
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = project();
+        for file in ["scene.void", "scene.voidb"] {
+            let path = dir.path().join(file);
+            project.save(&path).unwrap();
+            assert_eq!(Project::load(&path).unwrap(), project);
+        }
+        let error = Project::load(&dir.path().join("missing.void")).unwrap_err();
+        assert!(format!("{:#}", error).contains("missing.void"));
+    }
+
+    #[test]
+    fn test_upgrade_v1() {
+        let text = "(
+          version: 1,
+          scene: (
+            roots: [(
+              name: \"parent\",
+              transform: (
+                translation: (x: 1.0, y: 2.0, z: 3.0),
+                rotation: (x: 0.1, y: 0.2, z: 0.3),
+                scale: (x: 1.0, y: 1.0, z: 1.0),
+              ),
+              mesh: None,
+              material: None,
+              children: [],
+            )],
+            meshes: [],
+            materials: [],
+            images: [],
+          ),
+          layout: Leaf,
+          panels: [],
+        )";
+        let project = Project::from_bytes(text.as_bytes()).unwrap();
+        assert_eq!(project.version, VERSION);
+        let mut scene = Scene::new();
+        let roots = project.scene.load(&mut scene).unwrap();
+        let transform = scene.node(roots[0]).unwrap().transform();
+        let expected = Quat::from_euler(Vec3::new(0.1, 0.2, 0.3));
+        assert_eq!(transform.translation, Vec3::new(1.0, 2.0, 3.0));
+        assert!((transform.rotation.dot(expected) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_errors() {
+        let message = |bytes: &[u8]| format!("{:#}", Project::from_bytes(bytes).unwrap_err());
+        let text = project().to_text().unwrap();
+
+        let newer = text.replacen("version: 2", "version: 7", 1);
+        assert!(message(newer.as_bytes()).contains("newer"));
+        let mut binary = project().to_binary().unwrap();
+        binary[4] = 7;
+        assert!(message(&binary).contains("newer"));
+
+        assert!(Project::from_bytes(&text.as_bytes()[..text.len() / 2]).is_err());
+        assert!(Project::from_bytes(&project().to_binary().unwrap()[..100]).is_err());
+        assert!(message(b"\xff\xfe").contains("UTF-8"));
+        assert!(message(b"(layout: Leaf)").contains("version"));
+
+        // indices are checked before the scene is touched
+        let broken = text.replacen("mesh: Some(0)", "mesh: Some(5)", 1);
+        let project = Project::from_bytes(broken.as_bytes()).unwrap();
+        let mut scene = Scene::new();
+        let error = format!("{:#}", project.scene.load(&mut scene).unwrap_err());
+        assert!(
+            error.contains("\"a\"") && error.contains("mesh 5"),
+            "{}",
+            error
+        );
+        assert!(scene.is_empty());
+    }
+}
@@ -1,15 +1,127 @@
+#[cfg(test)]
+mod golden;
 mod interface;
+mod io;
 mod outliner;
 mod prim;
+mod scene;
 mod viewport;
 
-use interface::Window;
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result};
+use interface::{render_panel, ContextGlobal, Panel, PanelType, Window, Workspace};
+
+/// Image rendered instead of opening the window.
+enum Capture {
+    /// The scene seen through a viewport, as a preview of the files.
+    Thumbnail(PathBuf),
+    /// The whole window with its panels.
+    Screenshot(PathBuf),
+}
+
+impl Capture {
+    /// Takes `--thumbnail <png>` or `--screenshot <png>` from the front of
+    /// the arguments.
+    fn parse(args: &mut Vec<PathBuf>) -> Option<Capture> {
+        let capture = match args.first()?.to_str()? {
+            "--thumbnail" => Capture::Thumbnail,
+            "--screenshot" => Capture::Screenshot,
+            _ => return None,
+        };
+        let path = args.get(1)?.clone();
+        args.drain(..2);
+        Some(capture(path))
+    }
+
+    fn render(&self, window: &mut Window) -> Result<()> {
+        let (path, image) = match self {
+            Capture::Thumbnail(path) => {
+                let context = headless(256, 256, window)?;
+                let mut viewport = viewport::Viewport::default();
+                // look at the scene the way the first viewport does
+                let project = window.project()?;
+                if let Some(saved) = project.panels.iter().find(|x| x.kind == "viewport") {
+                    viewport.load_state(&saved.state)?;
+                }
+                viewport.init(&context);
+                (path, render_panel(&mut viewport, &context)?)
+            }
+            Capture::Screenshot(path) => {
+                let context = headless(1280, 800, window)?;
+                window.init(&context);
+                (path, window.render_image(&context)?)
+            }
+        };
+        image
+            .save(path)
+            .with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+/// Context drawing the scene of `window` into images of the given size.
+fn headless(width: u32, height: u32, window: &Window) -> Result<ContextGlobal> {
+    let size = winit::dpi::PhysicalSize::new(width, height);
+    pollster::block_on(ContextGlobal::init_headless(size, window.scene()))
+}
+
+fn register_panels(window: &mut Window) {
+    window.register(PanelType {
+        kind: "outliner",
+        name: "Outliner",
+        icon: '≡',
+        create: || Box::new(outliner::Outliner::default()),
+    });
+    window.register(PanelType {
+        kind: "viewport",
+        name: "Viewport",
+        icon: '▣',
+        create: || Box::new(viewport::Viewport::default()),
+    });
+}
 
 fn main() {
     // std::env::set_var("RUST_BACKTRACE", "1");
+    env_logger::init();
+
+    let mut paths: Vec<PathBuf> = std::env::args_os().skip(1).map(PathBuf::from).collect();
+    let capture = Capture::parse(&mut paths);
 
     let mut window = Window::new();
-    window.append(Box::new(outliner::Outliner::default()));
-    window.append(Box::new(viewport::Viewport::default()));
+    register_panels(&mut window);
+    // captures look the same whatever workspace was used last
+    match capture {
+        Some(_) => window
+            .set_workspace(&Workspace::presets()[0], None)
+            .unwrap(),
+        None => window.restore_workspace(),
+    }
+
+    // a project file replaces the workspace, other files are imported
+    for path in &paths {
+        let result = if io::is_project(path) {
+            window.set_project_path(path);
+            io::Project::load(path).and_then(|project| window.open(&project))
+        } else {
+            io::import(path, &mut window.scene().borrow_mut()).map(|_| ())
+        };
+        if let Err(error) = result {
+            log::error!("{:#}", error);
+        }
+    }
+    if paths.is_empty() {
+        let scene = window.scene();
+        let mut scene = scene.borrow_mut();
+        let cube = scene.add_mesh(scene::Mesh::cube(1.0));
+        let node = scene.add("Cube", None);
+        scene.set_mesh(node, Some(cube));
+    }
+    if let Some(capture) = capture {
+        if let Err(error) = capture.render(&mut window) {
+            log::error!("{:#}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
     window.run();
 }
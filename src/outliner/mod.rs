@@ -1,3 +1,3 @@
 mod outliner;
 
-pub use outliner::Outliner;
\ No newline at end of file
+pub use outliner::Outliner;
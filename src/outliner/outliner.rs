@@ -1,21 +1,203 @@
+use std::collections::HashSet;
+
 use wgpu::util::DeviceExt;
 
 use crate::interface::*;
-use crate::prim::{Rect, RGB};
+use crate::prim::{Pnt2, Rect, Vec2, RGB};
+use crate::scene::{NodeId, Scene};
+
+/// Row metrics in pixels.
+const ROW_HEIGHT: f64 = 20.0;
+const INDENT: f64 = 16.0;
+const PADDING: f64 = 4.0;
+const ARROW_SIZE: f64 = 8.0;
+const ICON_SIZE: f64 = 10.0;
+const FONT_SIZE: f64 = 13.0;
+
+const BACKGROUND: RGB = RGB::new(0.16, 0.16, 0.16);
+const ROW_ALTERNATE: RGB = RGB::new(0.19, 0.19, 0.19);
+const ROW_SELECTED: RGB = RGB::new(0.28, 0.4, 0.6);
+const ARROW: RGB = RGB::new(0.7, 0.7, 0.7);
+const ICON: RGB = RGB::new(0.9, 0.6, 0.3);
+const LABEL: RGB = RGB::new(0.85, 0.85, 0.85);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Row {
+    id: NodeId,
+    depth: usize,
+    has_children: bool,
+    expanded: bool,
+}
+
+/// Part of a row under the cursor.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Hit {
+    Arrow(NodeId),
+    Label(NodeId),
+}
 
+/// Tree view of the scene, one row per node below every expanded parent.
 #[derive(Default)]
 pub struct Outliner {
     pipeline: Option<wgpu::RenderPipeline>,
+    text: Option<TextRenderer>,
+    collapsed: HashSet<NodeId>,
+    /// Visible rows, rebuilt when the scene revision changes.
+    rows: Vec<Row>,
+    revision: Option<u64>,
+    /// Pixels scrolled down from the first row.
+    scroll: f64,
+    modifiers: ModifiersState,
+    size: Vec2,
+}
+
+impl Outliner {
+    fn sync(&mut self, scene: &Scene) {
+        if self.revision == Some(scene.revision()) {
+            return;
+        }
+        self.revision = Some(scene.revision());
+        self.collapsed.retain(|&id| scene.contains(id));
+
+        self.rows.clear();
+        let mut hidden_below = None;
+        for (id, depth) in scene.walk() {
+            match hidden_below {
+                Some(d) if depth > d => continue,
+                _ => hidden_below = None,
+            }
+            let expanded = !self.collapsed.contains(&id);
+            if !expanded {
+                hidden_below = Some(depth);
+            }
+            self.rows.push(Row {
+                id,
+                depth,
+                has_children: !scene.node(id).unwrap().children().is_empty(),
+                expanded,
+            });
+        }
+        self.scroll_by(0.0);
+    }
+
+    fn scroll_by(&mut self, pixels: f64) {
+        let max = (self.rows.len() as f64 * ROW_HEIGHT - self.size.y).max(0.0);
+        self.scroll = (self.scroll + pixels).clamp(0.0, max);
+    }
+
+    /// Area of a row in panel pixels, possibly outside the panel.
+    fn row_rect(&self, index: usize) -> Rect {
+        let top = self.size.y + self.scroll - index as f64 * ROW_HEIGHT;
+        Rect::from_corner(
+            Pnt2::new(0.0, top - ROW_HEIGHT),
+            Pnt2::new(self.size.x, top),
+        )
+    }
+
+    /// Left edge of the arrow column of a row.
+    fn indent(row: &Row) -> f64 {
+        PADDING + row.depth as f64 * INDENT
+    }
+
+    fn hit(&self, pos: Pnt2) -> Option<Hit> {
+        if pos.y < 0.0 || pos.y >= self.size.y {
+            return None;
+        }
+        let index = ((self.size.y + self.scroll - pos.y) / ROW_HEIGHT).floor() as usize;
+        let row = self.rows.get(index)?;
+        let x = pos.x - Self::indent(row);
+        if row.has_children && (0.0..INDENT).contains(&x) {
+            Some(Hit::Arrow(row.id))
+        } else {
+            Some(Hit::Label(row.id))
+        }
+    }
+
+    fn click(&mut self, pos: Pnt2, scene: &mut Scene) {
+        match self.hit(pos) {
+            Some(Hit::Arrow(id)) => {
+                if !self.collapsed.remove(&id) {
+                    self.collapsed.insert(id);
+                }
+                self.revision = None;
+            }
+            Some(Hit::Label(id)) if self.modifiers.ctrl() || self.modifiers.shift() => {
+                scene.toggle_selected(id)
+            }
+            Some(Hit::Label(id)) => scene.select(id),
+            None => scene.clear_selection(),
+        }
+    }
+
+    fn draw(&self, scene: &Scene, buffer: &mut DrawBuffer) {
+        let panel = Rect::from_corner(Pnt2::ZERO, Pnt2::new(self.size.x, self.size.y));
+        buffer.rect(panel, BACKGROUND);
+        let font = self.text.as_ref().unwrap().font();
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let rect = self.row_rect(index);
+            if rect.min.y >= panel.max.y || rect.max.y <= panel.min.y {
+                continue;
+            }
+            let clipped = Rect::from_corner(
+                Pnt2::new(rect.min.x, rect.min.y.max(panel.min.y)),
+                Pnt2::new(rect.max.x, rect.max.y.min(panel.max.y)),
+            );
+            if scene.is_selected(row.id) {
+                buffer.rect(clipped, ROW_SELECTED);
+            } else if index % 2 == 1 {
+                buffer.rect(clipped, ROW_ALTERNATE);
+            }
+
+            // partially visible rows only get their background
+            if clipped.extent().y < ROW_HEIGHT {
+                continue;
+            }
+            let center = Pnt2::new(Self::indent(row) + INDENT / 2.0, rect.center().y);
+            let h = ARROW_SIZE / 2.0;
+            if row.has_children && row.expanded {
+                buffer.triangle(
+                    center + Vec2::new(-h, h / 2.0),
+                    center + Vec2::new(h, h / 2.0),
+                    center + Vec2::new(0.0, -h),
+                    ARROW,
+                );
+            } else if row.has_children {
+                buffer.triangle(
+                    center + Vec2::new(-h / 2.0, h),
+                    center + Vec2::new(-h / 2.0, -h),
+                    center + Vec2::new(h, 0.0),
+                    ARROW,
+                );
+            }
+            let icon = Pnt2::new(Self::indent(row) + INDENT + ICON_SIZE / 2.0, center.y);
+            buffer.rect(
+                Rect::from_center(icon, Vec2::new(ICON_SIZE, ICON_SIZE)),
+                ICON,
+            );
+
+            let name = scene.node(row.id).unwrap().name();
+            let baseline = Pnt2::new(
+                icon.x + ICON_SIZE / 2.0 + PADDING,
+                font.centered_baseline(center.y, FONT_SIZE),
+            );
+            buffer.text(baseline, name, FONT_SIZE, LABEL);
+        }
+    }
 }
 
 impl Panel for Outliner {
+    fn kind(&self) -> &'static str {
+        "outliner"
+    }
+
     fn init(&mut self, context: &ContextGlobal) {
         let device = &context.device;
         let config = &context.config;
 
         let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
             label: Some("Pure Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("pure.wgsl").into()),
+            source: wgpu::ShaderSource::Wgsl(include_str!("../interface/pure.wgsl").into()),
         });
 
         let render_pipeline_layout =
@@ -60,11 +242,47 @@ impl Panel for Outliner {
                 multiview: None,
             }),
         );
+        self.text = Some(TextRenderer::new(context));
     }
 
-    fn render(&self, rect: Rect, context: &Context) -> wgpu::CommandBuffer {
+    fn resize(&mut self, size: Vec2, _context: &ContextGlobal) {
+        self.size = size;
+        self.scroll_by(0.0);
+    }
+
+    fn handle_event(&mut self, event: &Event, context: &ContextGlobal) {
+        let mut scene = context.scene.borrow_mut();
+        self.sync(&scene);
+        match *event {
+            Event::ModifiersChanged(state) => self.modifiers = state,
+            Event::MouseInput {
+                pos,
+                button: MouseButton::Left,
+                state: ElementState::Pressed,
+            } => self.click(pos, &mut scene),
+            Event::MouseWheel { delta, .. } => self.scroll_by(-delta.y * ROW_HEIGHT * 3.0),
+            _ => {}
+        }
+    }
+
+    fn save_state(&self) -> String {
+        ron::to_string(&self.scroll).unwrap()
+    }
+
+    fn load_state(&mut self, state: &str) -> anyhow::Result<()> {
+        self.scroll = ron::from_str(state)?;
+        Ok(())
+    }
+
+    fn render(&mut self, context: &Context) -> wgpu::CommandBuffer {
+        let scene = context.global.scene.borrow();
+        self.sync(&scene);
         let mut buffer = DrawBuffer::new();
-        buffer.rect(rect, RGB::BLUE);
+        self.draw(&scene, &mut buffer);
+        let text = self.text.as_mut().unwrap();
+        text.layout(&mut buffer);
+        text.prepare(&context.global.queue);
+        buffer.remap_to_ndc(self.size);
 
         let mut encoder =
             context
@@ -93,23 +311,125 @@ impl Panel for Outliner {
                     usage: wgpu::BufferUsages::INDEX,
                 });
 
-        let mut renderpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Outliner Render Pass"),
-            color_attachments: &[wgpu::RenderPassColorAttachment {
-                view: &context.local.view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: None,
+        let text_buffers = (!buffer.indices_text.is_empty()).then(|| {
+            let device = &context.global.device;
+            let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Outliner Text Vertex Buffer"),
+                contents: bytemuck::cast_slice(&buffer.vertices_text),
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+            let indices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("Outliner Text Index Buffer"),
+                contents: bytemuck::cast_slice(&buffer.indices_text),
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            (vertices, indices)
         });
+
+        let mut renderpass = context.begin_render_pass(
+            &mut encoder,
+            &wgpu::RenderPassDescriptor {
+                label: Some("Outliner Render Pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &context.local.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            },
+        );
         renderpass.set_pipeline(self.pipeline.as_ref().unwrap());
         renderpass.set_vertex_buffer(0, vertex_buffer_pure.slice(..));
         renderpass.set_index_buffer(index_buffer_pure.slice(..), wgpu::IndexFormat::Uint16);
         renderpass.draw_indexed(0..buffer.indices_pure.len() as u32, 0, 0..1);
+        if let Some((vertices, indices)) = &text_buffers {
+            self.text.as_ref().unwrap().draw(
+                &mut renderpass,
+                vertices,
+                indices,
+                buffer.indices_text.len() as u32,
+            );
+        }
         drop(renderpass);
         encoder.finish()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outliner(height: f64) -> Outliner {
+        Outliner {
+            size: Vec2::new(200.0, height),
+            ..Default::default()
+        }
+    }
+
+    /// Position in the label area of the `index`th row.
+    fn label(index: usize) -> Pnt2 {
+        Pnt2::new(150.0, 100.0 - (index as f64 + 0.5) * ROW_HEIGHT)
+    }
+
+    #[test]
+    fn test_rows_follow_scene() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let b = scene.add("b", Some(a));
+        let mut outliner = outliner(100.0);
+        outliner.sync(&scene);
+        assert_eq!(outliner.rows.len(), 2);
+        assert!(outliner.rows[0].has_children);
+        assert_eq!(outliner.rows[1].depth, 1);
+
+        let c = scene.add("c", None);
+        scene.remove(b);
+        outliner.sync(&scene);
+        let ids: Vec<_> = outliner.rows.iter().map(|x| x.id).collect();
+        assert_eq!(ids, vec![a, c]);
+        assert!(!outliner.rows[0].has_children);
+    }
+
+    #[test]
+    fn test_collapse_and_select() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let b = scene.add("b", Some(a));
+        scene.add("c", Some(b));
+        let d = scene.add("d", None);
+        let mut outliner = outliner(100.0);
+        outliner.sync(&scene);
+        assert_eq!(outliner.rows.len(), 4);
+
+        let arrow = Pnt2::new(PADDING + INDENT / 2.0, label(0).y);
+        assert_eq!(outliner.hit(arrow), Some(Hit::Arrow(a)));
+        outliner.click(arrow, &mut scene);
+        outliner.sync(&scene);
+        assert_eq!(outliner.rows.len(), 2);
+        assert!(!outliner.rows[0].expanded);
+
+        outliner.click(label(1), &mut scene);
+        assert_eq!(scene.selection(), &[d]);
+        outliner.click(label(3), &mut scene);
+        assert!(scene.selection().is_empty());
+    }
+
+    #[test]
+    fn test_scroll() {
+        let mut scene = Scene::new();
+        let ids: Vec<_> = (0..10).map(|i| scene.add(format!("{}", i), None)).collect();
+        let mut outliner = outliner(100.0);
+        outliner.sync(&scene);
+
+        outliner.scroll_by(1000.0);
+        assert_eq!(outliner.scroll, 10.0 * ROW_HEIGHT - 100.0);
+        assert_eq!(outliner.hit(label(0)), Some(Hit::Label(ids[5])));
+        outliner.scroll_by(-1000.0);
+        assert_eq!(outliner.scroll, 0.0);
+        assert_eq!(outliner.hit(label(0)), Some(Hit::Label(ids[0])));
+        assert_eq!(outliner.hit(Pnt2::new(10.0, -1.0)), None);
+    }
+}
@@ -1,6 +1,8 @@
-use super::{point::Pnt2, vector::Vec2};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug)]
+use super::{point::Pnt2, point::Pnt3, vector::Vec2, vector::Vec3, Mat4};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
     pub min: Pnt2,
     pub max: Pnt2,
@@ -30,6 +32,10 @@ impl Rect {
         self.max
     }
 
+    pub fn contains(&self, p: Pnt2) -> bool {
+        p.x >= self.min.x && p.x < self.max.x && p.y >= self.min.y && p.y < self.max.y
+    }
+
     pub fn empty() -> Rect {
         Rect {
             min: Pnt2::ZERO,
@@ -50,4 +56,247 @@ impl Rect {
             max: center + extent / 2.0,
         }
     }
+
+    /// Whether the rect covers no area, like [`Rect::empty`].
+    pub fn is_empty(&self) -> bool {
+        self.min.x >= self.max.x || self.min.y >= self.max.y
+    }
+
+    /// Overlap of both rects, `None` if they do not share any area.
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let rect = Rect {
+            min: self.min.max(other.min),
+            max: self.max.min(other.max),
+        };
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    /// Smallest rect containing both, ignoring empty ones.
+    pub fn union(&self, other: &Rect) -> Rect {
+        match (self.is_empty(), other.is_empty()) {
+            (_, true) => *self,
+            (true, false) => *other,
+            (false, false) => Rect {
+                min: self.min.min(other.min),
+                max: self.max.max(other.max),
+            },
+        }
+    }
+
+    /// Moves each side inwards by `amount`, collapsing to the center line
+    /// where the rect is too small.
+    pub fn inset(&self, amount: f64) -> Rect {
+        let center = self.center();
+        let min = self.min + Vec2::new(amount, amount);
+        let max = self.max - Vec2::new(amount, amount);
+        Rect {
+            min: min.min(center),
+            max: max.max(center),
+        }
+    }
+
+    /// Moves each side outwards by `amount`.
+    pub fn outset(&self, amount: f64) -> Rect {
+        self.inset(-amount)
+    }
+
+    /// Left and right part, the left one taking `fraction` of the width.
+    pub fn split_horizontal(&self, fraction: f64) -> (Rect, Rect) {
+        let x = self.min.x + self.extent().x * fraction.clamp(0.0, 1.0);
+        (
+            Rect::from_corner(self.min, Pnt2::new(x, self.max.y)),
+            Rect::from_corner(Pnt2::new(x, self.min.y), self.max),
+        )
+    }
+
+    /// Top and bottom part, the top one taking `fraction` of the height.
+    pub fn split_vertical(&self, fraction: f64) -> (Rect, Rect) {
+        let y = self.max.y - self.extent().y * fraction.clamp(0.0, 1.0);
+        (
+            Rect::from_corner(Pnt2::new(self.min.x, y), self.max),
+            Rect::from_corner(self.min, Pnt2::new(self.max.x, y)),
+        )
+    }
+
+    /// Closest point to `p` within the rect, including its far edges.
+    pub fn clamp(&self, p: Pnt2) -> Pnt2 {
+        p.max(self.min).min(self.max)
+    }
+}
+
+/// Axis aligned box in 3D. The empty box has its minimum above its maximum,
+/// so that expanding it by a point gives a box around just that point.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Aabb3 {
+    pub min: Pnt3,
+    pub max: Pnt3,
+}
+
+impl Aabb3 {
+    pub const EMPTY: Aabb3 = Aabb3 {
+        min: Pnt3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        max: Pnt3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+    };
+
+    /// Smallest box containing all `points`, empty if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Pnt3>) -> Aabb3 {
+        points.into_iter().fold(Aabb3::EMPTY, Aabb3::expand)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    pub fn center(&self) -> Pnt3 {
+        self.min.lerp(self.max, 0.5)
+    }
+
+    pub fn extent(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Grows the box to include `p`.
+    pub fn expand(self, p: Pnt3) -> Aabb3 {
+        Aabb3 {
+            min: self.min.min(p),
+            max: self.max.max(p),
+        }
+    }
+
+    /// Box around this one transformed by the affine matrix `m`.
+    pub fn transform(&self, m: &Mat4) -> Aabb3 {
+        if self.is_empty() {
+            return *self;
+        }
+        // the half extent along each new axis sums the absolute projections
+        // of the old half extents
+        let center = m.transform_point(self.center());
+        let half = self.extent() / 2.0;
+        let mut extent = Vec3::ZERO;
+        for row in 0..3 {
+            extent[row] = (0..3).map(|col| m.e(col, row).abs() * half[col]).sum();
+        }
+        Aabb3 {
+            min: center - extent,
+            max: center + extent,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prim::Quat;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Rect {
+        Rect::from_corner(Pnt2::new(x0, y0), Pnt2::new(x1, y1))
+    }
+
+    #[test]
+    fn test_contains() {
+        let r = rect(0.0, 0.0, 10.0, 5.0);
+        assert!(r.contains(Pnt2::new(0.0, 0.0)));
+        assert!(r.contains(Pnt2::new(9.5, 4.5)));
+        // the far edges belong to the neighbours
+        assert!(!r.contains(Pnt2::new(10.0, 2.0)));
+        assert!(!r.contains(Pnt2::new(2.0, 5.0)));
+        assert!(!Rect::empty().contains(Pnt2::ZERO));
+    }
+
+    #[test]
+    fn test_intersect_union() {
+        let a = rect(0.0, 0.0, 10.0, 5.0);
+        let b = rect(5.0, 2.0, 20.0, 10.0);
+        assert_eq!(a.intersect(&b), Some(rect(5.0, 2.0, 10.0, 5.0)));
+        assert_eq!(a.intersect(&a), Some(a));
+        assert_eq!(a.union(&b), rect(0.0, 0.0, 20.0, 10.0));
+        // touching edges share no area
+        assert_eq!(a.intersect(&rect(10.0, 0.0, 20.0, 5.0)), None);
+        assert_eq!(a.intersect(&rect(30.0, 30.0, 40.0, 40.0)), None);
+
+        let far = rect(30.0, 30.0, 40.0, 40.0);
+        assert!(Rect::empty().is_empty());
+        assert_eq!(a.intersect(&Rect::empty()), None);
+        assert_eq!(far.union(&Rect::empty()), far);
+        assert_eq!(Rect::empty().union(&far), far);
+        assert!(Rect::empty().union(&Rect::empty()).is_empty());
+    }
+
+    #[test]
+    fn test_inset() {
+        let r = rect(0.0, 0.0, 10.0, 4.0);
+        assert_eq!(r.inset(1.0), rect(1.0, 1.0, 9.0, 3.0));
+        assert_eq!(r.outset(1.0), rect(-1.0, -1.0, 11.0, 5.0));
+        assert_eq!(r.inset(-1.0), r.outset(1.0));
+        // too small to inset that far, the height collapses to its middle
+        assert_eq!(r.inset(3.0), rect(3.0, 2.0, 7.0, 2.0));
+        assert!(r.inset(3.0).is_empty());
+        assert_eq!(Rect::empty().inset(1.0), Rect::empty());
+        assert_eq!(Rect::empty().outset(1.0), rect(-1.0, -1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_split() {
+        let r = rect(0.0, 0.0, 10.0, 4.0);
+        assert_eq!(
+            r.split_horizontal(0.3),
+            (rect(0.0, 0.0, 3.0, 4.0), rect(3.0, 0.0, 10.0, 4.0))
+        );
+        assert_eq!(
+            r.split_vertical(0.25),
+            (rect(0.0, 3.0, 10.0, 4.0), rect(0.0, 0.0, 10.0, 3.0))
+        );
+        let (left, right) = r.split_horizontal(1.5);
+        assert_eq!((left, right.is_empty()), (r, true));
+        let (top, bottom) = Rect::empty().split_vertical(0.5);
+        assert!(top.is_empty() && bottom.is_empty());
+    }
+
+    #[test]
+    fn test_clamp() {
+        let r = rect(0.0, 0.0, 10.0, 4.0);
+        assert_eq!(r.clamp(Pnt2::new(5.0, 2.0)), Pnt2::new(5.0, 2.0));
+        assert_eq!(r.clamp(Pnt2::new(-5.0, 7.0)), Pnt2::new(0.0, 4.0));
+        assert_eq!(r.clamp(Pnt2::new(12.0, -1.0)), Pnt2::new(10.0, 0.0));
+        assert_eq!(Rect::empty().clamp(Pnt2::new(3.0, -3.0)), Pnt2::ZERO);
+    }
+
+    #[test]
+    fn test_aabb3() {
+        let a = Aabb3::from_points([Pnt3::new(1.0, 0.0, -1.0), Pnt3::new(-1.0, 2.0, 1.0)]);
+        assert_eq!(a.min, Pnt3::new(-1.0, 0.0, -1.0));
+        assert_eq!(a.max, Pnt3::new(1.0, 2.0, 1.0));
+        assert_eq!(a.center(), Pnt3::new(0.0, 1.0, 0.0));
+        assert_eq!(a.extent(), Vec3::new(2.0, 2.0, 2.0));
+
+        assert!(Aabb3::EMPTY.is_empty());
+        assert!(Aabb3::from_points([]).is_empty());
+        let p = Pnt3::new(1.0, 2.0, 3.0);
+        assert_eq!(Aabb3::EMPTY.expand(p), Aabb3 { min: p, max: p });
+        let b = a.expand(Pnt3::new(3.0, 1.0, 1.0));
+        assert_eq!(b.max, Pnt3::new(3.0, 2.0, 1.0));
+        assert_eq!(b.min, a.min);
+    }
+
+    #[test]
+    fn test_aabb3_transform() {
+        let corners: Vec<_> = (0..8)
+            .map(|i| {
+                let side = |bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+                Pnt3::new(side(1), side(2), side(4))
+            })
+            .collect();
+        let a = Aabb3::from_points(corners.iter().copied());
+        let m = Mat4::from_trs(
+            Vec3::new(1.0, 2.0, 3.0),
+            Quat::from_axis_angle(Vec3::Z, std::f64::consts::FRAC_PI_4),
+            Vec3::new(2.0, 1.0, 1.0),
+        );
+        let b = a.transform(&m);
+        // the same as the box around the transformed corners
+        let expected = Aabb3::from_points(corners.iter().map(|&p| m.transform_point(p)));
+        assert!((b.min - expected.min).length() < 1e-12);
+        assert!((b.max - expected.max).length() < 1e-12);
+        assert!(Aabb3::EMPTY.transform(&m).is_empty());
+    }
 }
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct RGB {
     pub r: f64,
     pub g: f64,
@@ -6,11 +8,20 @@ pub struct RGB {
 }
 
 impl RGB {
+    pub const fn new(r: f64, g: f64, b: f64) -> RGB {
+        RGB { r, g, b }
+    }
+
     pub const BLACK: Self = Self {
         r: 0.0,
         g: 0.0,
         b: 0.0,
     };
+    pub const WHITE: Self = Self {
+        r: 1.0,
+        g: 1.0,
+        b: 1.0,
+    };
     pub const RED: Self = Self {
         r: 1.0,
         g: 0.0,
@@ -0,0 +1,136 @@
+use super::{Aabb3, Mat4, Pnt3, Vec3, Vec4};
+
+/// Points `p` with `normal.dot(p) + d` = 0, the normal pointing to the
+/// positive side.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vec3,
+    pub d: f64,
+}
+
+impl Plane {
+    pub fn new(normal: Vec3, d: f64) -> Plane {
+        Plane { normal, d }
+    }
+
+    /// Plane with a unit normal, so that [`Plane::distance`] is in world
+    /// units.
+    pub fn normalize(self) -> Plane {
+        let length = self.normal.length();
+        Plane::new(self.normal / length, self.d / length)
+    }
+
+    /// Signed distance, positive on the side the normal points to.
+    pub fn distance(&self, p: Pnt3) -> f64 {
+        p.dot(self.normal) + self.d
+    }
+}
+
+/// Volume seen by a camera, bounded by planes with normals pointing inwards.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frustum {
+    /// Left, right, bottom, top, near and far.
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// Frustum of a view-projection matrix with depth from 0 at the near
+    /// plane to 1 at the far one, as set up by the camera.
+    pub fn from_matrix(view_projection: &Mat4) -> Frustum {
+        // a point is inside when -w <= x <= w and so on, and 0 <= z <= w,
+        // each inequality is a plane in world space
+        let r = |i: usize| view_projection.row(i);
+        let plane = |v: Vec4| Plane::new(Vec3::new(v.x, v.y, v.z), v.w).normalize();
+        let (r0, r1, r2, r3) = (r(0), r(1), r(2), r(3));
+        Frustum {
+            planes: [
+                plane(r3 + r0),
+                plane(r3 - r0),
+                plane(r3 + r1),
+                plane(r3 - r1),
+                plane(r2),
+                plane(r3 - r2),
+            ],
+        }
+    }
+
+    /// Whether the box may be visible. Conservative: boxes near the edges
+    /// that are outside, but not entirely behind a single plane, also pass.
+    pub fn intersects_aabb(&self, aabb: &Aabb3) -> bool {
+        if aabb.is_empty() {
+            return false;
+        }
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the normal
+            let n = plane.normal;
+            let corner = Pnt3::new(
+                if n.x >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if n.y >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if n.z >= 0.0 { aabb.max.z } else { aabb.min.z },
+            );
+            plane.distance(corner) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Looking from z = 5 towards the origin, 90° wide, from 1 to 10 units.
+    fn frustum() -> Frustum {
+        let depth = Mat4::from_translation(Vec3::new(0.0, 0.0, 0.5))
+            * Mat4::from_scale(Vec3::new(1.0, 1.0, 0.5));
+        let projection = Mat4::perspective(90f64.to_radians(), 1.0, 1.0, 10.0);
+        let view = Mat4::lookat(Pnt3::new(0.0, 0.0, 5.0), Pnt3::ZERO, Vec3::Y);
+        Frustum::from_matrix(&(depth * projection * view))
+    }
+
+    fn cube(center: Pnt3) -> Aabb3 {
+        let half = Vec3::new(0.5, 0.5, 0.5);
+        Aabb3::from_points([center - half, center + half])
+    }
+
+    #[test]
+    fn test_plane() {
+        let plane = Plane::new(Vec3::new(0.0, 2.0, 0.0), -4.0).normalize();
+        assert_eq!(plane.distance(Pnt3::new(7.0, 3.0, 1.0)), 1.0);
+        assert_eq!(plane, Plane::new(Vec3::Y, -2.0));
+    }
+
+    #[test]
+    fn test_planes() {
+        let frustum = frustum();
+        let [left, right, bottom, top, near, far] = frustum.planes;
+        let eps = 1e-12;
+        assert!((near.normal + Vec3::Z).length() < eps);
+        assert!((near.distance(Pnt3::new(0.0, 0.0, 4.0))).abs() < eps);
+        assert!((far.normal - Vec3::Z).length() < eps);
+        assert!((far.distance(Pnt3::new(0.0, 0.0, -5.0))).abs() < eps);
+        let diagonal = std::f64::consts::FRAC_1_SQRT_2;
+        let normals = [
+            (left, Vec3::new(diagonal, 0.0, -diagonal)),
+            (right, Vec3::new(-diagonal, 0.0, -diagonal)),
+            (bottom, Vec3::new(0.0, diagonal, -diagonal)),
+            (top, Vec3::new(0.0, -diagonal, -diagonal)),
+        ];
+        for (plane, normal) in normals {
+            assert!((plane.normal - normal).length() < eps);
+        }
+    }
+
+    #[test]
+    fn test_intersects_aabb() {
+        let frustum = frustum();
+        assert!(frustum.intersects_aabb(&cube(Pnt3::ZERO)));
+        // straddling the left plane, which is at x = -5 around the origin
+        assert!(frustum.intersects_aabb(&cube(Pnt3::new(-5.2, 0.0, 0.0))));
+        assert!(!frustum.intersects_aabb(&cube(Pnt3::new(-6.5, 0.0, 0.0))));
+        assert!(!frustum.intersects_aabb(&cube(Pnt3::new(0.0, 0.0, 6.0))));
+        assert!(!frustum.intersects_aabb(&cube(Pnt3::new(0.0, 0.0, -6.0))));
+        assert!(!frustum.intersects_aabb(&Aabb3::EMPTY));
+        let around =
+            Aabb3::from_points([Pnt3::new(-50.0, -50.0, -50.0), Pnt3::new(50.0, 50.0, 50.0)]);
+        assert!(frustum.intersects_aabb(&around));
+    }
+}
@@ -1,5 +1,6 @@
-use super::{Pnt3, Vec3, Vec4};
+use super::{Pnt3, Quat, Vec3, Vec4};
 
+/// 4x4 matrix in column-major order.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Mat4 {
     _e: [f64; 16],
@@ -56,6 +57,54 @@ impl Mat4 {
         self._e[i * 4 + j]
     }
 
+    #[rustfmt::skip]
+    pub fn from_translation(v: Vec3) -> Mat4 {
+        Mat4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 1.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            v.x, v.y, v.z, 1.0,
+        )
+    }
+
+    #[rustfmt::skip]
+    pub fn from_scale(v: Vec3) -> Mat4 {
+        Mat4::new(
+            v.x, 0.0, 0.0, 0.0,
+            0.0, v.y, 0.0, 0.0,
+            0.0, 0.0, v.z, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        )
+    }
+
+    /// Applies scale, then rotation, then translation.
+    pub fn from_trs(translation: Vec3, rotation: Quat, scale: Vec3) -> Mat4 {
+        Mat4::from_translation(translation) * rotation.to_mat4() * Mat4::from_scale(scale)
+    }
+
+    /// Splits an affine matrix without shear into translation, rotation and
+    /// scale, the inverse of [`Mat4::from_trs`]. A mirroring matrix gets a
+    /// negative x scale. Axes scaled to zero have no defined rotation.
+    pub fn decompose(&self) -> (Vec3, Quat, Vec3) {
+        let axis = |i: usize| {
+            let c = self.col(i);
+            Vec3::new(c.x, c.y, c.z)
+        };
+        let (c0, c1, c2) = (axis(0), axis(1), axis(2));
+        let translation = axis(3);
+        let sign = self.determinant().signum();
+        let scale = Vec3::new(sign * c0.length(), c1.length(), c2.length());
+        let unit = |v: Vec3, s: f64| if s == 0.0 { v } else { v / s };
+        let column = |v: Vec3| Vec4::new(v.x, v.y, v.z, 0.0);
+        let rotation = Mat4::from_columns(
+            column(unit(c0, scale.x)),
+            column(unit(c1, scale.y)),
+            column(unit(c2, scale.z)),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        );
+        (translation, Quat::from_mat4(&rotation), scale)
+    }
+
     pub fn lookto(eye: Pnt3, dir: Vec3, up: Vec3) -> Mat4 {
         let f = dir.normalize();
         let s = f.cross(up).normalize();
@@ -88,6 +137,117 @@ impl Mat4 {
             0.0, 0.0, c3r2, 0.0
         )
     }
+
+    pub fn orthographic(
+        left: f64,
+        right: f64,
+        bottom: f64,
+        top: f64,
+        znear: f64,
+        zfar: f64,
+    ) -> Mat4 {
+        let c0r0 = 2.0 / (right - left);
+        let c1r1 = 2.0 / (top - bottom);
+        let c2r2 = -2.0 / (zfar - znear);
+        let c3r0 = -(right + left) / (right - left);
+        let c3r1 = -(top + bottom) / (top - bottom);
+        let c3r2 = -(zfar + znear) / (zfar - znear);
+        #[cfg_attr(rustfmt, rustfmt_skip)]
+        Mat4::new(
+            c0r0, 0.0, 0.0, 0.0,
+            0.0, c1r1, 0.0, 0.0,
+            0.0, 0.0, c2r2, 0.0,
+            c3r0, c3r1, c3r2, 1.0
+        )
+    }
+
+    pub fn transform_point(&self, p: Pnt3) -> Pnt3 {
+        let v = self.col(0) * p.x + self.col(1) * p.y + self.col(2) * p.z + self.col(3);
+        Pnt3::new(v.x / v.w, v.y / v.w, v.z / v.w)
+    }
+
+    /// Applies the linear part only, ignoring translation.
+    pub fn transform_vector(&self, v: Vec3) -> Vec3 {
+        let v = self.col(0) * v.x + self.col(1) * v.y + self.col(2) * v.z;
+        Vec3::new(v.x, v.y, v.z)
+    }
+
+    /// Transpose of the cofactor matrix, the inverse times the determinant.
+    #[rustfmt::skip]
+    fn adjugate(&self) -> Mat4 {
+        let m = &self._e;
+        // determinants of 2x2 minors of the first two and the last two columns
+        let a0 = m[0] * m[5] - m[1] * m[4];
+        let a1 = m[0] * m[6] - m[2] * m[4];
+        let a2 = m[0] * m[7] - m[3] * m[4];
+        let a3 = m[1] * m[6] - m[2] * m[5];
+        let a4 = m[1] * m[7] - m[3] * m[5];
+        let a5 = m[2] * m[7] - m[3] * m[6];
+        let b0 = m[8] * m[13] - m[9] * m[12];
+        let b1 = m[8] * m[14] - m[10] * m[12];
+        let b2 = m[8] * m[15] - m[11] * m[12];
+        let b3 = m[9] * m[14] - m[10] * m[13];
+        let b4 = m[9] * m[15] - m[11] * m[13];
+        let b5 = m[10] * m[15] - m[11] * m[14];
+        Mat4::new(
+            m[5] * b5 - m[6] * b4 + m[7] * b3,
+            -m[1] * b5 + m[2] * b4 - m[3] * b3,
+            m[13] * a5 - m[14] * a4 + m[15] * a3,
+            -m[9] * a5 + m[10] * a4 - m[11] * a3,
+
+            -m[4] * b5 + m[6] * b2 - m[7] * b1,
+            m[0] * b5 - m[2] * b2 + m[3] * b1,
+            -m[12] * a5 + m[14] * a2 - m[15] * a1,
+            m[8] * a5 - m[10] * a2 + m[11] * a1,
+
+            m[4] * b4 - m[5] * b2 + m[7] * b0,
+            -m[0] * b4 + m[1] * b2 - m[3] * b0,
+            m[12] * a4 - m[13] * a2 + m[15] * a0,
+            -m[8] * a4 + m[9] * a2 - m[11] * a0,
+
+            -m[4] * b3 + m[5] * b1 - m[6] * b0,
+            m[0] * b3 - m[1] * b1 + m[2] * b0,
+            -m[12] * a3 + m[13] * a1 - m[14] * a0,
+            m[8] * a3 - m[9] * a1 + m[10] * a0,
+        )
+    }
+
+    pub fn determinant(&self) -> f64 {
+        let adjugate = self.adjugate();
+        (0..4).map(|i| self.e(0, i) * adjugate.e(i, 0)).sum()
+    }
+
+    /// Inverse matrix, or `None` if the matrix is singular.
+    pub fn inverse(&self) -> Option<Mat4> {
+        let adjugate = self.adjugate();
+        let det: f64 = (0..4).map(|i| self.e(0, i) * adjugate.e(i, 0)).sum();
+        if det.abs() < f64::EPSILON {
+            return None;
+        }
+        Some(Mat4 {
+            _e: adjugate._e.map(|x| x / det),
+        })
+    }
+
+    /// Matrix transforming normals: the cofactor matrix of the linear part,
+    /// which is the inverse transpose scaled by the determinant. The sign is
+    /// kept positive so mirroring does not flip normals, they still need
+    /// renormalizing afterwards.
+    pub fn normal_matrix(&self) -> Mat4 {
+        let col = |i: usize| {
+            let c = self.col(i);
+            Vec3::new(c.x, c.y, c.z)
+        };
+        let (c0, c1, c2) = (col(0), col(1), col(2));
+        let sign = c0.dot(c1.cross(c2)).signum();
+        let column = |v: Vec3| Vec4::new(v.x * sign, v.y * sign, v.z * sign, 0.0);
+        Mat4::from_columns(
+            column(c1.cross(c2)),
+            column(c2.cross(c0)),
+            column(c0.cross(c1)),
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+        )
+    }
 }
 
 impl std::ops::Mul<Mat4> for Mat4 {
@@ -101,6 +261,13 @@ impl std::ops::Mul<Mat4> for Mat4 {
     }
 }
 
+impl std::ops::Mul<Vec4> for Mat4 {
+    type Output = Vec4;
+    fn mul(self, rhs: Vec4) -> Self::Output {
+        self.col(0) * rhs.x + self.col(1) * rhs.y + self.col(2) * rhs.z + self.col(3) * rhs.w
+    }
+}
+
 impl Into<[f32; 16]> for Mat4 {
     fn into(self) -> [f32; 16] {
         self._e.map(|x| x as f32)
@@ -110,9 +277,183 @@ impl Into<[f32; 16]> for Mat4 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
+    use std::f64::consts::PI;
 
     #[test]
     fn test_identity_multiply() {
         assert_eq!(Mat4::I * Mat4::I, Mat4::I);
     }
+
+    fn assert_matches_cgmath(a: Mat4, b: cgmath::Matrix4<f64>) {
+        for col in 0..4 {
+            for row in 0..4 {
+                assert!((a.e(col, row) - b[col][row]).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn test_constructors() {
+        use cgmath::Matrix4;
+        assert_matches_cgmath(
+            Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0)),
+            Matrix4::from_translation(cgmath::vec3(1.0, 2.0, 3.0)),
+        );
+        assert_matches_cgmath(
+            Mat4::from_scale(Vec3::new(1.0, 2.0, 3.0)),
+            Matrix4::from_nonuniform_scale(1.0, 2.0, 3.0),
+        );
+    }
+
+    #[test]
+    fn test_transform() {
+        let m = Mat4::from_translation(Vec3::new(1.0, 2.0, 3.0))
+            * Mat4::from_scale(Vec3::new(2.0, 1.0, 1.0));
+        assert_eq!(
+            m.transform_point(Pnt3::new(1.0, 1.0, 1.0)),
+            Pnt3::new(3.0, 3.0, 4.0)
+        );
+        assert_eq!(
+            m.transform_vector(Vec3::new(1.0, 1.0, 1.0)),
+            Vec3::new(2.0, 1.0, 1.0)
+        );
+
+        // a normal of a plane squashed along x leans further towards x
+        let n = m.normal_matrix().transform_vector(Vec3::new(1.0, 1.0, 0.0));
+        let tangent = m.transform_vector(Vec3::new(-1.0, 1.0, 0.0));
+        assert!(n.dot(tangent).abs() < 1e-12);
+        assert!(n.x < n.y);
+    }
+
+    /// Rotated, scaled and translated, with no special structure.
+    #[rustfmt::skip]
+    fn sample() -> (Mat4, cgmath::Matrix4<f64>) {
+        let e = [
+            2.0, 0.5, -1.0, 0.0,
+            0.3, 1.5, 0.2, 0.1,
+            -0.7, 0.4, 3.0, 0.0,
+            1.0, -2.0, 4.0, 1.0,
+        ];
+        let a = Mat4 { _e: e };
+        let b = cgmath::Matrix4::new(
+            e[0], e[1], e[2], e[3],
+            e[4], e[5], e[6], e[7],
+            e[8], e[9], e[10], e[11],
+            e[12], e[13], e[14], e[15],
+        );
+        (a, b)
+    }
+
+    #[test]
+    fn test_inverse() {
+        use cgmath::SquareMatrix;
+        let (a, b) = sample();
+        assert!((a.determinant() - b.determinant()).abs() < 1e-12);
+        assert_matches_cgmath(a.inverse().unwrap(), b.invert().unwrap());
+        assert_matches_cgmath(a * a.inverse().unwrap(), cgmath::Matrix4::identity());
+
+        let view = Mat4::lookat(
+            Pnt3::new(1.0, 2.0, 3.0),
+            Pnt3::new(0.0, 0.5, 0.0),
+            Vec3::new(0.0, 1.0, 0.0),
+        );
+        let eye = view.inverse().unwrap().transform_point(Pnt3::ZERO);
+        assert!((eye - Pnt3::new(1.0, 2.0, 3.0)).length() < 1e-12);
+
+        assert_eq!(Mat4::from_scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+        assert_eq!(Mat4::default().determinant(), 0.0);
+    }
+
+    #[test]
+    fn test_rotation() {
+        use cgmath::{Matrix4, Rad};
+        assert_matches_cgmath(
+            Mat4::from_trs(
+                Vec3::new(1.0, 2.0, 3.0),
+                Quat::from_axis_angle(Vec3::Z, 0.5),
+                Vec3::new(2.0, 2.0, 2.0),
+            ),
+            Matrix4::from_translation(cgmath::vec3(1.0, 2.0, 3.0))
+                * Matrix4::from_angle_z(Rad(0.5))
+                * Matrix4::from_scale(2.0),
+        );
+
+        let (a, b) = sample();
+        let v = Vec4::new(1.0, 2.0, 3.0, 1.0);
+        let w = b * cgmath::vec4(1.0, 2.0, 3.0, 1.0);
+        let v = a * v;
+        assert!((0..4).all(|i| (v[i] - w[i]).abs() < 1e-12));
+    }
+
+    #[test]
+    fn test_decompose() {
+        let cases = [
+            (Vec3::new(0.1, 0.2, 0.3), Vec3::new(1.0, 2.0, 3.0)),
+            (Vec3::new(-2.0, 1.2, 3.0), Vec3::new(0.5, 0.5, 4.0)),
+            (
+                Vec3::new(0.4, std::f64::consts::FRAC_PI_2, 0.1),
+                Vec3::new(1.0, 1.0, 1.0),
+            ),
+            (Vec3::new(0.0, 0.0, 0.0), Vec3::new(-1.0, 1.0, 1.0)),
+        ];
+        for (angles, scale) in cases {
+            let translation = Vec3::new(-1.0, 4.0, 2.0);
+            let rotation = Quat::from_euler(angles);
+            let m = Mat4::from_trs(translation, rotation, scale);
+            let (t, r, s) = m.decompose();
+            assert!((t - translation).length() < 1e-12);
+            assert!(
+                r.dot(rotation).abs() > 1.0 - 1e-12,
+                "{:?} {:?}",
+                r,
+                rotation
+            );
+            assert!((s - scale).length() < 1e-12);
+            assert_matches(Mat4::from_trs(t, r, s), m);
+        }
+    }
+
+    /// Whether no element differs by more than `epsilon`.
+    fn approx_eq(a: Mat4, b: Mat4, epsilon: f64) -> bool {
+        (0..16).all(|i| (a._e[i] - b._e[i]).abs() <= epsilon)
+    }
+
+    fn assert_matches(a: Mat4, b: Mat4) {
+        assert!(approx_eq(a, b, 1e-12), "{:?} != {:?}", a, b);
+    }
+
+    /// Affine matrices, with scales far enough from zero to invert well.
+    fn trs() -> impl Strategy<Value = Mat4> {
+        let vector = |range: std::ops::Range<f64>| {
+            (range.clone(), range.clone(), range).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+        };
+        let angles = vector(-PI..PI);
+        let scale = vector(0.1..10.0);
+        (vector(-100.0..100.0), angles, scale, any::<bool>()).prop_map(|(t, r, s, mirror)| {
+            let s = if mirror { Vec3::new(-s.x, s.y, s.z) } else { s };
+            Mat4::from_trs(t, Quat::from_euler(r), s)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_inverse(a in trs()) {
+            let inverse = a.inverse().unwrap();
+            prop_assert!(approx_eq(a * inverse, Mat4::I, 1e-9));
+            prop_assert!(approx_eq(inverse.inverse().unwrap(), a, 1e-6));
+        }
+
+        #[test]
+        fn prop_determinant(a in trs(), b in trs()) {
+            let product = a.determinant() * b.determinant();
+            prop_assert!(((a * b).determinant() - product).abs() <= 1e-9 * product.abs());
+        }
+
+        #[test]
+        fn prop_decompose(a in trs()) {
+            let (t, r, s) = a.decompose();
+            prop_assert!(approx_eq(Mat4::from_trs(t, r, s), a, 1e-9));
+        }
+    }
 }
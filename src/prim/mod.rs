@@ -1,11 +1,17 @@
 mod bound;
 mod color;
+mod frustum;
 mod matrix;
 mod point;
+mod quat;
+mod ray;
 mod vector;
 
-pub use bound::Rect;
+pub use bound::{Aabb3, Rect};
 pub use color::RGB;
+pub use frustum::Frustum;
 pub use matrix::*;
 pub use point::*;
+pub use quat::*;
+pub use ray::Ray;
 pub use vector::*;
@@ -1,7 +1,91 @@
 use super::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
 use std::ops;
 
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+/// Operators between a point type and its vector type, and indexing, shared
+/// by the point types.
+macro_rules! point {
+    ($name:ident, $vector:ident { $($field:ident: $index:literal),+ }) => {
+        impl $name {
+            pub const ZERO: Self = Self { $($field: 0.0),+ };
+        }
+
+        impl ops::Add<$name> for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> Self::Output {
+                $name { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl ops::Sub<$name> for $name {
+            type Output = $vector;
+            fn sub(self, rhs: $name) -> Self::Output {
+                $vector { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl ops::Mul<f64> for $name {
+            type Output = $name;
+            fn mul(self, rhs: f64) -> Self::Output {
+                $name { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl ops::Div<f64> for $name {
+            type Output = $name;
+            fn div(self, rhs: f64) -> Self::Output {
+                $name { $($field: self.$field / rhs),+ }
+            }
+        }
+
+        impl ops::Add<$vector> for $name {
+            type Output = $name;
+            fn add(self, rhs: $vector) -> Self::Output {
+                $name { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl ops::Sub<$vector> for $name {
+            type Output = $name;
+            fn sub(self, rhs: $vector) -> Self::Output {
+                $name { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl ops::AddAssign<$vector> for $name {
+            fn add_assign(&mut self, rhs: $vector) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl ops::SubAssign<$vector> for $name {
+            fn sub_assign(&mut self, rhs: $vector) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl ops::Index<usize> for $name {
+            type Output = f64;
+            fn index(&self, index: usize) -> &Self::Output {
+                match index {
+                    $($index => &self.$field,)+
+                    _ => panic!("index {} out of range for {}", index, stringify!($name)),
+                }
+            }
+        }
+
+        impl ops::IndexMut<usize> for $name {
+            fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+                match index {
+                    $($index => &mut self.$field,)+
+                    _ => panic!("index {} out of range for {}", index, stringify!($name)),
+                }
+            }
+        }
+    };
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Pnt2 {
     pub x: f64,
     pub y: f64,
@@ -12,59 +96,18 @@ impl Pnt2 {
         Pnt2 { x, y }
     }
 
-    pub const ZERO: Self = Self::new(0.0, 0.0);
-}
-
-impl ops::Add<Pnt2> for Pnt2 {
-    type Output = Pnt2;
-    fn add(self, rhs: Pnt2) -> Self::Output {
-        Pnt2::new(self.x + rhs.x, self.y + rhs.y)
+    pub fn min(self, other: Pnt2) -> Pnt2 {
+        Pnt2::new(self.x.min(other.x), self.y.min(other.y))
     }
-}
 
-impl ops::Sub<Pnt2> for Pnt2 {
-    type Output = Vec2;
-    fn sub(self, rhs: Pnt2) -> Self::Output {
-        Vec2::new(self.x - rhs.x, self.y - rhs.y)
+    pub fn max(self, other: Pnt2) -> Pnt2 {
+        Pnt2::new(self.x.max(other.x), self.y.max(other.y))
     }
 }
 
-impl ops::Div<f64> for Pnt2 {
-    type Output = Pnt2;
-    fn div(self, rhs: f64) -> Self::Output {
-        Pnt2::new(self.x / rhs, self.y / rhs)
-    }
-}
+point!(Pnt2, Vec2 { x: 0, y: 1 });
 
-impl ops::Add<Vec2> for Pnt2 {
-    type Output = Pnt2;
-    fn add(self, rhs: Vec2) -> Self::Output {
-        Pnt2::new(self.x + rhs.x, self.y + rhs.y)
-    }
-}
-
-impl ops::Sub<Vec2> for Pnt2 {
-    type Output = Pnt2;
-    fn sub(self, rhs: Vec2) -> Self::Output {
-        Pnt2::new(self.x - rhs.x, self.y - rhs.y)
-    }
-}
-
-impl ops::AddAssign<Vec2> for Pnt2 {
-    fn add_assign(&mut self, rhs: Vec2) {
-        self.x += rhs.x;
-        self.y += rhs.y;
-    }
-}
-
-impl ops::SubAssign<Vec2> for Pnt2 {
-    fn sub_assign(&mut self, rhs: Vec2) {
-        self.x -= rhs.x;
-        self.y -= rhs.y;
-    }
-}
-
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Pnt3 {
     pub x: f64,
     pub y: f64,
@@ -76,23 +119,70 @@ impl Pnt3 {
         Pnt3 { x, y, z }
     }
 
-    pub const ZERO: Self = Self::new(0.0, 0.0, 0.0);
-
     pub fn dot(self, other: Vec3) -> f64 {
         self.x * other.x + self.y * other.y + self.z * other.z
     }
-}
 
-impl ops::Add<Pnt3> for Pnt3 {
-    type Output = Pnt3;
-    fn add(self, rhs: Pnt3) -> Self::Output {
-        Pnt3::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    /// `self` at `t` = 0, `other` at `t` = 1.
+    pub fn lerp(self, other: Pnt3, t: f64) -> Pnt3 {
+        self + (other - self) * t
+    }
+
+    pub fn min(self, other: Pnt3) -> Pnt3 {
+        Pnt3::new(
+            self.x.min(other.x),
+            self.y.min(other.y),
+            self.z.min(other.z),
+        )
+    }
+
+    pub fn max(self, other: Pnt3) -> Pnt3 {
+        Pnt3::new(
+            self.x.max(other.x),
+            self.y.max(other.y),
+            self.z.max(other.z),
+        )
     }
 }
 
-impl ops::Sub<Pnt3> for Pnt3 {
-    type Output = Vec3;
-    fn sub(self, rhs: Pnt3) -> Self::Output {
-        Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+point!(Pnt3, Vec3 { x: 0, y: 1, z: 2 });
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_operators() {
+        let mut p = Pnt3::new(1.0, 2.0, 3.0);
+        p += Vec3::X;
+        p -= Vec3::Z;
+        assert_eq!(p, Pnt3::new(2.0, 2.0, 2.0));
+        assert_eq!(p - Pnt3::ZERO, Vec3::new(2.0, 2.0, 2.0));
+        p[1] = 0.0;
+        assert_eq!(p.min(Pnt3::new(1.0, 1.0, 1.0)), Pnt3::new(1.0, 0.0, 1.0));
+        assert_eq!(
+            Pnt3::ZERO.lerp(Pnt3::new(2.0, 0.0, 4.0), 0.5),
+            Pnt3::new(1.0, 0.0, 2.0)
+        );
+    }
+
+    fn pnt3() -> impl Strategy<Value = Pnt3> {
+        (-1e3..1e3, -1e3..1e3, -1e3..1e3).prop_map(|(x, y, z)| Pnt3::new(x, y, z))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_difference(a in pnt3(), b in pnt3()) {
+            prop_assert!((b + (a - b) - a).length() < 1e-9);
+            prop_assert!((a - (a - b) - b).length() < 1e-9);
+            prop_assert_eq!(a - b, -(b - a));
+        }
+
+        #[test]
+        fn prop_lerp(a in pnt3(), b in pnt3(), t in 0.0..1.0) {
+            let p = a.lerp(b, t);
+            prop_assert!(((p - a).length() + (b - p).length() - (b - a).length()).abs() < 1e-9);
+        }
     }
 }
@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+use std::ops;
+
+use super::{Mat4, Vec3};
+
+/// Rotation as a unit quaternion, `w` being the real part.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Quat {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub w: f64,
+}
+
+impl Quat {
+    pub const fn new(x: f64, y: f64, z: f64, w: f64) -> Quat {
+        Quat { x, y, z, w }
+    }
+
+    pub const IDENTITY: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+
+    /// Rotation by `angle` radians around `axis`, which must be normalized.
+    pub fn from_axis_angle(axis: Vec3, angle: f64) -> Quat {
+        let (s, c) = (angle / 2.0).sin_cos();
+        Quat::new(axis.x * s, axis.y * s, axis.z * s, c)
+    }
+
+    /// Rotation by Euler angles in radians, turning around x first, then y,
+    /// then z, so its matrix is Rz * Ry * Rx.
+    pub fn from_euler(angles: Vec3) -> Quat {
+        Quat::from_axis_angle(Vec3::Z, angles.z)
+            * Quat::from_axis_angle(Vec3::Y, angles.y)
+            * Quat::from_axis_angle(Vec3::X, angles.x)
+    }
+
+    /// Rotation of the upper 3x3 part of `m`, which must be orthonormal.
+    pub fn from_mat4(m: &Mat4) -> Quat {
+        // largest of the four components first, for precision
+        let r = |row: usize, col: usize| m.e(col, row);
+        let trace = r(0, 0) + r(1, 1) + r(2, 2);
+        let q = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Quat::new(
+                (r(2, 1) - r(1, 2)) / s,
+                (r(0, 2) - r(2, 0)) / s,
+                (r(1, 0) - r(0, 1)) / s,
+                s / 4.0,
+            )
+        } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+            let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+            Quat::new(
+                s / 4.0,
+                (r(0, 1) + r(1, 0)) / s,
+                (r(0, 2) + r(2, 0)) / s,
+                (r(2, 1) - r(1, 2)) / s,
+            )
+        } else if r(1, 1) > r(2, 2) {
+            let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+            Quat::new(
+                (r(0, 1) + r(1, 0)) / s,
+                s / 4.0,
+                (r(1, 2) + r(2, 1)) / s,
+                (r(0, 2) - r(2, 0)) / s,
+            )
+        } else {
+            let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+            Quat::new(
+                (r(0, 2) + r(2, 0)) / s,
+                (r(1, 2) + r(2, 1)) / s,
+                s / 4.0,
+                (r(1, 0) - r(0, 1)) / s,
+            )
+        };
+        q.normalize()
+    }
+
+    #[rustfmt::skip]
+    pub fn to_mat4(self) -> Mat4 {
+        let r = self.rows();
+        Mat4::new(
+            r[0][0], r[1][0], r[2][0], 0.0,
+            r[0][1], r[1][1], r[2][1], 0.0,
+            r[0][2], r[1][2], r[2][2], 0.0,
+            0.0,     0.0,     0.0,     1.0,
+        )
+    }
+
+    /// Rotation matrix, indexed by row and then column.
+    fn rows(self) -> [[f64; 3]; 3] {
+        let Quat { x, y, z, w } = self;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - z * w),
+                2.0 * (x * z + y * w),
+            ],
+            [
+                2.0 * (x * y + z * w),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - x * w),
+            ],
+            [
+                2.0 * (x * z - y * w),
+                2.0 * (y * z + x * w),
+                1.0 - 2.0 * (x * x + y * y),
+            ],
+        ]
+    }
+
+    pub fn dot(self, other: Quat) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z + self.w * other.w
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalize(self) -> Quat {
+        self * self.length().recip()
+    }
+
+    pub fn rotate(self, v: Vec3) -> Vec3 {
+        let u = Vec3::new(self.x, self.y, self.z);
+        let t = u.cross(v) * 2.0;
+        v + t * self.w + u.cross(t)
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Quat::IDENTITY
+    }
+}
+
+/// Composes rotations, `a * b` applies `b` first.
+impl ops::Mul<Quat> for Quat {
+    type Output = Quat;
+    fn mul(self, rhs: Quat) -> Self::Output {
+        Quat::new(
+            self.w * rhs.x + self.x * rhs.w + self.y * rhs.z - self.z * rhs.y,
+            self.w * rhs.y - self.x * rhs.z + self.y * rhs.w + self.z * rhs.x,
+            self.w * rhs.z + self.x * rhs.y - self.y * rhs.x + self.z * rhs.w,
+            self.w * rhs.w - self.x * rhs.x - self.y * rhs.y - self.z * rhs.z,
+        )
+    }
+}
+
+impl ops::Mul<Vec3> for Quat {
+    type Output = Vec3;
+    fn mul(self, rhs: Vec3) -> Self::Output {
+        self.rotate(rhs)
+    }
+}
+
+impl ops::Mul<f64> for Quat {
+    type Output = Quat;
+    fn mul(self, rhs: f64) -> Self::Output {
+        Quat::new(self.x * rhs, self.y * rhs, self.z * rhs, self.w * rhs)
+    }
+}
+
+impl ops::Neg for Quat {
+    type Output = Quat;
+    fn neg(self) -> Self::Output {
+        Quat::new(-self.x, -self.y, -self.z, -self.w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use std::f64::consts::FRAC_PI_2;
+
+    fn cgmath_quat(q: Quat) -> cgmath::Quaternion<f64> {
+        cgmath::Quaternion::new(q.w, q.x, q.y, q.z)
+    }
+
+    fn assert_matrix(a: Mat4, b: cgmath::Matrix4<f64>) {
+        for col in 0..4 {
+            for row in 0..4 {
+                assert!((a.e(col, row) - b[col][row]).abs() < 1e-12);
+            }
+        }
+    }
+
+    /// Equal as rotations, q and -q being the same one.
+    fn assert_same_rotation(a: Quat, b: Quat) {
+        assert!(a.dot(b).abs() > 1.0 - 1e-12, "{:?} != {:?}", a, b);
+    }
+
+    fn samples() -> Vec<Quat> {
+        vec![
+            Quat::IDENTITY,
+            Quat::new(0.2, -0.4, 0.1, 0.8).normalize(),
+            Quat::new(-0.5, 0.5, 0.5, 0.5),
+            Quat::new(0.0, 1.0, 0.0, 1.0).normalize(),
+            Quat::new(0.9, 0.1, -0.3, -0.1).normalize(),
+            Quat::new(0.0, 0.0, 1.0, 0.0),
+        ]
+    }
+
+    #[test]
+    fn test_matrix() {
+        for q in samples() {
+            assert_matrix(q.to_mat4(), cgmath::Matrix4::from(cgmath_quat(q)));
+            assert_same_rotation(Quat::from_mat4(&q.to_mat4()), q);
+        }
+    }
+
+    #[test]
+    fn test_axis_angle() {
+        use cgmath::Rotation3;
+        let axis = Vec3::new(1.0, 2.0, -2.0) / 3.0;
+        let q = Quat::from_axis_angle(axis, 0.7);
+        let expected = cgmath::Quaternion::from_axis_angle(
+            cgmath::vec3(axis.x, axis.y, axis.z),
+            cgmath::Rad(0.7),
+        );
+        assert_matrix(q.to_mat4(), expected.into());
+    }
+
+    #[test]
+    fn test_multiply_and_rotate() {
+        let a = Quat::from_axis_angle(Vec3::Z, FRAC_PI_2);
+        let b = Quat::from_axis_angle(Vec3::X, FRAC_PI_2);
+        // b first turns y to z, which a leaves alone
+        assert!(((a * b) * Vec3::Y - Vec3::Z).length() < 1e-12);
+        assert!((a * Vec3::X - Vec3::Y).length() < 1e-12);
+
+        for (p, q) in samples().into_iter().zip(samples().into_iter().rev()) {
+            let expected = cgmath_quat(p) * cgmath_quat(q);
+            assert_matrix((p * q).to_mat4(), expected.into());
+            let v = Vec3::new(0.3, -1.0, 2.0);
+            let rotated = q.to_mat4().transform_vector(v);
+            assert!((q.rotate(v) - rotated).length() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_euler() {
+        use cgmath::{Matrix4, Rad};
+        for angles in [
+            Vec3::ZERO,
+            Vec3::new(0.3, -0.2, 1.1),
+            Vec3::new(-2.5, 0.7, 3.0),
+        ] {
+            let expected = Matrix4::from_angle_z(Rad(angles.z))
+                * Matrix4::from_angle_y(Rad(angles.y))
+                * Matrix4::from_angle_x(Rad(angles.x));
+            assert_matrix(Quat::from_euler(angles).to_mat4(), expected);
+        }
+    }
+
+    fn rotation() -> impl Strategy<Value = Quat> {
+        (-1.0..1.0, -1.0..1.0, -1.0..1.0, -1.0..1.0)
+            .prop_filter(
+                "too short to normalize",
+                |&(x, y, z, w): &(f64, f64, f64, f64)| x * x + y * y + z * z + w * w > 1e-3,
+            )
+            .prop_map(|(x, y, z, w)| Quat::new(x, y, z, w).normalize())
+    }
+
+    proptest! {
+        #[test]
+        fn prop_rotate(q in rotation(), x in -10.0..10.0, y in -10.0..10.0, z in -10.0..10.0) {
+            let v = Vec3::new(x, y, z);
+            prop_assert!(((q * v).length() - v.length()).abs() < 1e-9);
+            prop_assert!((q * v - q.to_mat4().transform_vector(v)).length() < 1e-9);
+        }
+
+        #[test]
+        fn prop_matrix(q in rotation()) {
+            let p = Quat::from_mat4(&q.to_mat4());
+            // the same rotation, q and -q being the same one
+            prop_assert!(p.dot(q).abs() > 1.0 - 1e-9);
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use super::{Aabb3, Mat4, Pnt2, Pnt3, Vec3};
+
+/// Half line from `origin` along `dir`, which need not be normalized; the
+/// distances returned by intersections are in units of `dir`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Pnt3,
+    pub dir: Vec3,
+}
+
+impl Ray {
+    pub fn new(origin: Pnt3, dir: Vec3) -> Ray {
+        Ray { origin, dir }
+    }
+
+    /// Ray through a point in normalized device coordinates, starting on the
+    /// near plane. Expects depth from 0 at the near plane to 1 at the far
+    /// one, as set up by the camera. `None` if the matrix is singular.
+    pub fn unproject(ndc: Pnt2, view_projection: &Mat4) -> Option<Ray> {
+        let inverse = view_projection.inverse()?;
+        let near = inverse.transform_point(Pnt3::new(ndc.x, ndc.y, 0.0));
+        let far = inverse.transform_point(Pnt3::new(ndc.x, ndc.y, 1.0));
+        Some(Ray::new(near, far - near))
+    }
+
+    pub fn transform(&self, m: &Mat4) -> Ray {
+        Ray::new(m.transform_point(self.origin), m.transform_vector(self.dir))
+    }
+
+    /// Distance to where the ray enters the box, zero if it starts inside.
+    pub fn intersect_aabb(&self, aabb: &Aabb3) -> Option<f64> {
+        if aabb.is_empty() {
+            return None;
+        }
+        // clip the ray against the three pairs of parallel planes, division
+        // by zero gives infinities that leave parallel slabs unclipped
+        let (mut near, mut far) = (0.0, f64::INFINITY);
+        for i in 0..3 {
+            let inverse = self.dir[i].recip();
+            let a = (aabb.min[i] - self.origin[i]) * inverse;
+            let b = (aabb.max[i] - self.origin[i]) * inverse;
+            near = a.min(b).max(near);
+            far = a.max(b).min(far);
+        }
+        (near <= far).then_some(near)
+    }
+
+    /// Distance to the hit on triangle `a`, `b`, `c` from either side
+    /// (Möller-Trumbore).
+    pub fn intersect_triangle(&self, a: Pnt3, b: Pnt3, c: Pnt3) -> Option<f64> {
+        let (ab, ac) = (b - a, c - a);
+        let p = self.dir.cross(ac);
+        let det = ab.dot(p);
+        if det.abs() < 1e-12 {
+            return None;
+        }
+        let ao = self.origin - a;
+        let u = ao.dot(p) / det;
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+        let q = ao.cross(ab);
+        let v = self.dir.dot(q) / det;
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+        let t = ac.dot(q) / det;
+        (t >= 0.0).then_some(t)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> Aabb3 {
+        Aabb3::from_points([Pnt3::new(-1.0, -1.0, -1.0), Pnt3::new(1.0, 1.0, 1.0)])
+    }
+
+    #[test]
+    fn test_aabb() {
+        let ray = Ray::new(Pnt3::new(-5.0, 0.5, 0.0), Vec3::new(2.0, 0.0, 0.0));
+        assert_eq!(ray.intersect_aabb(&unit_box()), Some(2.0));
+        // inside, behind, and passing beside the box
+        let inside = Ray::new(Pnt3::ZERO, Vec3::Y);
+        assert_eq!(inside.intersect_aabb(&unit_box()), Some(0.0));
+        let behind = Ray::new(Pnt3::new(0.0, 5.0, 0.0), Vec3::Y);
+        assert_eq!(behind.intersect_aabb(&unit_box()), None);
+        let beside = Ray::new(Pnt3::new(-5.0, 1.5, 0.0), Vec3::X);
+        assert_eq!(beside.intersect_aabb(&unit_box()), None);
+        let diagonal = Ray::new(Pnt3::new(-3.0, -3.0, 0.0), Vec3::new(1.0, 1.0, 0.0));
+        assert_eq!(diagonal.intersect_aabb(&unit_box()), Some(2.0));
+        assert_eq!(ray.intersect_aabb(&Aabb3::EMPTY), None);
+    }
+
+    #[test]
+    fn test_triangle() {
+        let (a, b, c) = (
+            Pnt3::new(0.0, 0.0, 0.0),
+            Pnt3::new(1.0, 0.0, 0.0),
+            Pnt3::new(0.0, 1.0, 0.0),
+        );
+        let ray = Ray::new(Pnt3::new(0.25, 0.25, 2.0), -Vec3::Z);
+        assert_eq!(ray.intersect_triangle(a, b, c), Some(2.0));
+        // the winding does not matter
+        assert_eq!(ray.intersect_triangle(a, c, b), Some(2.0));
+        let outside = Ray::new(Pnt3::new(0.75, 0.75, 2.0), -Vec3::Z);
+        assert_eq!(outside.intersect_triangle(a, b, c), None);
+        let away = Ray::new(Pnt3::new(0.25, 0.25, 2.0), Vec3::Z);
+        assert_eq!(away.intersect_triangle(a, b, c), None);
+        let parallel = Ray::new(Pnt3::new(-1.0, 0.25, 0.0), Vec3::X);
+        assert_eq!(parallel.intersect_triangle(a, b, c), None);
+    }
+
+    #[test]
+    fn test_unproject() {
+        let m = Mat4::from_translation(Vec3::new(0.0, 0.0, 0.5))
+            * Mat4::from_scale(Vec3::new(0.5, 0.5, 0.25));
+        let ray = Ray::unproject(Pnt2::new(0.5, -0.5), &m).unwrap();
+        assert!((ray.origin - Pnt3::new(1.0, -1.0, -2.0)).length() < 1e-12);
+        assert!((ray.dir - Vec3::new(0.0, 0.0, 4.0)).length() < 1e-12);
+
+        let moved = ray.transform(&Mat4::from_translation(Vec3::X));
+        assert!((moved.origin - Pnt3::new(2.0, -1.0, -2.0)).length() < 1e-12);
+        assert_eq!(moved.dir, ray.dir);
+    }
+}
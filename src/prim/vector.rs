@@ -1,46 +1,137 @@
+use serde::{Deserialize, Serialize};
 use std::ops;
 
-#[derive(Clone, Copy, Debug)]
+/// Componentwise arithmetic and indexing shared by the vector types.
+macro_rules! vector {
+    ($name:ident { $($field:ident: $index:literal),+ }) => {
+        impl $name {
+            pub const ZERO: Self = Self { $($field: 0.0),+ };
+        }
+
+        impl ops::Add<$name> for $name {
+            type Output = $name;
+            fn add(self, rhs: $name) -> Self::Output {
+                $name { $($field: self.$field + rhs.$field),+ }
+            }
+        }
+
+        impl ops::Sub<$name> for $name {
+            type Output = $name;
+            fn sub(self, rhs: $name) -> Self::Output {
+                $name { $($field: self.$field - rhs.$field),+ }
+            }
+        }
+
+        impl ops::Mul<$name> for $name {
+            type Output = $name;
+            fn mul(self, rhs: $name) -> Self::Output {
+                $name { $($field: self.$field * rhs.$field),+ }
+            }
+        }
+
+        impl ops::Mul<f64> for $name {
+            type Output = $name;
+            fn mul(self, rhs: f64) -> Self::Output {
+                $name { $($field: self.$field * rhs),+ }
+            }
+        }
+
+        impl ops::Div<$name> for $name {
+            type Output = $name;
+            fn div(self, rhs: $name) -> Self::Output {
+                $name { $($field: self.$field / rhs.$field),+ }
+            }
+        }
+
+        impl ops::Div<f64> for $name {
+            type Output = $name;
+            fn div(self, rhs: f64) -> Self::Output {
+                $name { $($field: self.$field / rhs),+ }
+            }
+        }
+
+        impl ops::Neg for $name {
+            type Output = $name;
+            fn neg(self) -> Self::Output {
+                $name { $($field: -self.$field),+ }
+            }
+        }
+
+        impl ops::AddAssign<$name> for $name {
+            fn add_assign(&mut self, rhs: $name) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl ops::SubAssign<$name> for $name {
+            fn sub_assign(&mut self, rhs: $name) {
+                *self = *self - rhs;
+            }
+        }
+
+        impl ops::MulAssign<f64> for $name {
+            fn mul_assign(&mut self, rhs: f64) {
+                *self = *self * rhs;
+            }
+        }
+
+        impl ops::DivAssign<f64> for $name {
+            fn div_assign(&mut self, rhs: f64) {
+                *self = *self / rhs;
+            }
+        }
+
+        impl ops::Index<usize> for $name {
+            type Output = f64;
+            fn index(&self, index: usize) -> &Self::Output {
+                match index {
+                    $($index => &self.$field,)+
+                    _ => panic!("index {} out of range for {}", index, stringify!($name)),
+                }
+            }
+        }
+
+        impl ops::IndexMut<usize> for $name {
+            fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+                match index {
+                    $($index => &mut self.$field,)+
+                    _ => panic!("index {} out of range for {}", index, stringify!($name)),
+                }
+            }
+        }
+
+        impl std::iter::Sum for $name {
+            fn sum<I>(iter: I) -> Self
+            where
+                I: Iterator<Item = $name>,
+            {
+                iter.fold($name::ZERO, |a, b| a + b)
+            }
+        }
+    };
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Vec2 {
     pub x: f64,
     pub y: f64,
 }
 
 impl Vec2 {
-    pub fn new(x: f64, y: f64) -> Vec2 {
+    pub const fn new(x: f64, y: f64) -> Vec2 {
         Vec2 { x, y }
     }
-}
 
-impl ops::Sub<Vec2> for Vec2 {
-    type Output = Vec2;
-    fn sub(self, rhs: Vec2) -> Self::Output {
-        Vec2::new(self.x - rhs.x, self.y - rhs.y)
+    /// Z component of the 3D cross product, positive when `other` is
+    /// counter-clockwise from `self`.
+    pub fn cross(self, other: Vec2) -> f64 {
+        self.x * other.y - self.y * other.x
     }
 }
 
-impl ops::Mul<Vec2> for Vec2 {
-    type Output = Vec2;
-    fn mul(self, rhs: Vec2) -> Self::Output {
-        Vec2::new(self.x * rhs.x, self.y * rhs.y)
-    }
-}
+vector!(Vec2 { x: 0, y: 1 });
 
-impl ops::Mul<f64> for Vec2 {
-    type Output = Vec2;
-    fn mul(self, rhs: f64) -> Self::Output {
-        Vec2::new(self.x * rhs, self.y * rhs)
-    }
-}
-
-impl ops::Div<f64> for Vec2 {
-    type Output = Vec2;
-    fn div(self, rhs: f64) -> Self::Output {
-        Vec2::new(self.x / rhs, self.y / rhs)
-    }
-}
-
-#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Vec3 {
     pub x: f64,
     pub y: f64,
@@ -56,22 +147,22 @@ impl Vec3 {
     pub const Y: Self = Self::new(0.0, 1.0, 0.0);
     pub const Z: Self = Self::new(0.0, 0.0, 1.0);
 
+    pub fn dot(self, other: Vec3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
     pub fn length(&self) -> f64 {
         self.length2().sqrt()
     }
 
     pub fn length2(&self) -> f64 {
-        self.x * self.x + self.y * self.y + self.z * self.z
+        self.dot(*self)
     }
 
     pub fn normalize(self) -> Vec3 {
         self / self.length()
     }
 
-    pub fn dot(self, other: Vec3) -> f64 {
-        self.x * other.x + self.y * other.y + self.z * other.z
-    }
-
     pub fn cross(self, other: Vec3) -> Vec3 {
         Vec3::new(
             (self.y * other.z) - (self.z * other.y),
@@ -81,28 +172,9 @@ impl Vec3 {
     }
 }
 
-impl ops::Sub<Vec3> for Vec3 {
-    type Output = Vec3;
-    fn sub(self, rhs: Vec3) -> Self::Output {
-        Vec3::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
-    }
-}
-
-impl ops::Mul<f64> for Vec3 {
-    type Output = Vec3;
-    fn mul(self, rhs: f64) -> Self::Output {
-        Vec3::new(self.x * rhs, self.y * rhs, self.z * rhs)
-    }
-}
-
-impl ops::Div<f64> for Vec3 {
-    type Output = Vec3;
-    fn div(self, rhs: f64) -> Self::Output {
-        Vec3::new(self.x / rhs, self.y / rhs, self.z / rhs)
-    }
-}
+vector!(Vec3 { x: 0, y: 1, z: 2 });
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Vec4 {
     pub x: f64,
     pub y: f64,
@@ -118,54 +190,19 @@ impl Vec4 {
     pub fn from_slice(e: &[f64]) -> Vec4 {
         Vec4::new(e[0], e[1], e[2], e[3])
     }
-
-    pub const ZERO: Self = Self::new(0.0, 0.0, 0.0, 0.0);
-}
-
-impl ops::Add<Vec4> for Vec4 {
-    type Output = Vec4;
-    fn add(self, rhs: Vec4) -> Self::Output {
-        Vec4::new(
-            self.x + rhs.x,
-            self.y + rhs.y,
-            self.z + rhs.z,
-            self.w + rhs.w,
-        )
-    }
-}
-
-impl ops::Mul<f64> for Vec4 {
-    type Output = Vec4;
-    fn mul(self, rhs: f64) -> Self::Output {
-        Vec4::new(self.x * rhs, self.y * rhs, self.z * rhs, self.w * rhs)
-    }
-}
-
-impl ops::Index<usize> for Vec4 {
-    type Output = f64;
-    fn index(&self, index: usize) -> &Self::Output {
-        match index {
-            0 => &self.x,
-            1 => &self.y,
-            2 => &self.z,
-            3 => &self.w,
-            _ => &f64::NAN,
-        }
-    }
 }
 
-impl std::iter::Sum for Vec4 {
-    fn sum<I>(iter: I) -> Self
-    where
-        I: Iterator<Item = Vec4>,
-    {
-        iter.fold(Vec4::ZERO, |a, b| a + b)
-    }
-}
+vector!(Vec4 {
+    x: 0,
+    y: 1,
+    z: 2,
+    w: 3
+});
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[test]
     fn test_normalized() {
@@ -174,4 +211,74 @@ mod tests {
             Vec3::new(0.0, 0.4472135954999579277, 0.89442719099991585541)
         );
     }
+
+    #[test]
+    fn test_index() {
+        let mut v = Vec4::new(1.0, 2.0, 3.0, 4.0);
+        v[3] = 5.0;
+        v[0] += 1.0;
+        assert_eq!(v, Vec4::new(2.0, 2.0, 3.0, 5.0));
+        assert_eq!(Vec2::new(1.0, 2.0)[1], 2.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "index 4 out of range for Vec4")]
+    fn test_index_out_of_range() {
+        let _ = Vec4::ZERO[4];
+    }
+
+    #[test]
+    fn test_assign() {
+        let mut v = Vec2::new(1.0, 2.0);
+        v += Vec2::new(1.0, 1.0);
+        v *= 2.0;
+        v -= Vec2::new(0.0, 2.0);
+        v /= 2.0;
+        assert_eq!(v, Vec2::new(2.0, 2.0));
+        assert_eq!(-v + v, Vec2::ZERO);
+    }
+
+    fn component() -> impl Strategy<Value = f64> {
+        -1e3..1e3
+    }
+
+    fn vec3() -> impl Strategy<Value = Vec3> {
+        (component(), component(), component()).prop_map(|(x, y, z)| Vec3::new(x, y, z))
+    }
+
+    fn vec4() -> impl Strategy<Value = Vec4> {
+        (component(), component(), component(), component())
+            .prop_map(|(x, y, z, w)| Vec4::new(x, y, z, w))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_add_sub(a in vec4(), b in vec4()) {
+            prop_assert!((0..4).all(|i| ((a + b - b)[i] - a[i]).abs() <= 1e-9));
+            prop_assert_eq!(a + b, b + a);
+            prop_assert_eq!(a - b, -(b - a));
+        }
+
+        #[test]
+        fn prop_dot(a in vec3(), b in vec3(), s in component()) {
+            prop_assert_eq!(a.dot(b), b.dot(a));
+            prop_assert!(((a * s).dot(b) - s * a.dot(b)).abs() <= 1e-9 * (1.0 + (s * a.dot(b)).abs()));
+            prop_assert!(a.length2() >= 0.0);
+        }
+
+        #[test]
+        fn prop_cross(a in vec3(), b in vec3()) {
+            let c = a.cross(b);
+            let scale = a.length() * b.length() * (a.length() + b.length()) + 1.0;
+            prop_assert!(c.dot(a).abs() <= 1e-12 * scale);
+            prop_assert!(c.dot(b).abs() <= 1e-12 * scale);
+            prop_assert_eq!(a.cross(b), -b.cross(a));
+        }
+
+        #[test]
+        fn prop_normalize(a in vec3()) {
+            prop_assume!(a.length() > 1e-6);
+            prop_assert!((a.normalize().length() - 1.0).abs() < 1e-12);
+        }
+    }
 }
@@ -0,0 +1,67 @@
+use std::path::PathBuf;
+
+use crate::prim::RGB;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MaterialId(pub(super) u32);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImageId(pub(super) u32);
+
+/// Surface appearance shared by the nodes that use it, following the
+/// metallic-roughness model of glTF.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: String,
+    pub base_color: RGB,
+    /// 1 is fully opaque.
+    pub opacity: f64,
+    /// Multiplied with `base_color` and `opacity`.
+    pub base_color_texture: Option<ImageId>,
+    pub metallic: f64,
+    pub roughness: f64,
+    /// Roughness in the green channel and metalness in the blue one,
+    /// multiplied with the factors.
+    pub metallic_roughness_texture: Option<ImageId>,
+    /// Tangent space normal map.
+    pub normal_texture: Option<ImageId>,
+    pub emissive: RGB,
+    /// Whether back faces are visible.
+    pub double_sided: bool,
+}
+
+impl Material {
+    pub fn new(name: impl Into<String>) -> Material {
+        Material {
+            name: name.into(),
+            ..Material::default()
+        }
+    }
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            name: String::new(),
+            base_color: RGB::WHITE,
+            opacity: 1.0,
+            base_color_texture: None,
+            metallic: 0.0,
+            roughness: 0.5,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            emissive: RGB::BLACK,
+            double_sided: false,
+        }
+    }
+}
+
+/// Texture image referenced by materials.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image {
+    pub name: String,
+    /// File the image was loaded from, exporters refer to it instead of
+    /// writing a copy when possible.
+    pub path: Option<PathBuf>,
+    pub image: image::RgbaImage,
+}
@@ -0,0 +1,180 @@
+use anyhow::*;
+use serde::{Deserialize, Serialize};
+
+use crate::prim::{Pnt3, Vec2, Vec3, RGB};
+
+/// Identifies a mesh stored in a scene. Meshes are not modified after being
+/// added, so an id always refers to the same geometry.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MeshId(pub(super) u32);
+
+/// Indexed triangle mesh. Every attribute holds one entry per vertex, and
+/// every three indices form a counter-clockwise triangle.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mesh {
+    pub positions: Vec<Pnt3>,
+    pub normals: Vec<Vec3>,
+    pub uvs: Vec<Vec2>,
+    pub colors: Option<Vec<RGB>>,
+    pub indices: Vec<u32>,
+}
+
+impl Mesh {
+    /// Mesh with smooth normals and zero uvs.
+    pub fn new(positions: Vec<Pnt3>, indices: Vec<u32>) -> Mesh {
+        let mut mesh = Mesh {
+            uvs: vec![Vec2::default(); positions.len()],
+            positions,
+            indices,
+            ..Mesh::default()
+        };
+        mesh.compute_normals();
+        mesh
+    }
+
+    /// Axis aligned cube centered at the origin, with flat faces.
+    pub fn cube(size: f64) -> Mesh {
+        let h = size / 2.0;
+        let mut mesh = Mesh::default();
+        for (normal, u, v) in [
+            (Vec3::X, -Vec3::Z, Vec3::Y),
+            (-Vec3::X, Vec3::Z, Vec3::Y),
+            (Vec3::Y, Vec3::X, -Vec3::Z),
+            (-Vec3::Y, Vec3::X, Vec3::Z),
+            (Vec3::Z, Vec3::X, Vec3::Y),
+            (-Vec3::Z, -Vec3::X, Vec3::Y),
+        ] {
+            let base = mesh.positions.len() as u32;
+            for (s, t) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+                let offset = (normal + u * (2.0 * s - 1.0) + v * (2.0 * t - 1.0)) * h;
+                mesh.positions.push(Pnt3::ZERO + offset);
+                mesh.normals.push(normal);
+                mesh.uvs.push(Vec2::new(s, t));
+            }
+            mesh.indices
+                .extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+        }
+        mesh
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Checks that attributes and indices are consistent.
+    pub fn validate(&self) -> Result<()> {
+        let count = self.positions.len();
+        ensure!(
+            self.normals.len() == count,
+            "mesh has {} normals for {} vertices",
+            self.normals.len(),
+            count
+        );
+        ensure!(
+            self.uvs.len() == count,
+            "mesh has {} uvs for {} vertices",
+            self.uvs.len(),
+            count
+        );
+        if let Some(colors) = &self.colors {
+            ensure!(
+                colors.len() == count,
+                "mesh has {} colors for {} vertices",
+                colors.len(),
+                count
+            );
+        }
+        ensure!(
+            self.indices.len().is_multiple_of(3),
+            "mesh has {} indices, not a multiple of 3",
+            self.indices.len()
+        );
+        if let Some(index) = self.indices.iter().find(|&&i| i as usize >= count) {
+            bail!(
+                "mesh index {} is out of range for {} vertices",
+                index,
+                count
+            );
+        }
+        Ok(())
+    }
+
+    /// Replaces the normals by the area weighted average of the normals of
+    /// the triangles around each vertex.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3::ZERO; self.positions.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| triangle[i] as usize);
+            let p = |i: usize| self.positions[i];
+            let normal = (p(b) - p(a)).cross(p(c) - p(a));
+            for i in [a, b, c] {
+                normals[i] += normal;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|n| {
+                if n.length2() > 0.0 {
+                    n.normalize()
+                } else {
+                    Vec3::Z
+                }
+            })
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cube() {
+        let cube = Mesh::cube(2.0);
+        assert_eq!(cube.vertex_count(), 24);
+        assert_eq!(cube.triangle_count(), 12);
+        cube.validate().unwrap();
+        assert!(cube
+            .positions
+            .iter()
+            .all(|p| p.x.abs() == 1.0 && p.y.abs() == 1.0 && p.z.abs() == 1.0));
+
+        // triangles wind counter-clockwise around the outward normals
+        for triangle in cube.indices.chunks_exact(3) {
+            let p = |i: usize| cube.positions[triangle[i] as usize];
+            let normal = (p(1) - p(0)).cross(p(2) - p(0));
+            assert!(normal.dot(cube.normals[triangle[0] as usize]) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_compute_normals() {
+        let mesh = Mesh::new(
+            vec![
+                Pnt3::new(0.0, 0.0, 0.0),
+                Pnt3::new(1.0, 0.0, 0.0),
+                Pnt3::new(0.0, 1.0, 0.0),
+                Pnt3::new(5.0, 5.0, 5.0),
+            ],
+            vec![0, 1, 2],
+        );
+        assert_eq!(mesh.normals[0], Vec3::Z);
+        // unused vertices still get a unit normal
+        assert_eq!(mesh.normals[3].length(), 1.0);
+        mesh.validate().unwrap();
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut mesh = Mesh::cube(1.0);
+        mesh.indices.push(24);
+        assert!(mesh.validate().is_err());
+        mesh.indices.truncate(36);
+        mesh.colors = Some(vec![RGB::WHITE]);
+        assert!(mesh.validate().is_err());
+    }
+}
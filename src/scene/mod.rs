@@ -0,0 +1,9 @@
+mod material;
+mod mesh;
+mod node;
+mod scene;
+
+pub use material::{Image, ImageId, Material, MaterialId};
+pub use mesh::{Mesh, MeshId};
+pub use node::{NodeId, Transform};
+pub use scene::{Scene, SharedScene};
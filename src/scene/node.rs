@@ -0,0 +1,84 @@
+use std::cell::Cell;
+
+use serde::{Deserialize, Serialize};
+
+use super::material::MaterialId;
+use super::mesh::MeshId;
+use crate::prim::{Mat4, Quat, Vec3};
+
+/// Identifies a node for the lifetime of a scene, ids are never reused.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub(super) u32);
+
+/// Local transform of a node relative to its parent, applied as scale, then
+/// rotation, then translation.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::new(0.0, 0.0, 0.0),
+        rotation: Quat::IDENTITY,
+        scale: Vec3::new(1.0, 1.0, 1.0),
+    };
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_trs(self.translation, self.rotation, self.scale)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::IDENTITY
+    }
+}
+
+pub struct Node {
+    pub(super) name: String,
+    pub(super) parent: Option<NodeId>,
+    pub(super) children: Vec<NodeId>,
+    pub(super) transform: Transform,
+    pub(super) mesh: Option<MeshId>,
+    pub(super) material: Option<MaterialId>,
+    pub(super) world: Cell<Mat4>,
+    pub(super) dirty: Cell<bool>,
+}
+
+impl Node {
+    pub(super) fn new(name: String, parent: Option<NodeId>) -> Node {
+        Node {
+            name,
+            parent,
+            children: vec![],
+            transform: Transform::IDENTITY,
+            mesh: None,
+            material: None,
+            world: Cell::new(Mat4::I),
+            dirty: Cell::new(true),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    pub fn mesh(&self) -> Option<MeshId> {
+        self.mesh
+    }
+
+    pub fn material(&self) -> Option<MaterialId> {
+        self.material
+    }
+}
@@ -0,0 +1,458 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use anyhow::*;
+
+use super::material::{Image, ImageId, Material, MaterialId};
+use super::mesh::{Mesh, MeshId};
+use super::node::{Node, NodeId, Transform};
+use crate::prim::Mat4;
+
+/// Handle shared between the panels that show or edit the scene.
+pub type SharedScene = Rc<RefCell<Scene>>;
+
+/// Tree of named nodes with local transforms. World matrices are computed on
+/// demand and cached until a transform above them changes.
+#[derive(Default)]
+pub struct Scene {
+    nodes: HashMap<NodeId, Node>,
+    roots: Vec<NodeId>,
+    meshes: HashMap<MeshId, Mesh>,
+    materials: HashMap<MaterialId, Material>,
+    images: HashMap<ImageId, Image>,
+    selection: Vec<NodeId>,
+    next_id: u32,
+    next_mesh_id: u32,
+    next_material_id: u32,
+    next_image_id: u32,
+    revision: u64,
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene::default()
+    }
+
+    pub fn shared(self) -> SharedScene {
+        Rc::new(RefCell::new(self))
+    }
+
+    /// Increases with every change, so views can tell when to refresh.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn contains(&self, id: NodeId) -> bool {
+        self.nodes.contains_key(&id)
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(&id)
+    }
+
+    pub fn roots(&self) -> &[NodeId] {
+        &self.roots
+    }
+
+    /// Adds a node below `parent`, or at the top level. Panics if `parent`
+    /// is not part of the scene.
+    pub fn add(&mut self, name: impl Into<String>, parent: Option<NodeId>) -> NodeId {
+        let id = NodeId(self.next_id);
+        self.next_id += 1;
+        self.siblings_mut(parent).push(id);
+        self.nodes.insert(id, Node::new(name.into(), parent));
+        self.revision += 1;
+        id
+    }
+
+    /// Removes the node and everything below it, returns whether it existed.
+    pub fn remove(&mut self, id: NodeId) -> bool {
+        let parent = match self.nodes.get(&id) {
+            Some(node) => node.parent,
+            None => return false,
+        };
+        self.siblings_mut(parent).retain(|&x| x != id);
+
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            if let Some(node) = self.nodes.remove(&id) {
+                pending.extend(node.children);
+            }
+        }
+        let nodes = &self.nodes;
+        self.selection.retain(|x| nodes.contains_key(x));
+        self.revision += 1;
+        true
+    }
+
+    pub fn rename(&mut self, id: NodeId, name: impl Into<String>) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.name = name.into();
+            self.revision += 1;
+        }
+    }
+
+    pub fn set_transform(&mut self, id: NodeId, transform: Transform) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.transform = transform;
+            self.invalidate(id);
+            self.revision += 1;
+        }
+    }
+
+    /// Moves the node with its subtree below `parent`, keeping local
+    /// transforms.
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) -> Result<()> {
+        let old = match self.nodes.get(&id) {
+            Some(node) => node.parent,
+            None => bail!("node {:?} does not exist", id),
+        };
+        if let Some(parent) = parent {
+            if !self.contains(parent) {
+                bail!("node {:?} does not exist", parent);
+            }
+            if self.ancestors(parent).any(|x| x == id) {
+                bail!("cannot move node {:?} below its own descendant", id);
+            }
+        }
+
+        self.siblings_mut(old).retain(|&x| x != id);
+        self.siblings_mut(parent).push(id);
+        self.nodes.get_mut(&id).unwrap().parent = parent;
+        self.invalidate(id);
+        self.revision += 1;
+        Ok(())
+    }
+
+    /// Attaches a mesh to the node, or detaches it with `None`.
+    pub fn set_mesh(&mut self, id: NodeId, mesh: Option<MeshId>) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.mesh = mesh;
+            self.revision += 1;
+        }
+    }
+
+    pub fn mesh(&self, id: MeshId) -> Option<&Mesh> {
+        self.meshes.get(&id)
+    }
+
+    pub fn meshes(&self) -> impl Iterator<Item = (MeshId, &Mesh)> + '_ {
+        self.meshes.iter().map(|(&id, mesh)| (id, mesh))
+    }
+
+    /// Stores a mesh so nodes can refer to it, possibly several times.
+    pub fn add_mesh(&mut self, mesh: Mesh) -> MeshId {
+        let id = MeshId(self.next_mesh_id);
+        self.next_mesh_id += 1;
+        self.meshes.insert(id, mesh);
+        self.revision += 1;
+        id
+    }
+
+    /// Removes the mesh and detaches it from the nodes using it, returns
+    /// whether it existed.
+    pub fn remove_mesh(&mut self, id: MeshId) -> bool {
+        if self.meshes.remove(&id).is_none() {
+            return false;
+        }
+        for node in self.nodes.values_mut() {
+            if node.mesh == Some(id) {
+                node.mesh = None;
+            }
+        }
+        self.revision += 1;
+        true
+    }
+
+    pub fn set_material(&mut self, id: NodeId, material: Option<MaterialId>) {
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.material = material;
+            self.revision += 1;
+        }
+    }
+
+    pub fn material(&self, id: MaterialId) -> Option<&Material> {
+        self.materials.get(&id)
+    }
+
+    pub fn materials(&self) -> impl Iterator<Item = (MaterialId, &Material)> + '_ {
+        self.materials.iter().map(|(&id, material)| (id, material))
+    }
+
+    pub fn add_material(&mut self, material: Material) -> MaterialId {
+        let id = MaterialId(self.next_material_id);
+        self.next_material_id += 1;
+        self.materials.insert(id, material);
+        self.revision += 1;
+        id
+    }
+
+    pub fn set_material_data(&mut self, id: MaterialId, material: Material) {
+        if let Some(x) = self.materials.get_mut(&id) {
+            *x = material;
+            self.revision += 1;
+        }
+    }
+
+    pub fn image(&self, id: ImageId) -> Option<&Image> {
+        self.images.get(&id)
+    }
+
+    pub fn images(&self) -> impl Iterator<Item = (ImageId, &Image)> + '_ {
+        self.images.iter().map(|(&id, image)| (id, image))
+    }
+
+    /// Stores an image so materials can refer to it. Images are not
+    /// modified after being added, like meshes.
+    pub fn add_image(&mut self, image: Image) -> ImageId {
+        let id = ImageId(self.next_image_id);
+        self.next_image_id += 1;
+        self.images.insert(id, image);
+        self.revision += 1;
+        id
+    }
+
+    /// Selected nodes in the order they were selected.
+    pub fn selection(&self) -> &[NodeId] {
+        &self.selection
+    }
+
+    pub fn is_selected(&self, id: NodeId) -> bool {
+        self.selection.contains(&id)
+    }
+
+    /// Makes `id` the only selected node.
+    pub fn select(&mut self, id: NodeId) {
+        if self.contains(id) {
+            self.selection = vec![id];
+            self.revision += 1;
+        }
+    }
+
+    pub fn toggle_selected(&mut self, id: NodeId) {
+        if self.is_selected(id) {
+            self.selection.retain(|&x| x != id);
+        } else if self.contains(id) {
+            self.selection.push(id);
+        }
+        self.revision += 1;
+    }
+
+    pub fn clear_selection(&mut self) {
+        if !self.selection.is_empty() {
+            self.selection.clear();
+            self.revision += 1;
+        }
+    }
+
+    /// The node itself followed by its parent, grandparent and so on.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), move |x| self.nodes.get(x).and_then(|n| n.parent))
+            .filter(move |x| self.contains(*x))
+    }
+
+    /// Every node in depth first order, paired with its depth.
+    pub fn walk(&self) -> Vec<(NodeId, usize)> {
+        let mut result = vec![];
+        let mut pending: Vec<(NodeId, usize)> = self.roots.iter().rev().map(|&x| (x, 0)).collect();
+        while let Some((id, depth)) = pending.pop() {
+            result.push((id, depth));
+            let children = &self.nodes[&id].children;
+            pending.extend(children.iter().rev().map(|&x| (x, depth + 1)));
+        }
+        result
+    }
+
+    /// Transform from the node's local space to world space.
+    pub fn world_matrix(&self, id: NodeId) -> Mat4 {
+        let node = match self.nodes.get(&id) {
+            Some(node) => node,
+            None => return Mat4::I,
+        };
+        if node.dirty.get() {
+            let local = node.transform.matrix();
+            let world = match node.parent {
+                Some(parent) => self.world_matrix(parent) * local,
+                None => local,
+            };
+            node.world.set(world);
+            node.dirty.set(false);
+        }
+        node.world.get()
+    }
+
+    fn siblings_mut(&mut self, parent: Option<NodeId>) -> &mut Vec<NodeId> {
+        match parent {
+            Some(parent) => {
+                &mut self
+                    .nodes
+                    .get_mut(&parent)
+                    .expect("parent node does not exist")
+                    .children
+            }
+            None => &mut self.roots,
+        }
+    }
+
+    /// Marks the cached world matrix of the node and its descendants stale.
+    fn invalidate(&self, id: NodeId) {
+        let mut pending = vec![id];
+        while let Some(id) = pending.pop() {
+            let node = &self.nodes[&id];
+            // a clean node never sits below a dirty one, so stop at dirty ones
+            if !node.dirty.replace(true) {
+                pending.extend(node.children.iter().copied());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prim::{Quat, Vec3, RGB};
+
+    fn origin(scene: &Scene, id: NodeId) -> Vec3 {
+        let col = scene.world_matrix(id).col(3);
+        Vec3::new(col.x, col.y, col.z)
+    }
+
+    #[test]
+    fn test_hierarchy() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let b = scene.add("b", Some(a));
+        let c = scene.add("c", Some(b));
+        let d = scene.add("d", None);
+        assert_eq!(scene.len(), 4);
+        assert_eq!(scene.roots(), &[a, d]);
+        assert_eq!(scene.walk(), vec![(a, 0), (b, 1), (c, 2), (d, 0)]);
+        assert_eq!(scene.ancestors(c).collect::<Vec<_>>(), vec![c, b, a]);
+
+        assert!(scene.remove(b));
+        assert!(!scene.remove(b));
+        assert!(!scene.contains(c));
+        assert!(scene.node(a).unwrap().children().is_empty());
+
+        // ids are not reused after removal
+        let e = scene.add("e", None);
+        assert!(e != b && e != c);
+    }
+
+    #[test]
+    fn test_set_parent() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let b = scene.add("b", Some(a));
+        assert!(scene.set_parent(a, Some(b)).is_err());
+        assert!(scene.set_parent(a, Some(a)).is_err());
+
+        scene.set_parent(b, None).unwrap();
+        assert_eq!(scene.roots(), &[a, b]);
+        assert_eq!(scene.node(b).unwrap().parent, None);
+    }
+
+    #[test]
+    fn test_world_matrix_propagation() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let b = scene.add("b", Some(a));
+        let c = scene.add("c", Some(b));
+        scene.set_transform(
+            b,
+            Transform {
+                translation: Vec3::new(0.0, 1.0, 0.0),
+                ..Transform::IDENTITY
+            },
+        );
+        assert_eq!(origin(&scene, c), Vec3::new(0.0, 1.0, 0.0));
+
+        scene.set_transform(
+            a,
+            Transform {
+                translation: Vec3::new(1.0, 0.0, 0.0),
+                rotation: Quat::from_axis_angle(Vec3::Z, std::f64::consts::FRAC_PI_2),
+                scale: Vec3::new(2.0, 2.0, 2.0),
+            },
+        );
+        let p = origin(&scene, c);
+        assert!((p - Vec3::new(-1.0, 0.0, 0.0)).length() < 1e-12);
+
+        scene.set_parent(c, None).unwrap();
+        assert_eq!(origin(&scene, c), Vec3::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let b = scene.add("b", Some(a));
+        let c = scene.add("c", None);
+        scene.select(b);
+        scene.toggle_selected(c);
+        assert_eq!(scene.selection(), &[b, c]);
+        scene.toggle_selected(b);
+        assert_eq!(scene.selection(), &[c]);
+
+        scene.toggle_selected(b);
+        scene.remove(a);
+        assert_eq!(scene.selection(), &[c]);
+        scene.clear_selection();
+        assert!(!scene.is_selected(c));
+    }
+
+    #[test]
+    fn test_meshes() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let b = scene.add("b", None);
+        let cube = scene.add_mesh(Mesh::cube(1.0));
+        scene.set_mesh(a, Some(cube));
+        scene.set_mesh(b, Some(cube));
+        assert_eq!(scene.node(b).unwrap().mesh(), Some(cube));
+        assert_eq!(scene.mesh(cube).unwrap().triangle_count(), 12);
+
+        assert!(scene.remove_mesh(cube));
+        assert!(!scene.remove_mesh(cube));
+        assert_eq!(scene.node(a).unwrap().mesh(), None);
+        assert_eq!(scene.meshes().count(), 0);
+        assert!(scene.add_mesh(Mesh::cube(1.0)) != cube);
+    }
+
+    #[test]
+    fn test_materials() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let red = scene.add_material(Material {
+            base_color: RGB::RED,
+            ..Material::new("red")
+        });
+        scene.set_material(a, Some(red));
+        assert_eq!(scene.node(a).unwrap().material(), Some(red));
+        assert_eq!(scene.material(red).unwrap().name, "red");
+
+        let revision = scene.revision();
+        scene.set_material_data(red, Material::new("white"));
+        assert_eq!(scene.material(red).unwrap().base_color, RGB::WHITE);
+        assert!(scene.revision() > revision);
+    }
+
+    #[test]
+    fn test_revision() {
+        let mut scene = Scene::new();
+        let a = scene.add("a", None);
+        let revision = scene.revision();
+        scene.rename(a, "b");
+        assert_eq!(scene.node(a).unwrap().name(), "b");
+        assert!(scene.revision() > revision);
+    }
+}
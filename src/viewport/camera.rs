@@ -1,6 +1,45 @@
-use crate::prim::{Mat4, Pnt3, Vec3};
+use serde::{Deserialize, Serialize};
 
-#[derive(Default)]
+use crate::prim::{Mat4, Pnt3, Vec2, Vec3};
+
+/// Field of view a perspective camera narrows to while blending into an
+/// orthographic one.
+const BLEND_FOVY: f64 = 10.0 * std::f64::consts::PI / 180.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    /// `width` is the horizontal extent of the view volume in world units.
+    Orthographic { width: f64 },
+}
+
+/// Axis aligned views, named by the side of the scene they look at.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum View {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+}
+
+impl View {
+    /// Direction from the target towards the eye, and the up vector.
+    pub fn orientation(self) -> (Vec3, Vec3) {
+        match self {
+            View::Front => (Vec3::Z, Vec3::Y),
+            View::Back => (-Vec3::Z, Vec3::Y),
+            View::Left => (-Vec3::X, Vec3::Y),
+            View::Right => (Vec3::X, Vec3::Y),
+            View::Top => (Vec3::Y, -Vec3::Z),
+            View::Bottom => (-Vec3::Y, Vec3::Z),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Camera {
     pub eye: Pnt3,
     pub target: Pnt3,
@@ -9,6 +48,7 @@ pub struct Camera {
     pub fovy: f64,
     pub znear: f64,
     pub zfar: f64,
+    pub projection: Projection,
 }
 
 impl Camera {
@@ -22,9 +62,148 @@ impl Camera {
 
     pub fn build_view_projection_matrix(&self) -> Mat4 {
         let view = Mat4::lookat(self.eye, self.target, self.up);
-        let proj = Mat4::perspective(self.fovy, self.aspect, self.znear, self.zfar);
-        return Self::OPENGL_TO_WGPU_MATRIX * proj * view;
+        Self::OPENGL_TO_WGPU_MATRIX * self.build_projection_matrix() * view
+    }
+
+    pub fn build_projection_matrix(&self) -> Mat4 {
+        match self.projection {
+            Projection::Perspective => {
+                Mat4::perspective(self.fovy, self.aspect, self.znear, self.zfar)
+            }
+            Projection::Orthographic { .. } => {
+                // the eye position is arbitrary for an orthographic camera, so
+                // keep geometry behind it too
+                let half = self.half_extent();
+                Mat4::orthographic(-half.x, half.x, -half.y, half.y, -self.zfar, self.zfar)
+            }
+        }
+    }
+
+    pub fn distance(&self) -> f64 {
+        (self.target - self.eye).length()
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        (self.target - self.eye).normalize()
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.forward().cross(self.up).normalize()
+    }
+
+    /// Up direction of the image plane, unlike `up` which only hints the roll.
+    pub fn screen_up(&self) -> Vec3 {
+        self.right().cross(self.forward())
+    }
+
+    /// Half extent of the image plane at the target distance.
+    pub fn half_extent(&self) -> Vec2 {
+        match self.projection {
+            Projection::Perspective => {
+                let half_height = self.distance() * (self.fovy / 2.0).tan();
+                Vec2::new(half_height * self.aspect, half_height)
+            }
+            Projection::Orthographic { width } => Vec2::new(width / 2.0, width / 2.0 / self.aspect),
+        }
     }
+
+    /// Scales the visible extent by `factor` without moving the camera.
+    pub fn scale_width(&mut self, factor: f64) {
+        if let Projection::Orthographic { width } = &mut self.projection {
+            *width *= factor;
+        }
+    }
+
+    /// This camera looking at the same target from the same distance along
+    /// one of the axes.
+    pub fn looking_from(&self, view: View) -> Camera {
+        let (dir, up) = view.orientation();
+        Camera {
+            eye: self.target + dir * self.distance(),
+            up,
+            ..*self
+        }
+    }
+
+    /// This camera with the other projection, framing the target plane the
+    /// same way.
+    pub fn toggled_projection(&self) -> Camera {
+        let projection = match self.projection {
+            Projection::Perspective => Projection::Orthographic {
+                width: self.half_extent().x * 2.0,
+            },
+            Projection::Orthographic { width } => {
+                let distance = width / 2.0 / self.aspect / (self.fovy / 2.0).tan();
+                return Camera {
+                    eye: self.target - self.forward() * distance,
+                    projection: Projection::Perspective,
+                    ..*self
+                };
+            }
+        };
+        Camera {
+            projection,
+            ..*self
+        }
+    }
+
+    /// Blends from this camera to `other`. The view direction turns around the
+    /// target and the framing of the target plane is interpolated; between a
+    /// perspective and an orthographic camera the field of view narrows so
+    /// the switch at the end is barely visible.
+    pub fn lerp(&self, other: &Camera, t: f64) -> Camera {
+        if t >= 1.0 {
+            return *other;
+        }
+        let mix = |a: f64, b: f64| a + (b - a) * t;
+        let target = self.target + (other.target - self.target) * t;
+        let dir = slerp(-self.forward(), -other.forward(), t, self.screen_up());
+        let up = slerp(self.up.normalize(), other.up.normalize(), t, self.right());
+
+        match (self.projection, other.projection) {
+            (Projection::Orthographic { width: a }, Projection::Orthographic { width: b }) => {
+                Camera {
+                    eye: target + dir * mix(self.distance(), other.distance()),
+                    target,
+                    up,
+                    projection: Projection::Orthographic { width: mix(a, b) },
+                    ..*other
+                }
+            }
+            _ => {
+                let fovy = |c: &Camera| match c.projection {
+                    Projection::Perspective => c.fovy,
+                    Projection::Orthographic { .. } => BLEND_FOVY,
+                };
+                let fovy = mix(fovy(self), fovy(other));
+                let half_height = mix(self.half_extent().y, other.half_extent().y);
+                Camera {
+                    eye: target + dir * (half_height / (fovy / 2.0).tan()),
+                    target,
+                    up,
+                    fovy,
+                    projection: Projection::Perspective,
+                    ..*other
+                }
+            }
+        }
+    }
+}
+
+/// Spherical interpolation of unit vectors, turning around `axis` when they
+/// point in opposite directions.
+fn slerp(a: Vec3, b: Vec3, t: f64, axis: Vec3) -> Vec3 {
+    let angle = a.dot(b).clamp(-1.0, 1.0).acos();
+    if angle < 1e-9 {
+        return b;
+    }
+    let axis = if angle > std::f64::consts::PI - 1e-9 {
+        axis.normalize()
+    } else {
+        a.cross(b).normalize()
+    };
+    let (sin, cos) = (angle * t).sin_cos();
+    a * cos + axis.cross(a) * sin + axis * (axis.dot(a) * (1.0 - cos))
 }
 
 #[repr(C)]
@@ -75,6 +254,72 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_orthographic() {
+        let a = Mat4::orthographic(-2.0, 3.0, -1.0, 1.5, 0.1, 100.0);
+        let b = cgmath::ortho(-2.0, 3.0, -1.0, 1.5, 0.1, 100.0);
+        for col in 0..4 {
+            for row in 0..4 {
+                assert_eq!(a.e(col, row), b[col][row]);
+            }
+        }
+    }
+
+    fn camera() -> Camera {
+        Camera {
+            eye: Pnt3::new(1.0, 2.0, 3.0),
+            target: Pnt3::new(1.0, 0.0, 0.0),
+            up: Vec3::Y,
+            aspect: 1.5,
+            fovy: 45.0f64.to_radians(),
+            znear: 0.1,
+            zfar: 100.0,
+            projection: Projection::Perspective,
+        }
+    }
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_toggle_preserves_framing() {
+        let perspective = camera();
+        let orthographic = perspective.toggled_projection();
+        assert!(matches!(
+            orthographic.projection,
+            Projection::Orthographic { .. }
+        ));
+        assert_close(orthographic.half_extent().x, perspective.half_extent().x);
+        assert_close(orthographic.half_extent().y, perspective.half_extent().y);
+
+        let back = orthographic.toggled_projection();
+        assert_eq!(back.projection, Projection::Perspective);
+        assert_close(back.distance(), perspective.distance());
+    }
+
+    #[test]
+    fn test_looking_from() {
+        let camera = camera().looking_from(View::Top);
+        assert_eq!(camera.target, Pnt3::new(1.0, 0.0, 0.0));
+        assert_close(camera.eye.y, 13.0f64.sqrt());
+        assert_eq!(camera.forward(), -Vec3::Y);
+        assert_close(camera.right().x, 1.0);
+    }
+
+    #[test]
+    fn test_lerp() {
+        let from = camera().looking_from(View::Front);
+        let to = from.looking_from(View::Back).toggled_projection();
+        assert_eq!(from.lerp(&to, 0.0).eye, from.eye);
+        assert_eq!(from.lerp(&to, 1.0), to);
+
+        let half = from.lerp(&to, 0.5);
+        assert_eq!(half.projection, Projection::Perspective);
+        assert_close(half.half_extent().y, from.half_extent().y);
+        assert_close(half.forward().z, 0.0);
+    }
+
     #[test]
     fn test_perspective() {
         let a = Mat4::perspective(45.0f64.to_radians(), 4.0 / 3.0, 0.1, 100.0);
@@ -0,0 +1,409 @@
+use std::time::{Duration, Instant};
+
+use super::camera::{Camera, View};
+use crate::interface::{
+    ElementState, Event, KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode,
+};
+use crate::prim::{Pnt2, Pnt3, Quat, Vec2, Vec3};
+
+/// Radians turned per pixel of tumble drag.
+const TUMBLE_SPEED: f64 = 0.005;
+/// Exponential distance change per pixel of dolly drag.
+const DOLLY_SPEED: f64 = 0.005;
+/// Distance scale per wheel line.
+const ZOOM_STEP: f64 = 0.9;
+const MIN_DISTANCE: f64 = 1e-3;
+/// Keeps the view direction away from the poles where `up` degenerates.
+const MAX_ELEVATION: f64 = 89.0 * std::f64::consts::PI / 180.0;
+const TRANSITION: Duration = Duration::from_millis(250);
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode {
+    Tumble,
+    Track,
+    Dolly,
+}
+
+struct Transition {
+    from: Camera,
+    to: Camera,
+    start: Instant,
+}
+
+/// Turns viewport input into camera motion.
+///
+/// Alt + left/middle/right drag tumbles, tracks and dollies the camera.
+/// Middle drag alone tumbles, with shift to track and ctrl to dolly. The
+/// wheel zooms towards the point under the cursor.
+///
+/// Numpad 1, 3 and 7 switch to the front, right and top views, or the
+/// opposite ones with ctrl, and numpad 5 toggles orthographic projection.
+/// These changes are animated by [`CameraController::update`].
+#[derive(Default)]
+pub struct CameraController {
+    modifiers: ModifiersState,
+    mode: Option<Mode>,
+    size: Vec2,
+    transition: Option<Transition>,
+}
+
+impl CameraController {
+    /// Size of the viewport in pixels.
+    pub fn resize(&mut self, size: Vec2) {
+        self.size = size;
+    }
+
+    /// Whether a drag is moving the camera.
+    pub fn is_dragging(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    /// Returns whether the camera changed.
+    pub fn handle_event(&mut self, event: &Event, camera: &mut Camera) -> bool {
+        match *event {
+            Event::ModifiersChanged(state) => {
+                self.modifiers = state;
+                false
+            }
+            Event::MouseInput {
+                button,
+                state: ElementState::Pressed,
+                ..
+            } => {
+                self.mode = self.mode.or_else(|| self.mode_for(button));
+                self.mode.is_some() && self.finish(camera)
+            }
+            Event::MouseInput {
+                state: ElementState::Released,
+                ..
+            } => {
+                self.mode = None;
+                false
+            }
+            Event::CursorMoved { delta, .. } => match self.mode {
+                Some(Mode::Tumble) => {
+                    tumble(camera, delta);
+                    true
+                }
+                Some(Mode::Track) => {
+                    self.track(camera, delta);
+                    true
+                }
+                Some(Mode::Dolly) => {
+                    dolly(camera, (-(delta.x + delta.y) * DOLLY_SPEED).exp());
+                    true
+                }
+                None => false,
+            },
+            Event::MouseWheel { pos, delta } if delta.y != 0.0 => {
+                self.finish(camera);
+                self.zoom_to(camera, pos, ZOOM_STEP.powf(delta.y));
+                true
+            }
+            Event::Keyboard(KeyboardInput {
+                state: ElementState::Pressed,
+                virtual_keycode: Some(key),
+                ..
+            }) => self.key_pressed(key, camera),
+            _ => false,
+        }
+    }
+
+    /// Advances a running view transition, returns whether the camera changed.
+    pub fn update(&mut self, camera: &mut Camera, now: Instant) -> bool {
+        let transition = match &self.transition {
+            Some(transition) => transition,
+            None => return false,
+        };
+        let t = now
+            .saturating_duration_since(transition.start)
+            .as_secs_f64()
+            / TRANSITION.as_secs_f64();
+        if t >= 1.0 {
+            return self.finish(camera);
+        }
+        let t = t * t * (3.0 - 2.0 * t);
+        *camera = transition.from.lerp(&transition.to, t);
+        true
+    }
+
+    /// Jumps to the end of a running transition.
+    fn finish(&mut self, camera: &mut Camera) -> bool {
+        match self.transition.take() {
+            Some(transition) => {
+                *camera = transition.to;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn key_pressed(&mut self, key: VirtualKeyCode, camera: &mut Camera) -> bool {
+        let ctrl = self.modifiers.ctrl();
+        let view = match key {
+            VirtualKeyCode::Numpad1 if ctrl => Some(View::Back),
+            VirtualKeyCode::Numpad1 => Some(View::Front),
+            VirtualKeyCode::Numpad3 if ctrl => Some(View::Left),
+            VirtualKeyCode::Numpad3 => Some(View::Right),
+            VirtualKeyCode::Numpad7 if ctrl => Some(View::Bottom),
+            VirtualKeyCode::Numpad7 => Some(View::Top),
+            _ => None,
+        };
+        let changed = self.finish(camera);
+        let to = match (view, key) {
+            (Some(view), _) => camera.looking_from(view),
+            (None, VirtualKeyCode::Numpad5) => camera.toggled_projection(),
+            _ => return changed,
+        };
+        self.transition = Some(Transition {
+            from: *camera,
+            to,
+            start: Instant::now(),
+        });
+        changed
+    }
+
+    fn mode_for(&self, button: MouseButton) -> Option<Mode> {
+        if self.modifiers.alt() {
+            match button {
+                MouseButton::Left => Some(Mode::Tumble),
+                MouseButton::Middle => Some(Mode::Track),
+                MouseButton::Right => Some(Mode::Dolly),
+                _ => None,
+            }
+        } else if button == MouseButton::Middle {
+            if self.modifiers.shift() {
+                Some(Mode::Track)
+            } else if self.modifiers.ctrl() {
+                Some(Mode::Dolly)
+            } else {
+                Some(Mode::Tumble)
+            }
+        } else {
+            None
+        }
+    }
+
+    /// World units covered by one pixel on the plane through the target.
+    fn pixel_size(&self, camera: &Camera) -> f64 {
+        if self.size.y > 0.0 {
+            2.0 * camera.half_extent().y / self.size.y
+        } else {
+            0.0
+        }
+    }
+
+    /// Moves the camera in its image plane so the scene follows the cursor.
+    fn track(&self, camera: &mut Camera, delta: Vec2) {
+        let scale = self.pixel_size(camera);
+        let offset = (camera.right() * delta.x + camera.screen_up() * delta.y) * -scale;
+        camera.eye += offset;
+        camera.target += offset;
+    }
+
+    /// Scales the camera distance by `factor` around the point under `pos`
+    /// on the target plane, so that point stays under the cursor.
+    fn zoom_to(&self, camera: &mut Camera, pos: Pnt2, factor: f64) {
+        let factor = factor.max(MIN_DISTANCE / camera.distance());
+        let anchor = if self.size.x > 0.0 && self.size.y > 0.0 {
+            let ndc = Vec2::new(
+                pos.x / self.size.x * 2.0 - 1.0,
+                pos.y / self.size.y * 2.0 - 1.0,
+            );
+            let half = camera.half_extent();
+            camera.target
+                + camera.right() * (ndc.x * half.x)
+                + camera.screen_up() * (ndc.y * half.y)
+        } else {
+            camera.target
+        };
+        camera.eye = scale_about(camera.eye, anchor, factor);
+        camera.target = scale_about(camera.target, anchor, factor);
+        camera.scale_width(factor);
+    }
+}
+
+fn scale_about(p: Pnt3, anchor: Pnt3, factor: f64) -> Pnt3 {
+    anchor + (p - anchor) * factor
+}
+
+/// Orbits the eye around the target, horizontal motion turns around the
+/// world up axis and vertical motion changes the elevation. Leaves the
+/// camera upright, e.g. after a top view.
+fn tumble(camera: &mut Camera, delta: Vec2) {
+    let offset = camera.eye - camera.target;
+    let distance = offset.length();
+    let up = Vec3::Y;
+
+    let elevation = (offset.dot(up) / distance).clamp(-1.0, 1.0).asin();
+    let pitch =
+        (elevation - delta.y * TUMBLE_SPEED).clamp(-MAX_ELEVATION, MAX_ELEVATION) - elevation;
+    let rotation = Quat::from_axis_angle(up, -delta.x * TUMBLE_SPEED)
+        * Quat::from_axis_angle(camera.right(), -pitch);
+
+    camera.eye = camera.target + rotation * offset;
+    camera.up = up;
+}
+
+/// Scales the distance between eye and target by `factor`.
+fn dolly(camera: &mut Camera, factor: f64) {
+    let factor = factor.max(MIN_DISTANCE / camera.distance());
+    camera.eye = camera.target - camera.forward() * (camera.distance() * factor);
+    camera.scale_width(factor);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::camera::Projection;
+    use super::*;
+
+    const EPSILON: f64 = 1e-9;
+
+    fn camera() -> Camera {
+        Camera {
+            eye: Pnt3::new(0.0, 0.0, 5.0),
+            target: Pnt3::ZERO,
+            up: Vec3::Y,
+            aspect: 2.0,
+            fovy: 90f64.to_radians(),
+            znear: 0.1,
+            zfar: 100.0,
+            projection: Projection::Perspective,
+        }
+    }
+
+    fn controller() -> CameraController {
+        let mut controller = CameraController::default();
+        controller.resize(Vec2::new(200.0, 100.0));
+        controller
+    }
+
+    fn press(controller: &mut CameraController, camera: &mut Camera, button: MouseButton) {
+        controller.handle_event(
+            &Event::MouseInput {
+                pos: Pnt2::new(100.0, 50.0),
+                button,
+                state: ElementState::Pressed,
+            },
+            camera,
+        );
+    }
+
+    fn drag(controller: &mut CameraController, camera: &mut Camera, x: f64, y: f64) -> bool {
+        controller.handle_event(
+            &Event::CursorMoved {
+                delta: Vec2::new(x, y),
+            },
+            camera,
+        )
+    }
+
+    #[test]
+    fn test_tumble_keeps_distance() {
+        let mut camera = camera();
+        let mut controller = controller();
+        press(&mut controller, &mut camera, MouseButton::Middle);
+        assert!(drag(&mut controller, &mut camera, 40.0, 25.0));
+        assert!((camera.distance() - 5.0).abs() < EPSILON);
+        assert_eq!(camera.target, Pnt3::ZERO);
+        assert!(camera.eye.x < 0.0);
+        assert!(camera.eye.y < 0.0);
+    }
+
+    #[test]
+    fn test_tumble_clamps_elevation() {
+        let mut camera = camera();
+        let mut controller = controller();
+        press(&mut controller, &mut camera, MouseButton::Middle);
+        drag(&mut controller, &mut camera, 0.0, -10000.0);
+        let elevation = (camera.eye.y / camera.distance()).asin();
+        assert!((elevation - MAX_ELEVATION).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_track_moves_eye_and_target() {
+        let mut camera = camera();
+        let mut controller = controller();
+        controller.handle_event(&Event::ModifiersChanged(ModifiersState::SHIFT), &mut camera);
+        press(&mut controller, &mut camera, MouseButton::Middle);
+        drag(&mut controller, &mut camera, 10.0, 0.0);
+        // 100 pixels span 2 * 5 * tan(45°) = 10 units
+        assert!((camera.target.x + 1.0).abs() < EPSILON);
+        assert!((camera.eye.x + 1.0).abs() < EPSILON);
+        assert!((camera.distance() - 5.0).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_release_stops_drag() {
+        let mut camera = camera();
+        let mut controller = controller();
+        press(&mut controller, &mut camera, MouseButton::Middle);
+        controller.handle_event(
+            &Event::MouseInput {
+                pos: Pnt2::new(100.0, 50.0),
+                button: MouseButton::Middle,
+                state: ElementState::Released,
+            },
+            &mut camera,
+        );
+        assert!(!drag(&mut controller, &mut camera, 10.0, 10.0));
+        assert_eq!(camera.eye, Pnt3::new(0.0, 0.0, 5.0));
+    }
+
+    #[test]
+    fn test_standard_view_transition() {
+        let mut camera = camera();
+        let mut controller = controller();
+        let key = |virtual_keycode| {
+            #[allow(deprecated)]
+            Event::Keyboard(KeyboardInput {
+                scancode: 0,
+                state: ElementState::Pressed,
+                virtual_keycode: Some(virtual_keycode),
+                modifiers: ModifiersState::empty(),
+            })
+        };
+        controller.handle_event(&key(VirtualKeyCode::Numpad7), &mut camera);
+        assert_eq!(camera.eye, Pnt3::new(0.0, 0.0, 5.0));
+
+        let now = Instant::now();
+        assert!(controller.update(&mut camera, now));
+        assert!(camera.eye.y < 5.0);
+        assert!(controller.update(&mut camera, now + TRANSITION));
+        assert!((camera.eye.y - 5.0).abs() < EPSILON);
+        assert!(!controller.update(&mut camera, now + TRANSITION));
+
+        // a drag skips whatever is left of the transition
+        let perspective = camera;
+        controller.handle_event(&key(VirtualKeyCode::Numpad5), &mut camera);
+        press(&mut controller, &mut camera, MouseButton::Middle);
+        assert!((camera.forward() - -Vec3::Y).length() < EPSILON);
+        assert!((camera.up - -Vec3::Z).length() < EPSILON);
+        // the orthographic view frames the target plane as before
+        match camera.projection {
+            Projection::Orthographic { width } => {
+                let expected = 2.0 * 5.0 * (perspective.fovy / 2.0).tan() * perspective.aspect;
+                assert!((width - expected).abs() < EPSILON);
+            }
+            _ => panic!("expected an orthographic camera"),
+        }
+        assert!(!controller.update(&mut camera, now + TRANSITION * 2));
+    }
+
+    #[test]
+    fn test_zoom_to_cursor() {
+        let mut camera = camera();
+        let mut controller = controller();
+        // the right edge of the viewport sits at x = 10 on the target plane
+        controller.handle_event(
+            &Event::MouseWheel {
+                pos: Pnt2::new(200.0, 50.0),
+                delta: Vec2::new(0.0, 1.0),
+            },
+            &mut camera,
+        );
+        assert!((camera.distance() - 4.5).abs() < EPSILON);
+        assert!((camera.target.x - 1.0).abs() < EPSILON);
+        assert!((camera.eye.x - 1.0).abs() < EPSILON);
+    }
+}
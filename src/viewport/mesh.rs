@@ -0,0 +1,103 @@
+use wgpu::util::DeviceExt;
+
+use crate::interface::VertexMesh;
+use crate::prim::{Aabb3, Mat4, Vec3, RGB};
+use crate::scene::Mesh;
+
+/// Mesh uploaded to persistent vertex and index buffers.
+pub struct GpuMesh {
+    pub vertices: wgpu::Buffer,
+    pub indices: wgpu::Buffer,
+    pub count: u32,
+    /// Box around the positions, for culling.
+    pub bounds: Aabb3,
+}
+
+impl GpuMesh {
+    pub fn new(device: &wgpu::Device, mesh: &Mesh) -> GpuMesh {
+        let vertices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Vertex Buffer"),
+            contents: bytemuck::cast_slice(&vertices(mesh)),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+        let indices = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Mesh Index Buffer"),
+            contents: bytemuck::cast_slice(&mesh.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+        GpuMesh {
+            vertices,
+            indices,
+            count: mesh.indices.len() as u32,
+            bounds: Aabb3::from_points(mesh.positions.iter().copied()),
+        }
+    }
+}
+
+/// Interleaves the attributes of `mesh`, missing colors default to white.
+pub fn vertices(mesh: &Mesh) -> Vec<VertexMesh> {
+    (0..mesh.positions.len())
+        .map(|i| {
+            let p = mesh.positions[i];
+            let n = mesh.normals.get(i).copied().unwrap_or(Vec3::Z);
+            let uv = mesh.uvs.get(i).copied().unwrap_or_default();
+            let color = match &mesh.colors {
+                Some(colors) => colors[i],
+                None => RGB::WHITE,
+            };
+            VertexMesh {
+                position: [p.x as f32, p.y as f32, p.z as f32],
+                normal: [n.x as f32, n.y as f32, n.z as f32],
+                uv: [uv.x as f32, uv.y as f32],
+                color: [color.r as f32, color.g as f32, color.b as f32],
+            }
+        })
+        .collect()
+}
+
+/// Per node data, stored at `ModelUniform::STRIDE` intervals in a single
+/// buffer and selected with a dynamic offset.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ModelUniform {
+    pub model: [f32; 16],
+    pub normal: [f32; 16],
+    pub color: [f32; 3],
+    /// 1 when the model matrix mirrors, turning front faces into back faces.
+    pub mirrored: f32,
+}
+
+impl ModelUniform {
+    /// Default `min_uniform_buffer_offset_alignment`.
+    pub const STRIDE: wgpu::BufferAddress = 256;
+
+    pub fn new(model: Mat4, color: RGB) -> Self {
+        Self {
+            model: model.into(),
+            normal: model.normal_matrix().into(),
+            color: [color.r as f32, color.g as f32, color.b as f32],
+            mirrored: if mirrored(model) { 1.0 } else { 0.0 },
+        }
+    }
+}
+
+fn mirrored(model: Mat4) -> bool {
+    model.determinant() < 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vertices() {
+        let mut mesh = Mesh::cube(1.0);
+        let interleaved = vertices(&mesh);
+        assert_eq!(interleaved.len(), 24);
+        assert_eq!(interleaved[0].color, [1.0, 1.0, 1.0]);
+        assert_eq!(interleaved[0].normal, [1.0, 0.0, 0.0]);
+
+        mesh.colors = Some(vec![RGB::RED; 24]);
+        assert_eq!(vertices(&mesh)[5].color, [1.0, 0.0, 0.0]);
+    }
+}
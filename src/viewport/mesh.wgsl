@@ -0,0 +1,94 @@
+// Vertex shader
+struct CameraUniform {
+    view_proj: mat4x4<f32>;
+};
+[[group(0), binding(0)]]
+var<uniform> camera: CameraUniform;
+
+struct ModelUniform {
+    model: mat4x4<f32>;
+    normal: mat4x4<f32>;
+    color: vec3<f32>;
+    mirrored: f32;
+};
+[[group(1), binding(0)]]
+var<uniform> model: ModelUniform;
+
+struct VertexInput {
+    [[location(0)]] position: vec3<f32>;
+    [[location(1)]] normal: vec3<f32>;
+    [[location(2)]] uv: vec2<f32>;
+    [[location(3)]] color: vec3<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] normal: vec3<f32>;
+    [[location(1)]] color: vec3<f32>;
+    [[location(2)]] mirrored: f32;
+    [[location(3)]] uv: vec2<f32>;
+};
+
+[[stage(vertex)]]
+fn vs_main(
+    vertex: VertexInput,
+) -> VertexOutput {
+    var out: VertexOutput;
+    out.normal = (model.normal * vec4<f32>(vertex.normal, 0.0)).xyz;
+    out.color = vertex.color * model.color;
+    out.mirrored = model.mirrored;
+    out.uv = vertex.uv;
+    out.clip_position = camera.view_proj * model.model * vec4<f32>(vertex.position, 1.0);
+    return out;
+}
+
+// Fragment shader
+
+[[group(2), binding(0)]]
+var t_base: texture_2d<f32>;
+[[group(2), binding(1)]]
+var s_base: sampler;
+
+[[stage(fragment)]]
+fn fs_main(
+    in: VertexOutput,
+    [[builtin(front_facing)]] front_facing: bool,
+) -> [[location(0)]] vec4<f32> {
+    var normal = normalize(in.normal);
+    if (front_facing == (in.mirrored > 0.5)) {
+        normal = -normal;
+    }
+    // a key light from the upper front and a dim fill light from behind
+    let key = max(dot(normal, normalize(vec3<f32>(0.4, 0.8, 0.6))), 0.0);
+    let fill = max(dot(normal, normalize(vec3<f32>(-0.6, 0.2, -0.4))), 0.0) * 0.3;
+    let light = 0.25 + key * 0.75 + fill;
+    let base = in.color * textureSample(t_base, s_base, in.uv).rgb;
+    return vec4<f32>(base * light, 1.0);
+}
+
+// Background
+
+struct BackgroundOutput {
+    [[builtin(position)]] clip_position: vec4<f32>;
+    [[location(0)]] height: f32;
+};
+
+[[stage(vertex)]]
+fn vs_background(
+    [[builtin(vertex_index)]] index: u32,
+) -> BackgroundOutput {
+    // a single triangle covering the viewport
+    let x = f32(i32(index & 1u) * 4 - 1);
+    let y = f32(i32(index >> 1u) * 4 - 1);
+    var out: BackgroundOutput;
+    out.clip_position = vec4<f32>(x, y, 1.0, 1.0);
+    out.height = y * 0.5 + 0.5;
+    return out;
+}
+
+[[stage(fragment)]]
+fn fs_background(in: BackgroundOutput) -> [[location(0)]] vec4<f32> {
+    let bottom = vec3<f32>(0.10, 0.10, 0.11);
+    let top = vec3<f32>(0.24, 0.25, 0.28);
+    return vec4<f32>(mix(bottom, top, clamp(in.height, 0.0, 1.0)), 1.0);
+}
@@ -1,4 +1,6 @@
 mod camera;
+mod controller;
+mod mesh;
 mod viewport;
 
 pub use viewport::Viewport;
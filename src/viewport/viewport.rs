@@ -1,32 +1,230 @@
+use std::collections::HashMap;
+use std::time::Instant;
 use wgpu::util::DeviceExt;
 
 use super::camera;
+use super::controller::CameraController;
+use super::mesh::{GpuMesh, ModelUniform};
 use crate::interface::*;
 use crate::prim::*;
+use crate::scene::{ImageId, MaterialId, MeshId, NodeId, Scene};
+
+/// Base color of meshes without material, multiplied with their vertex
+/// colors.
+const DEFAULT: RGB = RGB::new(0.8, 0.8, 0.8);
+const SELECTED: RGB = RGB::new(1.0, 0.6, 0.25);
 
 #[derive(Default)]
 pub struct Viewport {
     pipeline: Option<wgpu::RenderPipeline>,
-    diffuse_bind_group: Option<wgpu::BindGroup>,
+    background_pipeline: Option<wgpu::RenderPipeline>,
     camera_bind_group: Option<wgpu::BindGroup>,
+    camera_buffer: Option<wgpu::Buffer>,
     camera: camera::Camera,
+    controller: CameraController,
+    modifiers: ModifiersState,
+    size: Vec2,
+    camera_dirty: bool,
+    /// Whether `load_state` set the camera, which `init` then keeps.
+    camera_restored: bool,
+    depth: Option<Texture>,
+    meshes: HashMap<MeshId, GpuMesh>,
+    model_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    model_buffer: Option<wgpu::Buffer>,
+    model_bind_group: Option<wgpu::BindGroup>,
+    model_capacity: usize,
+    texture_bind_group_layout: Option<wgpu::BindGroupLayout>,
+    sampler: Option<wgpu::Sampler>,
+    textures: HashMap<ImageId, (Texture, wgpu::BindGroup)>,
+    white: Option<(Texture, wgpu::BindGroup)>,
+}
+
+impl Viewport {
+    fn upload_camera(&self, context: &ContextGlobal) {
+        let mut camera_uniform = camera::CameraUniform::new();
+        camera_uniform.update_view_proj(&self.camera);
+        context.queue.write_buffer(
+            self.camera_buffer.as_ref().unwrap(),
+            0,
+            bytemuck::cast_slice(&[camera_uniform]),
+        );
+    }
+
+    fn texture(
+        &self,
+        context: &ContextGlobal,
+        image: &image::RgbaImage,
+        label: &str,
+    ) -> (Texture, wgpu::BindGroup) {
+        let texture = Texture::from_image(
+            &context.device,
+            &context.queue,
+            &image::DynamicImage::ImageRgba8(image.clone()),
+            Some(label),
+        )
+        .unwrap();
+        let bind_group = context
+            .device
+            .create_bind_group(&wgpu::BindGroupDescriptor {
+                layout: self.texture_bind_group_layout.as_ref().unwrap(),
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        // glTF and OBJ textures repeat, unlike the texture's own sampler
+                        resource: wgpu::BindingResource::Sampler(self.sampler.as_ref().unwrap()),
+                    },
+                ],
+                label: Some("base_color_bind_group"),
+            });
+        (texture, bind_group)
+    }
+
+    /// Base color texture of the node's material, uploaded on first use.
+    fn node_texture(
+        &mut self,
+        context: &ContextGlobal,
+        scene: &Scene,
+        material: Option<MaterialId>,
+    ) -> Option<ImageId> {
+        let id = material
+            .and_then(|x| scene.material(x))?
+            .base_color_texture?;
+        let image = scene.image(id)?;
+        if !self.textures.contains_key(&id) {
+            let texture = self.texture(context, &image.image, &image.name);
+            self.textures.insert(id, texture);
+        }
+        Some(id)
+    }
+
+    /// Selects the mesh under `pos`, or toggles it with ctrl or shift held.
+    /// Clicking empty space clears the selection.
+    fn click(&self, pos: Pnt2, scene: &mut Scene) {
+        if self.size.x <= 0.0 || self.size.y <= 0.0 {
+            return;
+        }
+        let ndc = Pnt2::new(
+            pos.x / self.size.x * 2.0 - 1.0,
+            pos.y / self.size.y * 2.0 - 1.0,
+        );
+        let ray = match Ray::unproject(ndc, &self.camera.build_view_projection_matrix()) {
+            Some(ray) => ray,
+            None => return,
+        };
+        let bounds = |id| self.meshes.get(&id).map(|mesh| mesh.bounds);
+        match pick(scene, &ray, bounds) {
+            Some(id) if self.modifiers.ctrl() || self.modifiers.shift() => {
+                scene.toggle_selected(id)
+            }
+            Some(id) => scene.select(id),
+            None => scene.clear_selection(),
+        }
+    }
+
+    /// Uploads meshes and textures new to the scene, drops the ones it no
+    /// longer holds, and writes the model uniforms of the meshes in view.
+    /// Returns the mesh and texture of every uniform slot.
+    fn prepare_meshes(&mut self, context: &ContextGlobal) -> Vec<(MeshId, Option<ImageId>)> {
+        let scene = context.scene.borrow();
+        self.meshes.retain(|&id, _| scene.mesh(id).is_some());
+        self.textures.retain(|&id, _| scene.image(id).is_some());
+
+        let frustum = Frustum::from_matrix(&self.camera.build_view_projection_matrix());
+        let mut draws = vec![];
+        let mut uniforms = vec![];
+        for (id, _) in scene.walk() {
+            let node = scene.node(id).unwrap();
+            let mesh_id = match node.mesh() {
+                Some(mesh_id) => mesh_id,
+                None => continue,
+            };
+            let mesh = match scene.mesh(mesh_id) {
+                Some(mesh) if !mesh.indices.is_empty() => mesh,
+                _ => continue,
+            };
+            let gpu_mesh = self
+                .meshes
+                .entry(mesh_id)
+                .or_insert_with(|| GpuMesh::new(&context.device, mesh));
+            let world = scene.world_matrix(id);
+            if !is_visible(&frustum, &gpu_mesh.bounds, &world) {
+                continue;
+            }
+            let color = match node.material().and_then(|x| scene.material(x)) {
+                _ if scene.is_selected(id) => SELECTED,
+                Some(material) => material.base_color,
+                None => DEFAULT,
+            };
+            uniforms.push(ModelUniform::new(world, color));
+            let texture = self.node_texture(context, &scene, node.material());
+            draws.push((mesh_id, texture));
+        }
+
+        if uniforms.len() > self.model_capacity || self.model_buffer.is_none() {
+            self.model_capacity = uniforms.len().next_power_of_two().max(16);
+            let buffer = context.device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("Model Buffer"),
+                size: self.model_capacity as wgpu::BufferAddress * ModelUniform::STRIDE,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            });
+            self.model_bind_group = Some(context.device.create_bind_group(
+                &wgpu::BindGroupDescriptor {
+                    layout: self.model_bind_group_layout.as_ref().unwrap(),
+                    entries: &[wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer: &buffer,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(std::mem::size_of::<ModelUniform>() as u64),
+                        }),
+                    }],
+                    label: Some("model_bind_group"),
+                },
+            ));
+            self.model_buffer = Some(buffer);
+        }
+
+        let stride = ModelUniform::STRIDE as usize;
+        let mut bytes = vec![0u8; uniforms.len() * stride];
+        for (chunk, uniform) in bytes.chunks_exact_mut(stride).zip(&uniforms) {
+            let uniform = bytemuck::bytes_of(uniform);
+            chunk[..uniform.len()].copy_from_slice(uniform);
+        }
+        if !bytes.is_empty() {
+            context
+                .queue
+                .write_buffer(self.model_buffer.as_ref().unwrap(), 0, &bytes);
+        }
+        draws
+    }
 }
 
 impl Panel for Viewport {
+    fn kind(&self) -> &'static str {
+        "viewport"
+    }
+
     fn init(&mut self, context: &ContextGlobal) {
         let device = &context.device;
         let config = &context.config;
 
-        // init camera
-        self.camera = camera::Camera {
-            eye: Pnt3::new(0.0, 1.0, 2.0),
-            target: Pnt3::ZERO,
-            up: Vec3::Y,
-            aspect: config.width as f64 / config.height as f64,
-            fovy: 45.0f64.to_radians(),
-            znear: 0.1,
-            zfar: 100.0,
-        };
+        if !self.camera_restored {
+            self.camera = camera::Camera {
+                eye: Pnt3::new(0.0, 1.0, 2.0),
+                target: Pnt3::ZERO,
+                up: Vec3::Y,
+                aspect: 1.0,
+                fovy: 45.0f64.to_radians(),
+                znear: 0.1,
+                zfar: 100.0,
+                projection: camera::Projection::Perspective,
+            };
+        }
         let mut camera_uniform = camera::CameraUniform::new();
         camera_uniform.update_view_proj(&self.camera);
         let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -56,11 +254,27 @@ impl Panel for Viewport {
             }],
             label: Some("camera_bind_group"),
         }));
+        self.camera_buffer = Some(camera_buffer);
 
-        // init diffuse bind group
-        let diffuse_bytes = include_bytes!("happy-tree.png");
-        let diffuse_texture =
-            Texture::from_bytes(&device, &context.queue, diffuse_bytes, "happy-tree").unwrap();
+        // init model uniforms, one slot per drawn node
+        let model_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                entries: &[wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(
+                            std::mem::size_of::<ModelUniform>() as u64,
+                        ),
+                    },
+                    count: None,
+                }],
+                label: Some("model_bind_group_layout"),
+            });
+
+        // init base color textures, meshes without one sample plain white
         let texture_bind_group_layout =
             device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                 entries: &[
@@ -83,31 +297,30 @@ impl Panel for Viewport {
                 ],
                 label: Some("texture_bind_group_layout"),
             });
-        self.diffuse_bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
-            layout: &texture_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: wgpu::BindingResource::TextureView(&diffuse_texture.view),
-                },
-                wgpu::BindGroupEntry {
-                    binding: 1,
-                    resource: wgpu::BindingResource::Sampler(&diffuse_texture.sampler),
-                },
-            ],
-            label: Some("diffuse_bind_group"),
+        self.sampler = Some(device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
         }));
 
         let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
-            label: Some("Texture Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("texture.wgsl").into()),
+            label: Some("Mesh Shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("mesh.wgsl").into()),
         });
 
-        // init pipeline
+        // init pipelines
         let render_pipeline_layout =
             device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Viewport Render Pipeline Layout"),
-                bind_group_layouts: &[&texture_bind_group_layout, &camera_bind_group_layout],
+                bind_group_layouts: &[
+                    &camera_bind_group_layout,
+                    &model_bind_group_layout,
+                    &texture_bind_group_layout,
+                ],
                 push_constant_ranges: &[],
             });
         self.pipeline = Some(
@@ -117,7 +330,7 @@ impl Panel for Viewport {
                 vertex: wgpu::VertexState {
                     module: &shader,
                     entry_point: "vs_main",
-                    buffers: &[VertexUV::desc()],
+                    buffers: &[VertexMesh::desc()],
                 },
                 fragment: Some(wgpu::FragmentState {
                     module: &shader,
@@ -132,12 +345,19 @@ impl Panel for Viewport {
                     topology: wgpu::PrimitiveTopology::TriangleList,
                     strip_index_format: None,
                     front_face: wgpu::FrontFace::Ccw,
-                    cull_mode: Some(wgpu::Face::Back),
+                    // imported meshes are not always closed, so show both sides
+                    cull_mode: None,
                     polygon_mode: wgpu::PolygonMode::Fill,
                     unclipped_depth: false,
                     conservative: false,
                 },
-                depth_stencil: None,
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: true,
+                    depth_compare: wgpu::CompareFunction::Less,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
                 multisample: wgpu::MultisampleState {
                     count: 1,
                     mask: !0,
@@ -146,9 +366,103 @@ impl Panel for Viewport {
                 multiview: None,
             }),
         );
+
+        let background_pipeline_layout =
+            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Viewport Background Pipeline Layout"),
+                bind_group_layouts: &[],
+                push_constant_ranges: &[],
+            });
+        self.background_pipeline = Some(device.create_render_pipeline(
+            &wgpu::RenderPipelineDescriptor {
+                label: Some("Viewport Background Pipeline"),
+                layout: Some(&background_pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &shader,
+                    entry_point: "vs_background",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &shader,
+                    entry_point: "fs_background",
+                    targets: &[wgpu::ColorTargetState {
+                        format: config.format,
+                        blend: Some(wgpu::BlendState::REPLACE),
+                        write_mask: wgpu::ColorWrites::ALL,
+                    }],
+                }),
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: Texture::DEPTH_FORMAT,
+                    depth_write_enabled: false,
+                    depth_compare: wgpu::CompareFunction::Always,
+                    stencil: wgpu::StencilState::default(),
+                    bias: wgpu::DepthBiasState::default(),
+                }),
+                multisample: wgpu::MultisampleState {
+                    count: 1,
+                    mask: !0,
+                    alpha_to_coverage_enabled: false,
+                },
+                multiview: None,
+            },
+        ));
+        self.model_bind_group_layout = Some(model_bind_group_layout);
+        self.texture_bind_group_layout = Some(texture_bind_group_layout);
+        let white = image::RgbaImage::from_pixel(1, 1, image::Rgba([255; 4]));
+        self.white = Some(self.texture(context, &white, "white"));
     }
 
-    fn render(&self, rect: Rect, context: &Context) -> wgpu::CommandBuffer {
+    fn resize(&mut self, size: Vec2, context: &ContextGlobal) {
+        let config = &context.config;
+        self.depth = Some(Texture::create_depth_texture(
+            &context.device,
+            config,
+            "Viewport Depth Texture",
+        ));
+        if size.x > 0.0 && size.y > 0.0 {
+            self.camera.aspect = size.x / size.y;
+            self.controller.resize(size);
+            self.size = size;
+            self.camera_dirty = true;
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event, context: &ContextGlobal) {
+        if self.controller.handle_event(event, &mut self.camera) {
+            self.camera_dirty = true;
+        }
+        match *event {
+            Event::ModifiersChanged(state) => self.modifiers = state,
+            Event::MouseInput {
+                pos,
+                button: MouseButton::Left,
+                state: ElementState::Pressed,
+            } if !self.controller.is_dragging() => self.click(pos, &mut context.scene.borrow_mut()),
+            _ => {}
+        }
+    }
+
+    fn save_state(&self) -> String {
+        ron::to_string(&self.camera).unwrap()
+    }
+
+    fn load_state(&mut self, state: &str) -> anyhow::Result<()> {
+        self.camera = ron::from_str(state)?;
+        self.camera_dirty = true;
+        self.camera_restored = true;
+        Ok(())
+    }
+
+    fn render(&mut self, context: &Context) -> wgpu::CommandBuffer {
+        if self.controller.update(&mut self.camera, Instant::now()) {
+            self.camera_dirty = true;
+        }
+        if self.camera_dirty {
+            self.upload_camera(context.global);
+            self.camera_dirty = false;
+        }
+
         let mut encoder =
             context
                 .global
@@ -157,46 +471,153 @@ impl Panel for Viewport {
                     label: Some("Viewport Render Encoder"),
                 });
 
-        let mut buffer = DrawBuffer::new();
-        buffer.rect_uv(rect);
-        let vertex_buffer =
-            context
-                .global
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Outliner Vertex Buffer"),
-                    contents: bytemuck::cast_slice(&buffer.vertices_uv.as_slice()),
-                    usage: wgpu::BufferUsages::VERTEX,
-                });
-        let index_buffer =
-            context
-                .global
-                .device
-                .create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                    label: Some("Outliner Index Buffer"),
-                    contents: bytemuck::cast_slice(&buffer.indices_uv.as_slice()),
-                    usage: wgpu::BufferUsages::INDEX,
-                });
+        let draws = self.prepare_meshes(context.global);
+        let mut renderpass = context.begin_render_pass(
+            &mut encoder,
+            &wgpu::RenderPassDescriptor {
+                label: Some("Viewport Render Pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: &context.local.view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth.as_ref().unwrap().view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            },
+        );
+        renderpass.set_pipeline(self.background_pipeline.as_ref().unwrap());
+        renderpass.draw(0..3, 0..1);
 
-        let mut renderpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("Outliner Render Pass"),
-            color_attachments: &[wgpu::RenderPassColorAttachment {
-                view: &context.local.view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: None,
-        });
         renderpass.set_pipeline(self.pipeline.as_ref().unwrap());
-        renderpass.set_bind_group(0, self.diffuse_bind_group.as_ref().unwrap(), &[]);
-        renderpass.set_bind_group(1, self.camera_bind_group.as_ref().unwrap(), &[]);
-        renderpass.set_vertex_buffer(0, vertex_buffer.slice(..));
-        renderpass.set_index_buffer(index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-        renderpass.draw_indexed(0..buffer.indices_uv.len() as u32, 0, 0..1);
+        renderpass.set_bind_group(0, self.camera_bind_group.as_ref().unwrap(), &[]);
+        for (slot, (mesh_id, texture)) in draws.iter().enumerate() {
+            let mesh = &self.meshes[mesh_id];
+            let texture = match texture {
+                Some(id) => &self.textures[id].1,
+                None => &self.white.as_ref().unwrap().1,
+            };
+            renderpass.set_bind_group(2, texture, &[]);
+            let offset = slot as wgpu::BufferAddress * ModelUniform::STRIDE;
+            renderpass.set_bind_group(
+                1,
+                self.model_bind_group.as_ref().unwrap(),
+                &[offset as wgpu::DynamicOffset],
+            );
+            renderpass.set_vertex_buffer(0, mesh.vertices.slice(..));
+            renderpass.set_index_buffer(mesh.indices.slice(..), wgpu::IndexFormat::Uint32);
+            renderpass.draw_indexed(0..mesh.count, 0, 0..1);
+        }
         drop(renderpass);
         encoder.finish()
     }
 }
+
+/// Whether `bounds` moved by `world` overlaps `frustum`.
+fn is_visible(frustum: &Frustum, bounds: &Aabb3, world: &Mat4) -> bool {
+    frustum.intersects_aabb(&bounds.transform(world))
+}
+
+/// Nearest node whose mesh `ray` hits, checking the `bounds` of each mesh
+/// before its triangles. Meshes without bounds are skipped.
+fn pick(scene: &Scene, ray: &Ray, bounds: impl Fn(MeshId) -> Option<Aabb3>) -> Option<NodeId> {
+    let mut nearest: Option<(f64, NodeId)> = None;
+    for (id, _) in scene.walk() {
+        let (mesh, bounds) = match scene.node(id).unwrap().mesh() {
+            Some(mesh_id) => match (scene.mesh(mesh_id), bounds(mesh_id)) {
+                (Some(mesh), Some(bounds)) => (mesh, bounds),
+                _ => continue,
+            },
+            None => continue,
+        };
+        // distances along the ray are the same in object space
+        let ray = match scene.world_matrix(id).inverse() {
+            Some(inverse) => ray.transform(&inverse),
+            None => continue,
+        };
+        let limit = nearest.map_or(f64::INFINITY, |(t, _)| t);
+        if !ray.intersect_aabb(&bounds).is_some_and(|t| t < limit) {
+            continue;
+        }
+        let position = |i: u32| mesh.positions[i as usize];
+        let hit = mesh
+            .indices
+            .chunks_exact(3)
+            .filter_map(|x| ray.intersect_triangle(position(x[0]), position(x[1]), position(x[2])))
+            .fold(limit, f64::min);
+        if hit < limit {
+            nearest = Some((hit, id));
+        }
+    }
+    nearest.map(|(_, id)| id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Mesh, Transform};
+
+    #[test]
+    fn test_pick() {
+        let mut scene = Scene::new();
+        let mesh = scene.add_mesh(Mesh::cube(1.0));
+        let cube = scene.add("Cube", None);
+        scene.set_mesh(cube, Some(mesh));
+        let child = scene.add("Child", Some(cube));
+        scene.set_mesh(child, Some(mesh));
+        let mut transform = Transform::IDENTITY;
+        transform.translation.x = 3.0;
+        scene.set_transform(child, transform);
+        let behind = scene.add("Behind", None);
+        scene.set_mesh(behind, Some(mesh));
+        transform.translation = Vec3::new(0.0, 0.0, -3.0);
+        scene.set_transform(behind, transform);
+        scene.add("Empty", None);
+
+        let bounds = |id| {
+            let mesh = scene.mesh(id)?;
+            Some(Aabb3::from_points(mesh.positions.iter().copied()))
+        };
+        let down = |x: f64| Ray::new(Pnt3::new(x, 0.0, 10.0), -Vec3::Z);
+        assert_eq!(pick(&scene, &down(0.0), bounds), Some(cube));
+        assert_eq!(pick(&scene, &down(3.0), bounds), Some(child));
+        assert_eq!(pick(&scene, &down(1.5), bounds), None);
+        let up = Ray::new(Pnt3::new(0.0, 0.0, -10.0), Vec3::Z);
+        assert_eq!(pick(&scene, &up, bounds), Some(behind));
+        // meshes that were never uploaded have no bounds yet
+        assert_eq!(pick(&scene, &down(0.0), |_| None), None);
+    }
+
+    #[test]
+    fn test_is_visible() {
+        let camera = camera::Camera {
+            eye: Pnt3::new(0.0, 0.0, 5.0),
+            target: Pnt3::new(0.0, 0.0, 0.0),
+            up: Vec3::Y,
+            aspect: 1.0,
+            fovy: 45.0f64.to_radians(),
+            znear: 0.1,
+            zfar: 100.0,
+            projection: camera::Projection::Perspective,
+        };
+        let frustum = Frustum::from_matrix(&camera.build_view_projection_matrix());
+        let bounds = Aabb3::from_points(Mesh::cube(1.0).positions);
+        let at = |x, y, z| Mat4::from_translation(Vec3::new(x, y, z));
+
+        assert!(is_visible(&frustum, &bounds, &at(0.0, 0.0, 0.0)));
+        // straddling the left edge of the view
+        assert!(is_visible(&frustum, &bounds, &at(-2.5, 0.0, 0.0)));
+        // behind the eye, beside the view and past the far plane
+        assert!(!is_visible(&frustum, &bounds, &at(0.0, 0.0, 10.0)));
+        assert!(!is_visible(&frustum, &bounds, &at(10.0, 0.0, 0.0)));
+        assert!(!is_visible(&frustum, &bounds, &at(0.0, 0.0, -200.0)));
+    }
+}